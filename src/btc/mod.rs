@@ -1,3 +1,5 @@
 pub mod address;
+pub mod preview;
 pub mod raw_tx;
+pub mod uri;
 pub mod utils;
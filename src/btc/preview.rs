@@ -0,0 +1,183 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::CString;
+use std::os::raw::c_char;
+
+use anyhow::{anyhow, Result};
+use bitcoin::Network;
+
+use super::raw_tx::build_unsigned_tx;
+use super::uri::btc_to_satoshi;
+use super::utils::{get_bitcoin_network, BTC_TESTNET};
+use crate::dto::btc::{BtcInputPreview, BtcOutputPreview, BtcTxPreview, UtxoAggregator};
+use crate::dto::ecdsa::{MKPosAddressDto, MKPosDto};
+use crate::utilities::err_handling::{error_to_c_string, ErrorFFIKind};
+use crate::utilities::ffi::ffi_utils::{
+    get_addresses_derivation_map_from_raw, get_private_share_from_raw, get_str_from_c_char,
+};
+
+/// Addresses whose outputs count as change: the derived ones and the new change address.
+pub fn own_addresses(
+    addresses_derivation_map: &HashMap<String, MKPosDto>,
+    change_address_payload: &MKPosAddressDto,
+) -> HashSet<String> {
+    let mut addresses: HashSet<String> = addresses_derivation_map.keys().cloned().collect();
+    addresses.insert(change_address_payload.address.clone());
+    addresses
+}
+
+/// Summarize `tx`, whose inputs spend `spent` in order.
+pub fn preview_btc_tx(
+    tx: &bitcoin::Transaction,
+    spent: &[UtxoAggregator],
+    network: Network,
+    own_addresses: &HashSet<String>,
+) -> Result<BtcTxPreview> {
+    if tx.input.len() != spent.len() {
+        return Err(anyhow!(
+            "{} inputs but {} spent outputs",
+            tx.input.len(),
+            spent.len()
+        ));
+    }
+
+    let inputs: Vec<BtcInputPreview> = tx
+        .input
+        .iter()
+        .zip(spent)
+        .map(|(txin, utxo)| BtcInputPreview {
+            outpoint: txin.previous_output.to_string(),
+            address: utxo.address.clone(),
+            value: utxo.value as u64,
+        })
+        .collect();
+
+    let outputs: Vec<BtcOutputPreview> = tx
+        .output
+        .iter()
+        .map(|txout| {
+            let address =
+                bitcoin::Address::from_script(&txout.script_pubkey, network).map(|a| a.to_string());
+            let is_change = address
+                .as_ref()
+                .map_or(false, |a| own_addresses.contains(a));
+            BtcOutputPreview {
+                address,
+                value: txout.value,
+                is_change,
+            }
+        })
+        .collect();
+
+    let total_in: u64 = inputs.iter().map(|i| i.value).sum();
+    let total_out: u64 = outputs.iter().map(|o| o.value).sum();
+    let fee = total_in
+        .checked_sub(total_out)
+        .ok_or_else(|| anyhow!("outputs ({}) exceed inputs ({})", total_out, total_in))?;
+    let change: u64 = outputs
+        .iter()
+        .filter(|o| o.is_change)
+        .map(|o| o.value)
+        .sum();
+
+    Ok(BtcTxPreview {
+        inputs,
+        outputs,
+        sent: total_out - change,
+        change,
+        fee,
+    })
+}
+
+/// Fail unless `preview` pays exactly `amount` satoshis to `to_address` and nothing to any
+/// other foreign address.
+pub fn check_btc_payment(preview: &BtcTxPreview, to_address: &str, amount: u64) -> Result<()> {
+    let paid: u64 = preview
+        .outputs
+        .iter()
+        .filter(|o| o.address.as_deref() == Some(to_address))
+        .map(|o| o.value)
+        .sum();
+    if paid != amount || preview.sent != amount {
+        return Err(anyhow!(
+            "expected a payment of {} sat to {}, the transaction pays it {} and sends {} in total",
+            amount,
+            to_address,
+            paid,
+            preview.sent
+        ));
+    }
+    Ok(())
+}
+
+/// Preview the transaction `get_raw_btc_tx` would sign for the same arguments, without signing.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn get_btc_tx_preview(
+    c_to_address: *const c_char,
+    c_amount_btc: f64,
+    c_last_derived_pos: u32,
+    c_private_share_json: *const c_char,
+    c_addresses_derivation_map: *const c_char,
+) -> *mut c_char {
+    let to_address = match get_str_from_c_char(c_to_address, "to_address") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let private_share = match get_private_share_from_raw(c_private_share_json) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let addresses_derivation_map =
+        match get_addresses_derivation_map_from_raw(c_addresses_derivation_map) {
+            Ok(s) => s,
+            Err(e) => return error_to_c_string(e),
+        };
+
+    let preview = btc_to_satoshi(c_amount_btc)
+        .and_then(|amount_satoshi| {
+            build_unsigned_tx(
+                &to_address,
+                amount_satoshi,
+                c_last_derived_pos,
+                &private_share,
+            )
+        })
+        .and_then(|unsigned| {
+            preview_btc_tx(
+                &unsigned.transaction,
+                &unsigned.selected,
+                get_bitcoin_network(BTC_TESTNET)?,
+                &own_addresses(&addresses_derivation_map, &unsigned.change_address_payload),
+            )
+        });
+
+    let preview = match preview {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E103 {
+                msg: "btc_tx_preview".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let preview_json = match serde_json::to_string(&preview) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E102 {
+                msg: "btc_tx_preview".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(preview_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "btc_tx_preview".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
@@ -1,21 +1,28 @@
-use super::utils::{get_all_addresses, list_unspent_for_addresss, BTC_TESTNET};
+use super::preview::{check_btc_payment, own_addresses, preview_btc_tx};
+use super::uri::btc_to_satoshi;
+use super::utils::{
+    a_list_unspent_for_addresss, get_all_addresses, BLOCK_CYPHER_HOST, BTC_TESTNET,
+};
 use crate::btc::utils::{get_bitcoin_network, get_new_address, to_bitcoin_public_key};
-use crate::dto::btc::UtxoAggregator;
+use crate::dto::btc::{BlockCypherRawTx, UtxoAggregator};
 use crate::dto::ecdsa::{MKPosAddressDto, MKPosDto, PrivateShare};
-use crate::ecdsa::sign;
-use crate::utilities::derive_new_key;
+use crate::ecdsa::a_sign;
+use crate::utilities::a_requests::AsyncClientShim;
 use crate::utilities::err_handling::{error_to_c_string, ErrorFFIKind};
 use crate::utilities::ffi::ffi_utils::{
     get_addresses_derivation_map_from_raw, get_client_shim_from_raw, get_private_share_from_raw,
     get_str_from_c_char,
 };
 use crate::utilities::requests::ClientShim;
+use crate::utilities::runtime::block_on;
+use crate::utilities::{derive_new_key, to_bytes32};
 
 use anyhow::{anyhow, Result};
 use bitcoin::util::bip143::SigHashCache;
 use curv::arithmetic::traits::Converter; // Need for signing
 use curv::elliptic::curves::traits::ECPoint;
 use curv::BigInt;
+use futures::{stream, StreamExt, TryStreamExt};
 
 use std::collections::HashMap;
 use std::ffi::CString;
@@ -31,21 +38,43 @@ use serde_json;
 use hex;
 use std::str::FromStr;
 
+const UTXO_FETCH_CONCURRENCY: usize = 8;
+
 #[derive(Serialize, Deserialize)]
 pub struct BtcRawTxFFIResp {
     pub raw_tx_hex: String,
     pub change_address_payload: MKPosAddressDto,
 }
 
-pub fn create_raw_tx(
+/// Transaction paying `to_address` before signing, with the UTXOs it spends (in input order)
+/// and its freshly derived change address.
+pub struct BtcUnsignedTx {
+    pub transaction: bitcoin::Transaction,
+    pub selected: Vec<UtxoAggregator>,
+    pub change_address_payload: MKPosAddressDto,
+}
+
+pub fn build_unsigned_tx(
     to_address: &str,
-    sent_amount: f64,
-    client_shim: &ClientShim,
+    amount_satoshi: u64,
     last_derived_pos: u32,
     private_share: &PrivateShare,
-    addresses_derivation_map: &HashMap<String, MKPosDto>,
-) -> Result<Option<BtcRawTxFFIResp>> {
-    let selected = select_tx_in(last_derived_pos, private_share)?;
+) -> Result<BtcUnsignedTx> {
+    block_on(a_build_unsigned_tx(
+        to_address,
+        amount_satoshi,
+        last_derived_pos,
+        private_share,
+    ))
+}
+
+pub async fn a_build_unsigned_tx(
+    to_address: &str,
+    amount_satoshi: u64,
+    last_derived_pos: u32,
+    private_share: &PrivateShare,
+) -> Result<BtcUnsignedTx> {
+    let selected = a_select_tx_in(last_derived_pos, private_share).await?;
 
     /* Specify "vin" array aka Transaction Inputs */
     let txs_in: Vec<TxIn> = selected
@@ -64,7 +93,6 @@ pub fn create_raw_tx(
 
     /* Specify "vout" array aka Transaction Outputs */
     let relay_fees = 10_000; // Relay fees for miner
-    let amount_satoshi = (sent_amount * 100_000_000.0) as u64;
 
     let (change_pos, change_mk) = derive_new_key(private_share, last_derived_pos);
 
@@ -107,13 +135,99 @@ pub fn create_raw_tx(
         },
     ];
 
-    let mut transaction = bitcoin::Transaction {
+    let transaction = bitcoin::Transaction {
         version: 2,
         lock_time: 0,
         input: txs_in,
         output: txs_out,
     };
 
+    Ok(BtcUnsignedTx {
+        transaction,
+        selected,
+        change_address_payload,
+    })
+}
+
+pub fn create_raw_tx(
+    to_address: &str,
+    sent_amount: f64,
+    client_shim: &ClientShim,
+    last_derived_pos: u32,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<String, MKPosDto>,
+) -> Result<Option<BtcRawTxFFIResp>> {
+    block_on(a_create_raw_tx(
+        to_address,
+        sent_amount,
+        client_shim,
+        last_derived_pos,
+        private_share,
+        addresses_derivation_map,
+    ))
+}
+
+/// `create_raw_tx` for an amount in BTC, see `btc_to_satoshi`.
+pub async fn a_create_raw_tx(
+    to_address: &str,
+    sent_amount: f64,
+    client_shim: &AsyncClientShim,
+    last_derived_pos: u32,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<String, MKPosDto>,
+) -> Result<Option<BtcRawTxFFIResp>> {
+    a_create_raw_tx_sat(
+        to_address,
+        btc_to_satoshi(sent_amount)?,
+        client_shim,
+        last_derived_pos,
+        private_share,
+        addresses_derivation_map,
+    )
+    .await
+}
+
+pub fn create_raw_tx_sat(
+    to_address: &str,
+    amount_satoshi: u64,
+    client_shim: &ClientShim,
+    last_derived_pos: u32,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<String, MKPosDto>,
+) -> Result<Option<BtcRawTxFFIResp>> {
+    block_on(a_create_raw_tx_sat(
+        to_address,
+        amount_satoshi,
+        client_shim,
+        last_derived_pos,
+        private_share,
+        addresses_derivation_map,
+    ))
+}
+
+pub async fn a_create_raw_tx_sat(
+    to_address: &str,
+    amount_satoshi: u64,
+    client_shim: &AsyncClientShim,
+    last_derived_pos: u32,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<String, MKPosDto>,
+) -> Result<Option<BtcRawTxFFIResp>> {
+    let BtcUnsignedTx {
+        mut transaction,
+        selected,
+        change_address_payload,
+    } = a_build_unsigned_tx(to_address, amount_satoshi, last_derived_pos, private_share).await?;
+
+    // Check what is about to be signed pays what was asked for
+    let preview = preview_btc_tx(
+        &transaction,
+        &selected,
+        get_bitcoin_network(BTC_TESTNET)?,
+        &own_addresses(addresses_derivation_map, &change_address_payload),
+    )?;
+    check_btc_payment(&preview, to_address, amount_satoshi)?;
+
     let mut signed_transaction = transaction.clone();
 
     /* Signing transaction */
@@ -142,17 +256,18 @@ pub fn create_raw_tx(
             SigHashType::All,
         );
 
-        let signature = sign(
+        let signature = a_sign(
             client_shim,
             BigInt::from_hex(&hex::encode(&sig_hash[..])).unwrap(),
             mk,
             BigInt::from(0),
             BigInt::from(address_derivation.pos),
             &private_share.id,
-        )?;
+        )
+        .await?;
 
-        let mut v = BigInt::to_bytes(&signature.r);
-        v.extend(BigInt::to_bytes(&signature.s));
+        let mut v = to_bytes32(&signature.r).to_vec();
+        v.extend(to_bytes32(&signature.s));
 
         // Serialize the (R,S) value of ECDSA Signature
         let mut sig_vec = Signature::from_compact(&v[..])?.serialize_der().to_vec();
@@ -173,20 +288,38 @@ pub fn select_tx_in(
     last_derived_pos: u32,
     private_share: &PrivateShare,
 ) -> Result<Vec<UtxoAggregator>> {
-    // greedy selection
-    let list_unspent: Vec<UtxoAggregator> = get_all_addresses(last_derived_pos, private_share)?
-        .into_iter()
-        .filter_map(|a| list_unspent_for_addresss(a.to_string()).ok())
-        .flatten()
-        .collect();
+    block_on(a_select_tx_in(last_derived_pos, private_share))
+}
 
-    // println!("list_unspent {:#?}", list_unspent);
+pub async fn a_select_tx_in(
+    last_derived_pos: u32,
+    private_share: &PrivateShare,
+) -> Result<Vec<UtxoAggregator>> {
+    // greedy selection, failing if the UTXOs of any address cannot be listed
+    let list_unspent: Vec<Vec<UtxoAggregator>> = stream::iter(
+        get_all_addresses(last_derived_pos, private_share)?
+            .into_iter()
+            .map(|a| a_list_unspent_for_addresss(a.to_string())),
+    )
+    .buffered(UTXO_FETCH_CONCURRENCY)
+    .try_collect()
+    .await?;
+
+    Ok(list_unspent.into_iter().flatten().collect())
+}
 
-    let mut selected: Vec<UtxoAggregator> = Vec::new();
-    for unspent in list_unspent {
-        selected.push(unspent.clone());
-    }
-    Ok(selected)
+/// Broadcast a raw signed transaction, returning the BlockCypher response.
+pub async fn a_push_raw_tx(raw_tx_hex: String) -> Result<String> {
+    let raw_tx_url = BLOCK_CYPHER_HOST.to_owned() + "/txs/push";
+    let raw_tx = BlockCypherRawTx { tx: raw_tx_hex };
+    let tx_resp_str = reqwest::Client::new()
+        .post(raw_tx_url)
+        .json(&raw_tx)
+        .send()
+        .await?
+        .text()
+        .await?;
+    Ok(tx_resp_str)
 }
 
 #[no_mangle]
@@ -0,0 +1,215 @@
+use std::ffi::CString;
+use std::fmt;
+use std::os::raw::c_char;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use bitcoin::Network;
+use web3::types::U256;
+
+use super::utils::{get_bitcoin_network, to_bitcoin_address, BTC_TESTNET};
+use crate::dto::ecdsa::{MKPosAddressDto, PaymentRequestDto};
+use crate::utilities::derive_new_key;
+use crate::utilities::err_handling::{error_to_c_string, ErrorFFIKind};
+use crate::utilities::ffi::ffi_utils::{get_private_share_from_raw, get_str_from_c_char};
+use crate::utilities::uri::{format_units, parse_units, percent_encode, split_uri};
+
+const BTC_DECIMALS: u32 = 8;
+
+/// A BIP-21 `bitcoin:` payment URI, `amount` in satoshis.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct BtcPaymentUri {
+    pub address: String,
+    pub amount: Option<u64>,
+    pub label: Option<String>,
+    pub message: Option<String>,
+}
+
+impl BtcPaymentUri {
+    pub fn new(address: &str, amount: Option<u64>, label: Option<String>) -> BtcPaymentUri {
+        BtcPaymentUri {
+            address: address.to_owned(),
+            amount,
+            label,
+            message: None,
+        }
+    }
+
+    /// Parse `uri`, failing if its address is not for `network` or if it has a `req-`
+    /// parameter this wallet does not understand.
+    pub fn parse(uri: &str, network: Network) -> Result<BtcPaymentUri> {
+        let (address, params) = split_uri(uri, "bitcoin")?;
+
+        let parsed_address = bitcoin::Address::from_str(address)?;
+        if parsed_address.network != network {
+            return Err(anyhow!(
+                "address {} is for {}, the wallet is on {}",
+                address,
+                parsed_address.network,
+                network
+            ));
+        }
+
+        let mut payment_uri = BtcPaymentUri::new(&parsed_address.to_string(), None, None);
+        for (key, value) in params {
+            match key.as_str() {
+                "amount" => payment_uri.amount = Some(parse_btc_amount(&value)?),
+                "label" => payment_uri.label = Some(value),
+                "message" => payment_uri.message = Some(value),
+                k if k.starts_with("req-") => {
+                    return Err(anyhow!("unsupported required parameter: {}", k))
+                }
+                _ => {}
+            }
+        }
+        Ok(payment_uri)
+    }
+}
+
+/// Parse an amount in BTC, e.g. `0.0015`, to satoshis.
+pub fn parse_btc_amount(amount: &str) -> Result<u64> {
+    let satoshis = parse_units(amount, BTC_DECIMALS)?;
+    if satoshis > U256::from(u64::MAX) {
+        return Err(anyhow!("amount out of range: {}", amount));
+    }
+    Ok(satoshis.as_u64())
+}
+
+/// Inverse of `parse_btc_amount`.
+pub fn format_btc_amount(satoshis: u64) -> String {
+    format_units(U256::from(satoshis), BTC_DECIMALS)
+}
+
+/// Convert an amount in BTC given as a float to satoshis. Fails if it is negative, not a
+/// number or has more than 8 decimals.
+pub fn btc_to_satoshi(amount: f64) -> Result<u64> {
+    // The shortest decimal representation of the float is the amount that was meant
+    parse_btc_amount(&amount.to_string())
+}
+
+impl fmt::Display for BtcPaymentUri {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut params = Vec::new();
+        if let Some(amount) = self.amount {
+            params.push(format!("amount={}", format_btc_amount(amount)));
+        }
+        if let Some(label) = &self.label {
+            params.push(format!("label={}", percent_encode(label)));
+        }
+        if let Some(message) = &self.message {
+            params.push(format!("message={}", percent_encode(message)));
+        }
+
+        write!(f, "bitcoin:{}", self.address)?;
+        if !params.is_empty() {
+            write!(f, "?{}", params.join("&"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a `bitcoin:` URI for the wallet's network (BTC testnet).
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn parse_btc_payment_uri(c_uri: *const c_char) -> *mut c_char {
+    let uri = match get_str_from_c_char(c_uri, "uri") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let payment_uri = match get_bitcoin_network(BTC_TESTNET)
+        .and_then(|network| BtcPaymentUri::parse(&uri, network))
+    {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E103 {
+                msg: "btc_payment_uri".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let payment_uri_json = match serde_json::to_string(&payment_uri) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E102 {
+                msg: "btc_payment_uri".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(payment_uri_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "btc_payment_uri".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
+
+/// Derive a new address, like `get_btc_addrs`, and a `bitcoin:` URI requesting `c_amount_sat`
+/// (none if 0) to it. `c_label` may be empty.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn get_btc_payment_request(
+    c_private_share_json: *const c_char,
+    c_last_derived_pos: u32,
+    c_amount_sat: u64,
+    c_label: *const c_char,
+) -> *mut c_char {
+    let private_share = match get_private_share_from_raw(c_private_share_json) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let label = match get_str_from_c_char(c_label, "label") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let (pos, mk) = derive_new_key(&private_share, c_last_derived_pos);
+
+    let address = match to_bitcoin_address(BTC_TESTNET, &mk) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E103 {
+                msg: "bitcoin_address".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let uri = BtcPaymentUri::new(
+        &address.to_string(),
+        Some(c_amount_sat).filter(|a| *a > 0),
+        Some(label).filter(|l| !l.is_empty()),
+    );
+
+    let payment_request = PaymentRequestDto {
+        uri: uri.to_string(),
+        mk_pos_address: MKPosAddressDto {
+            address: address.to_string(),
+            pos,
+            mk,
+        },
+    };
+
+    let payment_request_json = match serde_json::to_string(&payment_request) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E102 {
+                msg: "payment_request".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(payment_request_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "payment_request".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
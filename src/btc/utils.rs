@@ -8,13 +8,18 @@ use kms::ecdsa::two_party::MasterKey2;
 use crate::dto::btc::{BlockCypherAddress, UtxoAggregator};
 use crate::dto::ecdsa::PrivateShare;
 use crate::utilities::derive_new_key;
+use crate::utilities::runtime::block_on;
 
 pub const BTC_TESTNET: &str = "testnet";
 pub const BLOCK_CYPHER_HOST: &str = "https://api.blockcypher.com/v1/btc/test3"; // TODO: Centralize the config constants
 
 pub fn list_unspent_for_addresss(address: String) -> Result<Vec<UtxoAggregator>> {
+    block_on(a_list_unspent_for_addresss(address))
+}
+
+pub async fn a_list_unspent_for_addresss(address: String) -> Result<Vec<UtxoAggregator>> {
     let unspent_tx_url = BLOCK_CYPHER_HOST.to_owned() + "/addrs/" + &address + "?unspentOnly=true";
-    let res = reqwest::blocking::get(unspent_tx_url)?.text()?;
+    let res = reqwest::get(unspent_tx_url).await?.text().await?;
     let address_balance_with_tx_refs: BlockCypherAddress = serde_json::from_str(res.as_str())?;
     if let Some(tx_refs) = address_balance_with_tx_refs.txrefs {
         Ok(tx_refs
@@ -42,3 +42,29 @@ pub struct BlockCypherTxRef {
 pub struct BlockCypherRawTx {
     pub tx: String,
 }
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct BtcInputPreview {
+    pub outpoint: String,
+    pub address: String,
+    pub value: u64,
+}
+
+/// `address` is unset for scripts without an address form.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct BtcOutputPreview {
+    pub address: Option<String>,
+    pub value: u64,
+    pub is_change: bool,
+}
+
+/// Summary of an unsigned BTC transaction for a confirmation screen, amounts in satoshis.
+/// `sent` excludes change outputs.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct BtcTxPreview {
+    pub inputs: Vec<BtcInputPreview>,
+    pub outputs: Vec<BtcOutputPreview>,
+    pub sent: u64,
+    pub change: u64,
+    pub fee: u64,
+}
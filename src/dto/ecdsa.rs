@@ -38,3 +38,10 @@ pub struct MKPosAddressDto {
     pub pos: u32,
     pub mk: MasterKey2,
 }
+
+/// A freshly derived address and a payment URI requesting funds to it.
+#[derive(Serialize, Deserialize)]
+pub struct PaymentRequestDto {
+    pub uri: String,
+    pub mk_pos_address: MKPosAddressDto,
+}
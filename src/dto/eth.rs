@@ -30,3 +30,115 @@ pub struct EthSendTxResp {
 pub struct EthSendTxReqBody {
     pub raw_tx: Bytes,
 }
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub enum EthTxStatus {
+    Pending,
+    Success,
+    Reverted,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct EthTxReceiptReport {
+    pub tx_hash: H256,
+    pub status: EthTxStatus,
+    pub block_number: Option<U64>,
+    pub gas_used: Option<U256>,
+    pub effective_gas_price: Option<U256>,
+    pub confirmations: u64,
+}
+
+/// An ETH transaction sent by the wallet, kept so that it can be replaced while pending.
+/// For EIP-1559 transactions `gas_price` holds the max fee per gas.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct EthTxRecord {
+    pub tx_hash: H256,
+    pub chain_id: u64,
+    pub from: Address,
+    pub to: Option<Address>,
+    pub nonce: U256,
+    pub gas: U256,
+    pub gas_price: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub value: U256,
+    pub data: Bytes,
+    pub transaction_type: Option<U64>,
+    pub access_list: AccessList,
+    #[serde(default)]
+    pub replaces: Option<H256>,
+    #[serde(default)]
+    pub replaced_by: Option<H256>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub enum EthTransferKind {
+    Native,
+    Erc20,
+}
+
+/// A native or ERC-20 transfer touching a derived address. `token` and `log_index` are only
+/// set for ERC-20 transfers.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct EthTransfer {
+    pub tx_hash: H256,
+    pub log_index: Option<U256>,
+    pub block_number: U64,
+    pub kind: EthTransferKind,
+    pub token: Option<Address>,
+    pub from: Address,
+    pub to: Address,
+    pub value: U256,
+    pub confirmations: u64,
+}
+
+/// Transfers of one chain, scanned up to `synced_block`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct EthTransferHistory {
+    pub chain_id: u64,
+    pub synced_block: Option<U64>,
+    pub transfers: Vec<EthTransfer>,
+}
+
+/// What the calldata of a transaction does, decoded from known ERC-20 selectors.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(tag = "kind")]
+pub enum EthCallPreview {
+    Transfer,
+    Erc20Transfer {
+        token: Address,
+        to: Address,
+        amount: U256,
+    },
+    Erc20TransferFrom {
+        token: Address,
+        from: Address,
+        to: Address,
+        amount: U256,
+    },
+    Erc20Approve {
+        token: Address,
+        spender: Address,
+        amount: U256,
+    },
+    ContractCall {
+        contract: Address,
+        selector: Bytes,
+    },
+    ContractCreation,
+}
+
+/// Summary of an unsigned ETH transaction for a confirmation screen. `max_fee` is the most
+/// the transaction can cost on top of `value`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct EthTxPreview {
+    pub chain_id: u64,
+    pub from: Address,
+    pub to: Option<Address>,
+    pub nonce: U256,
+    pub value: U256,
+    pub gas: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee: U256,
+    pub call: EthCallPreview,
+}
@@ -3,45 +3,38 @@ use floating_duration::TimeFormat;
 use serde_json;
 use std::time::Instant;
 
-use curv::cryptographic_primitives::twoparty::dh_key_exchange_variant_with_pok_comm::*;
-use curv::elliptic::curves::secp256_k1::GE;
-
 use kms::chain_code::two_party as chain_code;
 use kms::ecdsa::two_party::*;
-use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::*;
 use zk_paillier::zkproofs::SALT_STRING;
 
 use crate::dto::ecdsa::PrivateShare;
 use crate::utilities::err_handling::{error_to_c_string, ErrorFFIKind};
 use crate::utilities::ffi::ffi_utils::get_client_shim_from_raw;
 use crate::utilities::requests::ClientShim;
+use crate::utilities::runtime::block_on;
 
-use super::super::utilities::requests;
+use super::transport::Party1Transport;
 
 use std::ffi::CString;
 use std::os::raw::c_char;
 
-const KG_PATH_PRE: &str = "ecdsa/keygen";
-
 pub fn get_private_share(client_shim: &ClientShim) -> Result<PrivateShare> {
+    block_on(a_get_private_share(client_shim))
+}
+
+pub async fn a_get_private_share<T: Party1Transport + ?Sized>(
+    transport: &T,
+) -> Result<PrivateShare> {
     let start = Instant::now();
     // Receive ECDH key exchange message from P1
-    let (id, kg_party_one_first_message): (String, party_one::KeyGenFirstMsg) =
-        match requests::post(client_shim, &format!("{}/first", KG_PATH_PRE))? {
-            Some(s) => s,
-            None => return Err(anyhow!("keygen first message request failed")),
-        };
+    let (id, kg_party_one_first_message) = transport.keygen_first().await?;
 
     let (kg_party_two_first_message, kg_ec_key_pair_party2) = MasterKey2::key_gen_first_message();
 
     let body = &kg_party_two_first_message.d_log_proof;
 
     // Send ECDH key exchange message to P1 & receive the Paillier pubkey from P1
-    let kg_party_one_second_message: party1::KeyGenParty1Message2 =
-        match requests::postb(client_shim, &format!("{}/{}/second", KG_PATH_PRE, id), body)? {
-            Some(s) => s,
-            None => return Err(anyhow!("keygen second message request failed")),
-        };
+    let kg_party_one_second_message = transport.keygen_second(&id, body).await?;
 
     let (_, party_two_paillier) = match MasterKey2::key_gen_second_message(
         &kg_party_one_first_message,
@@ -53,13 +46,7 @@ pub fn get_private_share(client_shim: &ClientShim) -> Result<PrivateShare> {
     };
 
     // Receive non-interactive zk proof from P1
-    let cc_party_one_first_message: Party1FirstMessage = match requests::post(
-        client_shim,
-        &format!("{}/{}/chaincode/first", KG_PATH_PRE, id),
-    )? {
-        Some(s) => s,
-        None => return Err(anyhow!("chaincode first message request failed")),
-    };
+    let cc_party_one_first_message = transport.chaincode_first(&id).await?;
 
     let (cc_party_two_first_message, cc_ec_key_pair2) =
         chain_code::party2::ChainCode2::chain_code_first_message();
@@ -67,14 +54,7 @@ pub fn get_private_share(client_shim: &ClientShim) -> Result<PrivateShare> {
     let body = &cc_party_two_first_message.d_log_proof;
 
     // Initiate 2-round zk proof with P1 & receive the decom proof from P1
-    let cc_party_one_second_message: Party1SecondMessage<GE> = match requests::postb(
-        client_shim,
-        &format!("{}/{}/chaincode/second", KG_PATH_PRE, id),
-        body,
-    )? {
-        Some(s) => s,
-        None => return Err(anyhow!("chaincode second message request failed")),
-    };
+    let cc_party_one_second_message = transport.chaincode_second(&id, body).await?;
 
     let cc_party_two_second_message = chain_code::party2::ChainCode2::chain_code_second_message(
         &cc_party_one_first_message,
@@ -2,7 +2,11 @@ pub mod keygen;
 pub mod recover;
 pub mod rotate;
 pub mod sign;
+pub mod transport;
+pub mod verify;
 
-pub use keygen::get_private_share;
-pub use rotate::rotate_private_share;
-pub use sign::sign;
+pub use keygen::{a_get_private_share, get_private_share};
+pub use rotate::{a_rotate_private_share, rotate_private_share};
+pub use sign::{a_sign, sign};
+pub use transport::Party1Transport;
+pub use verify::verify_signature;
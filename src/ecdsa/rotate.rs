@@ -2,68 +2,54 @@ use crate::dto::ecdsa::PrivateShare;
 use crate::utilities::err_handling::{error_to_c_string, ErrorFFIKind};
 use crate::utilities::ffi::ffi_utils::{get_client_shim_from_raw, get_private_share_from_raw};
 use crate::utilities::requests::ClientShim;
+use crate::utilities::runtime::block_on;
 
-use super::super::utilities::requests;
-use curv::cryptographic_primitives::twoparty::coin_flip_optimal_rounds;
-use curv::elliptic::curves::secp256_k1::GE;
+use super::transport::Party1Transport;
 
 use anyhow::{anyhow, Result};
-use kms::ecdsa::two_party::*;
 use kms::rotation::two_party::party2::Rotation2;
 use zk_paillier::zkproofs::SALT_STRING;
 
 use std::ffi::CString;
 use std::os::raw::c_char;
 
-const ROT_PATH_PRE: &str = "ecdsa/rotate";
-
 pub fn rotate_private_share(
     private_share: PrivateShare,
     client_shim: &ClientShim,
+) -> Result<PrivateShare> {
+    block_on(a_rotate_private_share(private_share, client_shim))
+}
+
+pub async fn a_rotate_private_share<T: Party1Transport + ?Sized>(
+    private_share: PrivateShare,
+    transport: &T,
 ) -> Result<PrivateShare> {
     let id = &private_share.id.clone();
-    let coin_flip_party1_first_message: coin_flip_optimal_rounds::Party1FirstMessage<GE> =
-        match requests::post(client_shim, &format!("{}/{}/first", ROT_PATH_PRE, id)) {
-            Ok(s) => match s {
-                Some(s) => s,
-                None => {
-                    return Err(anyhow!("coin flip p1 first msg return None"));
-                }
-            },
-            Err(e) => {
-                return Err(anyhow!(
-                    "coin flip p1 first msg request for rotating failed:\n {}",
-                    e
-                ));
-            }
-        };
+    let coin_flip_party1_first_message = match transport.rotate_first(id).await {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(anyhow!(
+                "coin flip p1 first msg request for rotating failed:\n {}",
+                e
+            ));
+        }
+    };
 
     let coin_flip_party2_first_message =
         Rotation2::key_rotate_first_message(&coin_flip_party1_first_message);
 
     let body = &coin_flip_party2_first_message;
 
-    let (coin_flip_party1_second_message, rotation_party1_first_message): (
-        coin_flip_optimal_rounds::Party1SecondMessage<GE>,
-        party1::RotationParty1Message1,
-    ) = match requests::postb(
-        client_shim,
-        &format!("{}/{}/second", ROT_PATH_PRE, id.clone()),
-        body,
-    ) {
-        Ok(s) => match s {
-            Some(s) => s,
-            None => {
-                return Err(anyhow!("coin flip p1 second msg return None"));
+    let (coin_flip_party1_second_message, rotation_party1_first_message) =
+        match transport.rotate_second(id, body).await {
+            Ok(s) => s,
+            Err(e) => {
+                return Err(anyhow!(
+                    "coin flip p1 second msg request for rotating failed:\n {}",
+                    e
+                ));
             }
-        },
-        Err(e) => {
-            return Err(anyhow!(
-                "coin flip p1 second msg request for rotating failed:\n {}",
-                e
-            ));
-        }
-    };
+        };
 
     let random2 = Rotation2::key_rotate_second_message(
         &coin_flip_party1_second_message,
@@ -5,13 +5,15 @@ use kms::ecdsa::two_party::MasterKey2;
 use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::party_one;
 use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::party_two;
 
-use super::super::utilities::requests;
+use super::transport::Party1Transport;
+use super::verify::verify_signature;
 use crate::dto::ecdsa::SignSecondMsgRequest;
 use crate::utilities::err_handling::error_to_c_string;
 use crate::utilities::err_handling::ErrorFFIKind;
 use crate::utilities::ffi::ffi_utils::get_client_shim_from_raw;
 use crate::utilities::ffi::ffi_utils::get_str_from_c_char;
 use crate::utilities::requests::ClientShim;
+use crate::utilities::runtime::block_on;
 
 // iOS bindings
 use std::ffi::CString;
@@ -24,6 +26,17 @@ pub fn sign(
     x_pos: BigInt,
     y_pos: BigInt,
     id: &str,
+) -> Result<party_one::SignatureRecid> {
+    block_on(a_sign(client_shim, message, mk, x_pos, y_pos, id))
+}
+
+pub async fn a_sign<T: Party1Transport + ?Sized>(
+    transport: &T,
+    message: BigInt,
+    mk: &MasterKey2,
+    x_pos: BigInt,
+    y_pos: BigInt,
+    id: &str,
 ) -> Result<party_one::SignatureRecid> {
     // Choose ephemeral key
     let (eph_key_gen_first_message_party_two, eph_comm_witness, eph_ec_key_pair_party2) =
@@ -32,11 +45,7 @@ pub fn sign(
     let request: party_two::EphKeyGenFirstMsg = eph_key_gen_first_message_party_two;
 
     // Repeat Key Generation protocol for ephemeral key to obtain random point on curve that will be used in generating signature
-    let sign_party_one_first_message: party_one::EphKeyGenFirstMsg =
-        match requests::postb(client_shim, &format!("/ecdsa/sign/{}/first", id), &request)? {
-            Some(s) => s,
-            None => return Err(anyhow!("party1 sign first message request failed")),
-        };
+    let sign_party_one_first_message = transport.sign_first(id, &request).await?;
 
     // Generate encryption of derivative of the signature, called c3
     let party_two_sign_message = mk.sign_second_message(
@@ -47,23 +56,28 @@ pub fn sign(
     );
 
     // Send c3 to P1 to verify and get valid signature
-    let signature = match get_signature(
-        client_shim,
-        message,
+    let signature = match a_get_signature(
+        transport,
+        message.clone(),
         party_two_sign_message,
         x_pos,
         y_pos,
         id,
-    ) {
+    )
+    .await
+    {
         Ok(s) => s,
         Err(e) => return Err(anyhow!("ecdsa::get_signature failed failed: {}", e)),
     };
 
+    // Never trust P1's output: check it against the child public key before it is used
+    let signature = verify_signature(&message, &signature, mk)?;
+
     Ok(signature)
 }
 
-fn get_signature(
-    client_shim: &ClientShim,
+async fn a_get_signature<T: Party1Transport + ?Sized>(
+    transport: &T,
     message: BigInt,
     party_two_sign_message: party2::SignMessage,
     x_pos_child_key: BigInt,
@@ -77,13 +91,7 @@ fn get_signature(
         y_pos_child_key,
     };
 
-    let signature: party_one::SignatureRecid =
-        match requests::postb(client_shim, &format!("/ecdsa/sign/{}/second", id), &request)? {
-            Some(s) => s,
-            None => return Err(anyhow!("party1 sign second message request failed",)),
-        };
-
-    Ok(signature)
+    transport.sign_second(id, &request).await
 }
 
 #[no_mangle]
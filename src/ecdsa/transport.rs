@@ -0,0 +1,164 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+use curv::cryptographic_primitives::twoparty::coin_flip_optimal_rounds;
+use curv::cryptographic_primitives::twoparty::dh_key_exchange_variant_with_pok_comm::{
+    Party1FirstMessage, Party1SecondMessage,
+};
+use curv::elliptic::curves::secp256_k1::GE;
+use kms::ecdsa::two_party::party1;
+use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::{party_one, party_two};
+
+use crate::dto::ecdsa::SignSecondMsgRequest;
+use crate::utilities::a_requests::{self, AsyncClientShim};
+
+const KG_PATH_PRE: &str = "ecdsa/keygen";
+const SIGN_PATH_PRE: &str = "ecdsa/sign";
+const ROT_PATH_PRE: &str = "ecdsa/rotate";
+
+/// Connection to party one, one method per round of the two-party protocols. `id` is the id
+/// of the shared key, as returned by `keygen_first`.
+#[async_trait]
+pub trait Party1Transport: Send + Sync {
+    async fn keygen_first(&self) -> Result<(String, party_one::KeyGenFirstMsg)>;
+
+    async fn keygen_second(
+        &self,
+        id: &str,
+        d_log_proof: &DLogProof<GE>,
+    ) -> Result<party1::KeyGenParty1Message2>;
+
+    async fn chaincode_first(&self, id: &str) -> Result<Party1FirstMessage>;
+
+    async fn chaincode_second(
+        &self,
+        id: &str,
+        d_log_proof: &DLogProof<GE>,
+    ) -> Result<Party1SecondMessage<GE>>;
+
+    async fn sign_first(
+        &self,
+        id: &str,
+        eph_key_gen_first_message: &party_two::EphKeyGenFirstMsg,
+    ) -> Result<party_one::EphKeyGenFirstMsg>;
+
+    async fn sign_second(
+        &self,
+        id: &str,
+        request: &SignSecondMsgRequest,
+    ) -> Result<party_one::SignatureRecid>;
+
+    async fn rotate_first(
+        &self,
+        id: &str,
+    ) -> Result<coin_flip_optimal_rounds::Party1FirstMessage<GE>>;
+
+    async fn rotate_second(
+        &self,
+        id: &str,
+        coin_flip_party2_first_message: &coin_flip_optimal_rounds::Party2FirstMessage<GE>,
+    ) -> Result<(
+        coin_flip_optimal_rounds::Party1SecondMessage<GE>,
+        party1::RotationParty1Message1,
+    )>;
+
+    /// Last derivation position party one knows of for the key, used when restoring a
+    /// wallet from backup.
+    async fn recover(&self, id: &str) -> Result<u32>;
+}
+
+/// Party one over HTTP, see `a_requests`.
+#[async_trait]
+impl Party1Transport for AsyncClientShim {
+    async fn keygen_first(&self) -> Result<(String, party_one::KeyGenFirstMsg)> {
+        a_requests::a_post(self, &format!("{}/first", KG_PATH_PRE))
+            .await?
+            .ok_or_else(|| anyhow!("keygen first message request failed"))
+    }
+
+    async fn keygen_second(
+        &self,
+        id: &str,
+        d_log_proof: &DLogProof<GE>,
+    ) -> Result<party1::KeyGenParty1Message2> {
+        a_requests::a_postb(self, &format!("{}/{}/second", KG_PATH_PRE, id), d_log_proof)
+            .await?
+            .ok_or_else(|| anyhow!("keygen second message request failed"))
+    }
+
+    async fn chaincode_first(&self, id: &str) -> Result<Party1FirstMessage> {
+        a_requests::a_post(self, &format!("{}/{}/chaincode/first", KG_PATH_PRE, id))
+            .await?
+            .ok_or_else(|| anyhow!("chaincode first message request failed"))
+    }
+
+    async fn chaincode_second(
+        &self,
+        id: &str,
+        d_log_proof: &DLogProof<GE>,
+    ) -> Result<Party1SecondMessage<GE>> {
+        a_requests::a_postb(
+            self,
+            &format!("{}/{}/chaincode/second", KG_PATH_PRE, id),
+            d_log_proof,
+        )
+        .await?
+        .ok_or_else(|| anyhow!("chaincode second message request failed"))
+    }
+
+    async fn sign_first(
+        &self,
+        id: &str,
+        eph_key_gen_first_message: &party_two::EphKeyGenFirstMsg,
+    ) -> Result<party_one::EphKeyGenFirstMsg> {
+        a_requests::a_postb(
+            self,
+            &format!("/{}/{}/first", SIGN_PATH_PRE, id),
+            eph_key_gen_first_message,
+        )
+        .await?
+        .ok_or_else(|| anyhow!("party1 sign first message request failed"))
+    }
+
+    async fn sign_second(
+        &self,
+        id: &str,
+        request: &SignSecondMsgRequest,
+    ) -> Result<party_one::SignatureRecid> {
+        a_requests::a_postb(self, &format!("/{}/{}/second", SIGN_PATH_PRE, id), request)
+            .await?
+            .ok_or_else(|| anyhow!("party1 sign second message request failed"))
+    }
+
+    async fn rotate_first(
+        &self,
+        id: &str,
+    ) -> Result<coin_flip_optimal_rounds::Party1FirstMessage<GE>> {
+        a_requests::a_post(self, &format!("{}/{}/first", ROT_PATH_PRE, id))
+            .await?
+            .ok_or_else(|| anyhow!("coin flip p1 first msg return None"))
+    }
+
+    async fn rotate_second(
+        &self,
+        id: &str,
+        coin_flip_party2_first_message: &coin_flip_optimal_rounds::Party2FirstMessage<GE>,
+    ) -> Result<(
+        coin_flip_optimal_rounds::Party1SecondMessage<GE>,
+        party1::RotationParty1Message1,
+    )> {
+        a_requests::a_postb(
+            self,
+            &format!("{}/{}/second", ROT_PATH_PRE, id),
+            coin_flip_party2_first_message,
+        )
+        .await?
+        .ok_or_else(|| anyhow!("coin flip p1 second msg return None"))
+    }
+
+    async fn recover(&self, id: &str) -> Result<u32> {
+        a_requests::a_post_idempotent(self, &format!("ecdsa/{}/recover", id))
+            .await?
+            .ok_or_else(|| anyhow!("recover request failed"))
+    }
+}
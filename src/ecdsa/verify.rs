@@ -0,0 +1,70 @@
+use bitcoin::secp256k1::{Message, Secp256k1, Signature};
+use curv::elliptic::curves::secp256_k1::FE;
+use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use curv::BigInt;
+use kms::ecdsa::two_party::MasterKey2;
+use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::party_one::SignatureRecid;
+use thiserror::Error;
+use web3::signing::recover;
+
+use crate::eth::utils::pubkey_to_eth_address;
+use crate::utilities::to_bytes32;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum SignatureError {
+    #[error("signature r or s is not in [1, n - 1]")]
+    OutOfRange,
+    #[error("malformed signature: {0}")]
+    Malformed(String),
+    #[error("signature does not verify against the child public key")]
+    Invalid,
+    #[error("no recovery id recovers the child public key")]
+    Unrecoverable,
+}
+
+/// Check a signature returned by party one against the message and the child public key.
+///
+/// The returned signature is normalised to low-S and carries a recovery id recomputed
+/// locally, so callers never rely on the server for either.
+pub fn verify_signature(
+    message: &BigInt,
+    signature: &SignatureRecid,
+    mk: &MasterKey2,
+) -> Result<SignatureRecid, SignatureError> {
+    let order = FE::q();
+    let zero = BigInt::from(0);
+    if signature.r <= zero || signature.r >= order || signature.s <= zero || signature.s >= order {
+        return Err(SignatureError::OutOfRange);
+    }
+
+    let half_order = &order / &BigInt::from(2);
+    let s = if signature.s > half_order {
+        &order - &signature.s
+    } else {
+        signature.s.clone()
+    };
+
+    let mut compact = to_bytes32(&signature.r).to_vec();
+    compact.extend_from_slice(&to_bytes32(&s));
+    let hash = to_bytes32(message);
+
+    let secp = Secp256k1::verification_only();
+    let msg = Message::from_slice(&hash).map_err(|e| SignatureError::Malformed(e.to_string()))?;
+    let sig =
+        Signature::from_compact(&compact).map_err(|e| SignatureError::Malformed(e.to_string()))?;
+    let pk = mk.public.q.get_element();
+    if secp.verify(&msg, &sig, &pk).is_err() {
+        return Err(SignatureError::Invalid);
+    }
+
+    let address = pubkey_to_eth_address(mk);
+    let recid = (0..2u8)
+        .find(|id| matches!(recover(&hash, &compact, *id as i32), Ok(a) if a == address))
+        .ok_or(SignatureError::Unrecoverable)?;
+
+    Ok(SignatureRecid {
+        r: signature.r.clone(),
+        s,
+        recid,
+    })
+}
@@ -0,0 +1,40 @@
+use anyhow::Result;
+use web3::ethabi::{self, Token};
+use web3::signing::keccak256;
+use web3::types::{Address, Bytes, CallRequest};
+use web3::Web3;
+
+use crate::eth::transport::EthTransport;
+
+/// First 4 bytes of the keccak256 of a function signature, e.g. `transfer(address,uint256)`.
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = keccak256(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Calldata of a call to `signature` with ABI encoded `args`.
+pub fn encode_call(signature: &str, args: &[Token]) -> Vec<u8> {
+    let mut data = selector(signature).to_vec();
+    data.extend(ethabi::encode(args));
+    data
+}
+
+/// `eth_call` at the latest block.
+pub async fn call(
+    web3_connection: &Web3<EthTransport>,
+    to: Address,
+    data: Vec<u8>,
+) -> Result<Bytes> {
+    let result = web3_connection
+        .eth()
+        .call(
+            CallRequest {
+                to: Some(to),
+                data: Some(data.into()),
+                ..Default::default()
+            },
+            None,
+        )
+        .await?;
+    Ok(result)
+}
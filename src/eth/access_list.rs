@@ -0,0 +1,70 @@
+use anyhow::{anyhow, Result};
+use web3::types::{AccessList, BlockNumber, CallRequest, U256, U64};
+use web3::{Transport, Web3};
+
+use crate::eth::transaction::ACCESSLISTS_TX_ID;
+use crate::eth::transport::EthTransport;
+
+/// Response of `eth_createAccessList`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessListWithGasUsed {
+    pub access_list: AccessList,
+    pub gas_used: U256,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Ask the node for the storage slots `call` touches, at the pending block.
+pub async fn create_access_list(
+    web3_connection: &Web3<EthTransport>,
+    call: &CallRequest,
+) -> Result<AccessListWithGasUsed> {
+    let params = vec![
+        serde_json::to_value(call)?,
+        serde_json::to_value(BlockNumber::Pending)?,
+    ];
+    let result = web3_connection
+        .transport()
+        .execute("eth_createAccessList", params)
+        .await?;
+    let access_list: AccessListWithGasUsed = serde_json::from_value(result)?;
+    if let Some(e) = access_list.error {
+        return Err(anyhow!("eth_createAccessList failed: {}", e));
+    }
+    Ok(access_list)
+}
+
+/// Access list to attach to `call` and the gas limit to use with it, if the list makes the
+/// call cheaper than `gas_without_list`.
+pub async fn find_gas_saving_access_list(
+    web3_connection: &Web3<EthTransport>,
+    call: &CallRequest,
+    gas_without_list: U256,
+) -> Result<Option<(AccessList, U256)>> {
+    let access_list = create_access_list(web3_connection, call).await?.access_list;
+    if access_list.is_empty() {
+        return Ok(None);
+    }
+
+    let gas_with_list = web3_connection
+        .eth()
+        .estimate_gas(
+            CallRequest {
+                access_list: Some(access_list.clone()),
+                transaction_type: Some(
+                    call.transaction_type
+                        .unwrap_or(U64::from(ACCESSLISTS_TX_ID)),
+                ),
+                ..call.clone()
+            },
+            None,
+        )
+        .await?;
+
+    if gas_with_list < gas_without_list {
+        Ok(Some((access_list, gas_with_list)))
+    } else {
+        Ok(None)
+    }
+}
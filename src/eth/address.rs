@@ -1,8 +1,14 @@
 use std::{
     ffi::{CStr, CString},
+    fmt,
     os::raw::c_char,
+    str::FromStr,
 };
 
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use web3::{signing::keccak256, types::Address};
+
 use crate::{
     dto::ecdsa::{MKPosAddressDto, PrivateShare},
     utilities::{
@@ -13,6 +19,90 @@ use crate::{
 
 use super::utils::pubkey_to_eth_address;
 
+/// An Ethereum address that always prints (and serializes) with its EIP-55 checksum.
+///
+/// Parsing accepts all-lowercase or all-uppercase hex, mixed case must match the checksum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EthAddress(Address);
+
+impl EthAddress {
+    pub fn address(&self) -> Address {
+        self.0
+    }
+
+    pub fn to_checksum(&self) -> String {
+        let lower_hex = hex::encode(self.0.as_bytes());
+        let hash = keccak256(lower_hex.as_bytes());
+
+        let checksummed: String = lower_hex
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+                if nibble >= 8 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect();
+
+        format!("0x{}", checksummed)
+    }
+}
+
+impl From<Address> for EthAddress {
+    fn from(address: Address) -> Self {
+        EthAddress(address)
+    }
+}
+
+impl From<EthAddress> for Address {
+    fn from(address: EthAddress) -> Self {
+        address.0
+    }
+}
+
+impl FromStr for EthAddress {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let hex_part = s.strip_prefix("0x").unwrap_or(s);
+        if hex_part.len() != 40 || !hex_part.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow!("invalid ETH address: {}", s));
+        }
+
+        let address = EthAddress(Address::from_slice(&hex::decode(hex_part)?));
+
+        let has_lower = hex_part.chars().any(|c| c.is_ascii_lowercase());
+        let has_upper = hex_part.chars().any(|c| c.is_ascii_uppercase());
+        if has_lower && has_upper && address.to_checksum()[2..] != *hex_part {
+            return Err(anyhow!("invalid EIP-55 checksum for ETH address: {}", s));
+        }
+
+        Ok(address)
+    }
+}
+
+impl fmt::Display for EthAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_checksum())
+    }
+}
+
+impl Serialize for EthAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_checksum())
+    }
+}
+
+impl<'de> Deserialize<'de> for EthAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        EthAddress::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
 #[no_mangle]
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn get_eth_addrs(
@@ -30,7 +120,7 @@ pub extern "C" fn get_eth_addrs(
     let address = pubkey_to_eth_address(&mk);
 
     let mk_pos_address = MKPosAddressDto {
-        address: format!("{:?}", address),
+        address: EthAddress::from(address).to_string(),
         pos,
         mk,
     };
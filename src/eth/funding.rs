@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use futures::future::try_join_all;
+use web3::types::{Address, CallRequest, U256};
+use web3::Web3;
+
+use crate::dto::ecdsa::{MKPosDto, PrivateShare};
+use crate::dto::eth::{EthTxRecord, EthTxStatus};
+use crate::eth::address::EthAddress;
+use crate::eth::network::EthNetwork;
+use crate::eth::raw_tx::{a_sign_tx, build_tx, suggest_fees};
+use crate::eth::receipt::{a_wait_for_eth_tx_confirmations, DEFAULT_CONFIRMATION_TIMEOUT};
+use crate::eth::replace::CANCEL_TX_GAS;
+use crate::eth::transaction::Transaction;
+use crate::eth::transport::EthTransport;
+use crate::eth::utils::{establish_web3_connection, get_pos_mk};
+use crate::utilities::a_requests::AsyncClientShim;
+use crate::utilities::requests::ClientShim;
+use crate::utilities::runtime::block_on;
+
+/// A transfer of `value` from `from` to `to`, costing at most `max_fee`: `gas` at
+/// `gas_price` (the max fee per gas on EIP-1559 chains).
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct EthFundingStep {
+    pub from: Address,
+    pub to: Address,
+    pub value: U256,
+    pub gas: U256,
+    pub gas_price: U256,
+    pub max_fee: U256,
+}
+
+impl EthFundingStep {
+    /// `tx`, built for this step, with the planned gas and fee. A sweep moves a whole balance
+    /// but its planned fee, it could not pay more if fees went up since planning.
+    pub fn with_planned_fee(&self, tx: Transaction) -> Transaction {
+        Transaction {
+            gas: self.gas,
+            gas_price: self.gas_price,
+            max_priority_fee_per_gas: tx.max_priority_fee_per_gas.min(self.gas_price),
+            ..tx
+        }
+    }
+}
+
+/// How to pay `payment.value`: first sweep other derived addresses into `payment.from`, if
+/// needed, then send from it.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct EthFundingPlan {
+    pub sweeps: Vec<EthFundingStep>,
+    pub payment: EthFundingStep,
+}
+
+/// Plan a payment of `value` to `to` from `balances`, paying `payment_gas` for the payment
+/// and `CANCEL_TX_GAS` for each sweep at `gas_price` (the max fee per gas on EIP-1559 chains).
+///
+/// The address with the smallest balance still covering value and fee pays. Without one,
+/// and if `sweep` is set, the largest balance pays after the others, largest first, are
+/// swept into it until it can.
+pub fn plan_funding(
+    balances: &[(Address, U256)],
+    to: Address,
+    value: U256,
+    payment_gas: U256,
+    gas_price: U256,
+    sweep: bool,
+) -> Result<EthFundingPlan> {
+    let payment_fee = payment_gas * gas_price;
+    let sweep_fee = U256::from(CANCEL_TX_GAS) * gas_price;
+    let needed = value + payment_fee;
+
+    let mut balances = balances.to_vec();
+    balances.sort_by_key(|(_, balance)| *balance);
+
+    let payment = |from: Address| EthFundingStep {
+        from,
+        to,
+        value,
+        gas: payment_gas,
+        gas_price,
+        max_fee: payment_fee,
+    };
+
+    if let Some((from, _)) = balances.iter().find(|(_, balance)| *balance >= needed) {
+        return Ok(EthFundingPlan {
+            sweeps: vec![],
+            payment: payment(*from),
+        });
+    }
+
+    let total: U256 = balances
+        .iter()
+        .map(|(_, balance)| *balance)
+        .fold(U256::zero(), |a, b| a + b);
+    let (sender, mut funded) = match balances.pop() {
+        Some(largest) if sweep => largest,
+        _ => {
+            return Err(anyhow!(
+                "no single address holds {} (value and fee), the wallet holds {} in total",
+                needed,
+                total
+            ))
+        }
+    };
+
+    let mut sweeps = Vec::new();
+    for (from, balance) in balances.into_iter().rev() {
+        if funded >= needed {
+            break;
+        }
+        if balance <= sweep_fee {
+            continue;
+        }
+        let swept = balance - sweep_fee;
+        funded += swept;
+        sweeps.push(EthFundingStep {
+            from,
+            to: sender,
+            value: swept,
+            gas: U256::from(CANCEL_TX_GAS),
+            gas_price,
+            max_fee: sweep_fee,
+        });
+    }
+
+    if funded < needed {
+        return Err(anyhow!(
+            "the wallet cannot cover {} (value and fees), {} at most can be gathered",
+            needed,
+            funded
+        ));
+    }
+    Ok(EthFundingPlan {
+        sweeps,
+        payment: payment(sender),
+    })
+}
+
+async fn get_balances(
+    web3_connection: &Web3<EthTransport>,
+    addresses: &[Address],
+) -> Result<Vec<(Address, U256)>> {
+    let balances = try_join_all(
+        addresses
+            .iter()
+            .map(|a| web3_connection.eth().balance(*a, None)),
+    )
+    .await?;
+    Ok(addresses.iter().copied().zip(balances).collect())
+}
+
+/// Plan a payment on `network` from the current balances of `addresses`.
+pub fn plan_eth_funding(
+    network: &EthNetwork,
+    addresses: &[Address],
+    to: Address,
+    value: U256,
+    sweep: bool,
+) -> Result<EthFundingPlan> {
+    block_on(a_plan_eth_funding(network, addresses, to, value, sweep))
+}
+
+pub async fn a_plan_eth_funding(
+    network: &EthNetwork,
+    addresses: &[Address],
+    to: Address,
+    value: U256,
+    sweep: bool,
+) -> Result<EthFundingPlan> {
+    let web3_connection = establish_web3_connection(network.rpc_url()?).await?;
+    network.check_chain_id(&web3_connection).await?;
+    let balances = get_balances(&web3_connection, addresses).await?;
+    let (_, gas_price, _) = suggest_fees(&web3_connection, network).await?;
+
+    // The sender is picked by fee, which needs a first estimate without it
+    let payment_gas = estimate_payment_gas(&web3_connection, None, to, None).await?;
+    let plan = plan_funding(&balances, to, value, payment_gas, gas_price, sweep)?;
+
+    // Payable recipients may need more gas when paid. Until its sweeps land the sender may
+    // hold less than `value`, and nodes refuse to estimate a transfer it cannot pay.
+    let sender = plan.payment.from;
+    let sender_balance = balances
+        .iter()
+        .find(|(address, _)| *address == sender)
+        .map(|(_, balance)| *balance)
+        .unwrap_or_default();
+    let sender_gas = estimate_payment_gas(
+        &web3_connection,
+        Some(sender),
+        to,
+        Some(value.min(sender_balance)),
+    )
+    .await?;
+    if sender_gas <= payment_gas {
+        return Ok(plan);
+    }
+    plan_funding(&balances, to, value, sender_gas, gas_price, sweep)
+}
+
+async fn estimate_payment_gas(
+    web3_connection: &Web3<EthTransport>,
+    from: Option<Address>,
+    to: Address,
+    value: Option<U256>,
+) -> Result<U256> {
+    let gas = web3_connection
+        .eth()
+        .estimate_gas(
+            CallRequest {
+                from,
+                to: Some(to),
+                value,
+                ..Default::default()
+            },
+            None,
+        )
+        .await?;
+    Ok(gas)
+}
+
+/// Send `step` with its planned gas and fee.
+async fn a_send_step(
+    step: &EthFundingStep,
+    network: &EthNetwork,
+    client_shim: &AsyncClientShim,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<EthAddress, MKPosDto>,
+) -> Result<EthTxRecord> {
+    let pos_mk = get_pos_mk(
+        &EthAddress::from(step.from).to_string(),
+        addresses_derivation_map,
+    )?;
+
+    let web3_connection = establish_web3_connection(network.rpc_url()?).await?;
+    network.check_chain_id(&web3_connection).await?;
+    let tx = build_tx(
+        &web3_connection,
+        network,
+        step.from,
+        step.to,
+        step.value,
+        vec![],
+    )
+    .await?;
+
+    let (raw_tx, record) = a_sign_tx(
+        step.with_planned_fee(tx),
+        network.chain_id,
+        pos_mk,
+        client_shim,
+        private_share,
+    )
+    .await?;
+    web3_connection.eth().send_raw_transaction(raw_tx).await?;
+    Ok(record)
+}
+
+/// Send the sweeps of `plan`, wait for them to be mined, then send the payment. Every
+/// transaction pays the gas and fee it was planned with.
+pub fn execute_funding_plan(
+    plan: &EthFundingPlan,
+    network: &EthNetwork,
+    client_shim: &ClientShim,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<EthAddress, MKPosDto>,
+) -> Result<Vec<EthTxRecord>> {
+    block_on(a_execute_funding_plan(
+        plan,
+        network,
+        client_shim,
+        private_share,
+        addresses_derivation_map,
+    ))
+}
+
+pub async fn a_execute_funding_plan(
+    plan: &EthFundingPlan,
+    network: &EthNetwork,
+    client_shim: &AsyncClientShim,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<EthAddress, MKPosDto>,
+) -> Result<Vec<EthTxRecord>> {
+    let mut records = Vec::new();
+    for step in &plan.sweeps {
+        records.push(
+            a_send_step(
+                step,
+                network,
+                client_shim,
+                private_share,
+                addresses_derivation_map,
+            )
+            .await?,
+        );
+    }
+
+    // Nodes check balances against the latest block, the payment has to wait for the sweeps
+    for sweep in &records {
+        let report = a_wait_for_eth_tx_confirmations(
+            network,
+            sweep.tx_hash,
+            1,
+            DEFAULT_CONFIRMATION_TIMEOUT,
+        )
+        .await?;
+        if report.status == EthTxStatus::Reverted {
+            return Err(anyhow!("sweep {:?} reverted", sweep.tx_hash));
+        }
+    }
+
+    records.push(
+        a_send_step(
+            &plan.payment,
+            network,
+            client_shim,
+            private_share,
+            addresses_derivation_map,
+        )
+        .await?,
+    );
+    Ok(records)
+}
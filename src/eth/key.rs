@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use curv::arithmetic::traits::Converter;
+use curv::BigInt;
+use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::party_one::SignatureRecid;
+use tokio::runtime::{Handle, RuntimeFlavor};
+use web3::signing::{Key, Signature, SigningError};
+use web3::types::{Address, H256};
+
+use crate::dto::ecdsa::{MKPosDto, PrivateShare};
+use crate::ecdsa::sign::sign;
+use crate::eth::address::EthAddress;
+use crate::eth::utils::{get_pos_mk, pubkey_to_eth_address};
+use crate::utilities::requests::ClientShim;
+use crate::utilities::to_bytes32;
+
+/// A derived TSS key usable wherever web3 takes a `signing::Key`, e.g.
+/// `accounts().sign_transaction`. Every signature runs the two-party protocol with the server.
+#[derive(Clone, Copy)]
+pub struct TssKey<'a> {
+    client_shim: &'a ClientShim,
+    private_share: &'a PrivateShare,
+    pos_mk: &'a MKPosDto,
+}
+
+impl<'a> TssKey<'a> {
+    pub fn new(
+        client_shim: &'a ClientShim,
+        private_share: &'a PrivateShare,
+        pos_mk: &'a MKPosDto,
+    ) -> TssKey<'a> {
+        TssKey {
+            client_shim,
+            private_share,
+            pos_mk,
+        }
+    }
+
+    /// Key of the derived address `address`.
+    pub fn for_address(
+        address: &str,
+        client_shim: &'a ClientShim,
+        private_share: &'a PrivateShare,
+        addresses_derivation_map: &'a HashMap<EthAddress, MKPosDto>,
+    ) -> Result<TssKey<'a>> {
+        let pos_mk = get_pos_mk(address, addresses_derivation_map)?;
+        Ok(TssKey::new(client_shim, private_share, pos_mk))
+    }
+
+    fn sign_hash(&self, message: &[u8]) -> Result<SignatureRecid, SigningError> {
+        if message.len() != 32 {
+            return Err(SigningError::InvalidMessage);
+        }
+
+        let signature = run_blocking(|| {
+            sign(
+                self.client_shim,
+                BigInt::from_hex(&hex::encode(message)).unwrap(),
+                &self.pos_mk.mk,
+                BigInt::from(0),
+                BigInt::from(self.pos_mk.pos),
+                &self.private_share.id,
+            )
+        });
+
+        // `SigningError` cannot carry the cause, keep it in the logs
+        signature.map_err(|e| {
+            error!("two-party signing failed: {}", e);
+            SigningError::InvalidMessage
+        })
+    }
+}
+
+/// The signing protocol uses blocking HTTP, which must not run on an async worker thread as
+/// is. web3 calls `Key` from async code, so move off the worker first.
+fn run_blocking<T, F>(f: F) -> T
+where
+    F: FnOnce() -> T + Send,
+    T: Send,
+{
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        Ok(_) => std::thread::scope(|scope| {
+            scope
+                .spawn(f)
+                .join()
+                .expect("two-party signing thread panicked")
+        }),
+        Err(_) => f(),
+    }
+}
+
+impl Key for TssKey<'_> {
+    /// v is EIP-155 encoded when `chain_id` is given, 27/28 otherwise.
+    fn sign(&self, message: &[u8], chain_id: Option<u64>) -> Result<Signature, SigningError> {
+        let signature = self.sign_hash(message)?;
+        let recovery_id = signature.recid as u64;
+        let v = match chain_id {
+            Some(chain_id) => recovery_id + 35 + chain_id * 2,
+            None => recovery_id + 27,
+        };
+
+        Ok(Signature {
+            v,
+            r: H256::from(to_bytes32(&signature.r)),
+            s: H256::from(to_bytes32(&signature.s)),
+        })
+    }
+
+    /// v is the bare recovery id, as typed transactions expect.
+    fn sign_message(&self, message: &[u8]) -> Result<Signature, SigningError> {
+        let signature = self.sign_hash(message)?;
+
+        Ok(Signature {
+            v: signature.recid as u64,
+            r: H256::from(to_bytes32(&signature.r)),
+            s: H256::from(to_bytes32(&signature.s)),
+        })
+    }
+
+    fn address(&self) -> Address {
+        pubkey_to_eth_address(&self.pos_mk.mk)
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use curv::arithmetic::traits::Converter;
+use curv::BigInt;
+use web3::signing::{keccak256, recover};
+use web3::types::{Address, Bytes};
+
+use crate::dto::ecdsa::{MKPosDto, PrivateShare};
+use crate::ecdsa::sign::sign;
+use crate::eth::address::EthAddress;
+use crate::eth::typed_data::TypedData;
+use crate::eth::utils::{get_pos_mk, pubkey_to_eth_address};
+use crate::utilities::err_handling::{error_to_c_string, ErrorFFIKind};
+use crate::utilities::ffi::ffi_utils::{
+    get_addresses_derivation_map_from_raw, get_client_shim_from_raw, get_private_share_from_raw,
+    get_str_from_c_char,
+};
+use crate::utilities::requests::ClientShim;
+use crate::utilities::to_bytes32;
+
+pub const PERSONAL_MESSAGE_PREFIX: &str = "\x19Ethereum Signed Message:\n";
+
+/// EIP-191 (version 0x45) hash of a `personal_sign` message.
+pub fn hash_personal_message(message: &[u8]) -> [u8; 32] {
+    let mut eth_message = format!("{}{}", PERSONAL_MESSAGE_PREFIX, message.len()).into_bytes();
+    eth_message.extend_from_slice(message);
+    keccak256(&eth_message)
+}
+
+/// Sign `message` with `personal_sign` semantics, returning the 65 bytes r ‖ s ‖ v (v = 27/28).
+pub fn personal_sign(
+    from: &str,
+    message: &[u8],
+    client_shim: &ClientShim,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<EthAddress, MKPosDto>,
+) -> Result<Bytes> {
+    let pos_mk = get_pos_mk(from, addresses_derivation_map)?;
+    sign_hash(
+        hash_personal_message(message),
+        pos_mk,
+        client_shim,
+        private_share,
+    )
+}
+
+/// Sign EIP-712 typed data with `eth_signTypedData_v4` semantics.
+pub fn sign_typed_data(
+    from: &str,
+    typed_data_json: &str,
+    client_shim: &ClientShim,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<EthAddress, MKPosDto>,
+) -> Result<Bytes> {
+    let pos_mk = get_pos_mk(from, addresses_derivation_map)?;
+    let typed_data = TypedData::from_json(typed_data_json)?;
+    sign_hash(
+        typed_data.signing_hash()?,
+        pos_mk,
+        client_shim,
+        private_share,
+    )
+}
+
+/// Run the two-party signing protocol over a 32 bytes hash and check the result recovers
+/// to the derived address.
+pub fn sign_hash(
+    hash: [u8; 32],
+    pos_mk: &MKPosDto,
+    client_shim: &ClientShim,
+    private_share: &PrivateShare,
+) -> Result<Bytes> {
+    let sig = sign(
+        client_shim,
+        BigInt::from_hex(&hex::encode(&hash[..])).unwrap(),
+        &pos_mk.mk,
+        BigInt::from(0),
+        BigInt::from(pos_mk.pos),
+        &private_share.id,
+    )?;
+
+    let mut signature = to_bytes32(&sig.r).to_vec();
+    signature.extend_from_slice(&to_bytes32(&sig.s));
+    signature.push(sig.recid + 27);
+
+    let address = pubkey_to_eth_address(&pos_mk.mk);
+    if recover_signer(&hash, &signature)? != address {
+        return Err(anyhow!("signature does not recover to {:?}", address));
+    }
+
+    Ok(signature.into())
+}
+
+/// Recover the signer of a 65 bytes r ‖ s ‖ v signature, v being either 0/1 or 27/28.
+pub fn recover_signer(hash: &[u8; 32], signature: &[u8]) -> Result<Address> {
+    if signature.len() != 65 {
+        return Err(anyhow!("signature must be 65 bytes"));
+    }
+    let recovery_id = match signature[64] {
+        v @ 0..=1 => v as i32,
+        v @ 27..=28 => (v - 27) as i32,
+        v => return Err(anyhow!("invalid signature v: {}", v)),
+    };
+    match recover(hash, &signature[..64], recovery_id) {
+        Ok(address) => Ok(address),
+        Err(e) => Err(anyhow!("signature recovery failed: {:?}", e)),
+    }
+}
+
+pub fn verify_personal_sign(address: Address, message: &[u8], signature: &[u8]) -> Result<bool> {
+    Ok(recover_signer(&hash_personal_message(message), signature)? == address)
+}
+
+pub fn verify_typed_data(
+    address: Address,
+    typed_data_json: &str,
+    signature: &[u8],
+) -> Result<bool> {
+    let typed_data = TypedData::from_json(typed_data_json)?;
+    Ok(recover_signer(&typed_data.signing_hash()?, signature)? == address)
+}
+
+/// Messages given as 0x-prefixed hex are signed as raw bytes, like `personal_sign` does.
+fn message_to_bytes(message: &str) -> Vec<u8> {
+    match message.strip_prefix("0x").map(hex::decode) {
+        Some(Ok(bytes)) => bytes,
+        _ => message.as_bytes().to_vec(),
+    }
+}
+
+pub fn signature_to_c_string(signature: Result<Bytes>) -> *mut c_char {
+    let signature = match signature {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E103 {
+                msg: "signature".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let signature_json = match serde_json::to_string(&signature) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E102 {
+                msg: "signature".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(signature_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "signature".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
+
+pub fn verification_to_c_string(verified: Result<bool>) -> *mut c_char {
+    let result = match verified {
+        Ok(true) => "success",
+        Ok(false) => "failed",
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E103 {
+                msg: "verify_signature".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(result) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "verify_signature".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn eth_personal_sign(
+    c_endpoint: *const c_char,
+    c_auth_token: *const c_char,
+    c_user_id: *const c_char,
+    c_from_address: *const c_char,
+    c_message: *const c_char,
+    c_private_share_json: *const c_char,
+    c_addresses_derivation_map: *const c_char,
+) -> *mut c_char {
+    let from_address = match get_str_from_c_char(c_from_address, "from_address") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let message = match get_str_from_c_char(c_message, "message") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let client_shim = match get_client_shim_from_raw(c_endpoint, c_auth_token, c_user_id) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let private_share = match get_private_share_from_raw(c_private_share_json) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let addresses_derivation_map =
+        match get_addresses_derivation_map_from_raw(c_addresses_derivation_map) {
+            Ok(s) => s,
+            Err(e) => return error_to_c_string(e),
+        };
+
+    signature_to_c_string(personal_sign(
+        &from_address,
+        &message_to_bytes(&message),
+        &client_shim,
+        &private_share,
+        &addresses_derivation_map,
+    ))
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn eth_sign_typed_data(
+    c_endpoint: *const c_char,
+    c_auth_token: *const c_char,
+    c_user_id: *const c_char,
+    c_from_address: *const c_char,
+    c_typed_data_json: *const c_char,
+    c_private_share_json: *const c_char,
+    c_addresses_derivation_map: *const c_char,
+) -> *mut c_char {
+    let from_address = match get_str_from_c_char(c_from_address, "from_address") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let typed_data_json = match get_str_from_c_char(c_typed_data_json, "typed_data_json") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let client_shim = match get_client_shim_from_raw(c_endpoint, c_auth_token, c_user_id) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let private_share = match get_private_share_from_raw(c_private_share_json) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let addresses_derivation_map =
+        match get_addresses_derivation_map_from_raw(c_addresses_derivation_map) {
+            Ok(s) => s,
+            Err(e) => return error_to_c_string(e),
+        };
+
+    signature_to_c_string(sign_typed_data(
+        &from_address,
+        &typed_data_json,
+        &client_shim,
+        &private_share,
+        &addresses_derivation_map,
+    ))
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn eth_verify_personal_sign(
+    c_address: *const c_char,
+    c_message: *const c_char,
+    c_signature: *const c_char,
+) -> *mut c_char {
+    let address = match get_str_from_c_char(c_address, "address") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let message = match get_str_from_c_char(c_message, "message") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let signature = match get_str_from_c_char(c_signature, "signature") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    verification_to_c_string(parse_address_and_signature(&address, &signature).and_then(
+        |(address, signature)| {
+            verify_personal_sign(address, &message_to_bytes(&message), &signature)
+        },
+    ))
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn eth_verify_typed_data(
+    c_address: *const c_char,
+    c_typed_data_json: *const c_char,
+    c_signature: *const c_char,
+) -> *mut c_char {
+    let address = match get_str_from_c_char(c_address, "address") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let typed_data_json = match get_str_from_c_char(c_typed_data_json, "typed_data_json") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let signature = match get_str_from_c_char(c_signature, "signature") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    verification_to_c_string(
+        parse_address_and_signature(&address, &signature).and_then(|(address, signature)| {
+            verify_typed_data(address, &typed_data_json, &signature)
+        }),
+    )
+}
+
+fn parse_address_and_signature(address: &str, signature: &str) -> Result<(Address, Vec<u8>)> {
+    let address = Address::from_str(address)?;
+    let signature = hex::decode(signature.trim_start_matches("0x"))?;
+    Ok((address, signature))
+}
@@ -1,4 +1,22 @@
+pub mod abi;
+pub mod access_list;
 pub mod address;
+pub mod funding;
+pub mod key;
+pub mod message;
+pub mod network;
+pub mod nft;
+pub mod permit;
+pub mod preview;
 pub mod raw_tx;
+pub mod receipt;
+pub mod replace;
+pub mod safe;
+pub mod siwe;
+pub mod sync;
 pub mod transaction;
+pub mod transport;
+pub mod typed_data;
+pub mod uri;
+pub mod user_operation;
 pub mod utils;
@@ -0,0 +1,226 @@
+use anyhow::{anyhow, Result};
+use web3::types::U256;
+use web3::Web3;
+
+use super::transport::EthTransport;
+use super::utils::establish_web3_connection;
+use crate::utilities::runtime::block_on;
+use crate::utilities::uri::format_units;
+
+pub const MAINNET: &str = "mainnet";
+pub const SEPOLIA: &str = "sepolia";
+pub const HOLESKY: &str = "holesky";
+pub const DEV: &str = "dev";
+pub const POLYGON: &str = "polygon";
+pub const ARBITRUM: &str = "arbitrum";
+pub const OPTIMISM: &str = "optimism";
+pub const BSC: &str = "bsc";
+
+pub const EVM_NETWORKS: [&str; 8] = [
+    MAINNET, SEPOLIA, HOLESKY, POLYGON, ARBITRUM, OPTIMISM, BSC, DEV,
+];
+
+/// Chain id used by local dev nodes (anvil, hardhat).
+pub const DEV_CHAIN_ID: u64 = 31337;
+
+/// An EVM chain the two-party key can hold funds on. The same derived addresses are valid on
+/// every chain, only the chain id and the endpoints differ.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EthNetwork {
+    pub name: String,
+    pub chain_id: u64,
+    pub rpc_urls: Vec<String>,
+    #[serde(default = "default_currency_symbol")]
+    pub currency_symbol: String,
+    #[serde(default = "default_decimals")]
+    pub decimals: u8,
+    #[serde(default = "default_eip1559")]
+    pub eip1559: bool,
+}
+
+fn default_currency_symbol() -> String {
+    "ETH".to_owned()
+}
+
+fn default_decimals() -> u8 {
+    18
+}
+
+fn default_eip1559() -> bool {
+    true
+}
+
+impl EthNetwork {
+    pub fn new(name: &str, chain_id: u64, rpc_urls: &[&str]) -> EthNetwork {
+        EthNetwork {
+            name: name.to_owned(),
+            chain_id,
+            rpc_urls: rpc_urls.iter().map(|u| u.to_string()).collect(),
+            currency_symbol: default_currency_symbol(),
+            decimals: default_decimals(),
+            eip1559: default_eip1559(),
+        }
+    }
+
+    pub fn with_native_currency(mut self, symbol: &str, decimals: u8) -> EthNetwork {
+        self.currency_symbol = symbol.to_owned();
+        self.decimals = decimals;
+        self
+    }
+
+    /// For chains whose nodes do not accept EIP-1559 transactions.
+    pub fn without_eip1559(mut self) -> EthNetwork {
+        self.eip1559 = false;
+        self
+    }
+
+    /// Convert an amount in the smallest unit of the native currency to the currency itself.
+    /// Any amount converts, large ones losing precision.
+    pub fn to_native(&self, amount: U256) -> f64 {
+        format_units(amount, self.decimals as u32)
+            .parse()
+            .unwrap_or(f64::INFINITY)
+    }
+
+    /// Fails for negative, non-finite and too large amounts rather than saturating.
+    pub fn from_native(&self, amount: f64) -> Result<U256> {
+        let units = (amount * 10f64.powi(self.decimals as i32)).round();
+        if !units.is_finite() || units < 0.0 || units >= u128::MAX as f64 {
+            return Err(anyhow!(
+                "invalid {} amount: {}",
+                self.currency_symbol,
+                amount
+            ));
+        }
+        Ok(U256::from(units as u128))
+    }
+
+    /// Use `rpc_url` in front of the default endpoints, e.g. for a keyed provider.
+    pub fn with_rpc_url(mut self, rpc_url: &str) -> EthNetwork {
+        self.rpc_urls.insert(0, rpc_url.to_owned());
+        self
+    }
+
+    /// Preferred endpoint, whatever its transport.
+    pub fn rpc_url(&self) -> Result<&str> {
+        match self.rpc_urls.first() {
+            Some(url) => Ok(url),
+            None => Err(anyhow!("no RPC endpoint for network {}", self.name)),
+        }
+    }
+
+    pub fn ws_url(&self) -> Result<&str> {
+        match self
+            .rpc_urls
+            .iter()
+            .find(|u| u.starts_with("ws://") || u.starts_with("wss://"))
+        {
+            Some(url) => Ok(url),
+            None => Err(anyhow!("no WebSocket endpoint for network {}", self.name)),
+        }
+    }
+
+    /// Fail unless the node behind `web3_connection` serves this network.
+    pub async fn check_chain_id(&self, web3_connection: &Web3<EthTransport>) -> Result<()> {
+        let node_chain_id = web3_connection.eth().chain_id().await?;
+        if node_chain_id.as_u64() != self.chain_id {
+            return Err(anyhow!(
+                "node chain id {} does not match network {} (chain id {})",
+                node_chain_id,
+                self.name,
+                self.chain_id
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Look up a network by name. `testnet` is kept as an alias of Sepolia for existing wallets.
+/// Other EVM chains can be given to the FFI as an `EthNetwork` JSON object.
+pub fn get_eth_network(name: &str) -> Result<EthNetwork> {
+    let network = match name.to_lowercase().as_str() {
+        MAINNET => EthNetwork::new(
+            MAINNET,
+            1,
+            &[
+                "wss://ethereum-rpc.publicnode.com",
+                "https://ethereum-rpc.publicnode.com",
+            ],
+        ),
+        SEPOLIA | "testnet" => EthNetwork::new(
+            SEPOLIA,
+            11155111,
+            &[
+                "wss://ethereum-sepolia-rpc.publicnode.com",
+                "https://ethereum-sepolia-rpc.publicnode.com",
+            ],
+        ),
+        HOLESKY => EthNetwork::new(
+            HOLESKY,
+            17000,
+            &[
+                "wss://ethereum-holesky-rpc.publicnode.com",
+                "https://ethereum-holesky-rpc.publicnode.com",
+            ],
+        ),
+        POLYGON | "matic" => EthNetwork::new(
+            POLYGON,
+            137,
+            &[
+                "wss://polygon-bor-rpc.publicnode.com",
+                "https://polygon-bor-rpc.publicnode.com",
+            ],
+        )
+        .with_native_currency("POL", 18),
+        ARBITRUM => EthNetwork::new(
+            ARBITRUM,
+            42161,
+            &[
+                "wss://arbitrum-one-rpc.publicnode.com",
+                "https://arbitrum-one-rpc.publicnode.com",
+            ],
+        ),
+        OPTIMISM => EthNetwork::new(
+            OPTIMISM,
+            10,
+            &[
+                "wss://optimism-rpc.publicnode.com",
+                "https://optimism-rpc.publicnode.com",
+            ],
+        ),
+        BSC => EthNetwork::new(
+            BSC,
+            56,
+            &[
+                "wss://bsc-rpc.publicnode.com",
+                "https://bsc-rpc.publicnode.com",
+            ],
+        )
+        .with_native_currency("BNB", 18)
+        .without_eip1559(),
+        DEV | "local" => EthNetwork::new(
+            DEV,
+            DEV_CHAIN_ID,
+            &["ws://127.0.0.1:8545", "http://127.0.0.1:8545"],
+        ),
+        _ => return Err(anyhow!("unknown Ethereum network: {}", name)),
+    };
+    Ok(network)
+}
+
+pub fn get_eth_network_by_chain_id(chain_id: u64) -> Result<EthNetwork> {
+    for name in EVM_NETWORKS {
+        let network = get_eth_network(name)?;
+        if network.chain_id == chain_id {
+            return Ok(network);
+        }
+    }
+    Err(anyhow!("unknown EVM chain id: {}", chain_id))
+}
+
+pub fn verify_node_chain_id(network: &EthNetwork) -> Result<()> {
+    block_on(async {
+        let web3_connection = establish_web3_connection(network.rpc_url()?).await?;
+        network.check_chain_id(&web3_connection).await
+    })
+}
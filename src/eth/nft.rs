@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::os::raw::c_char;
+
+use anyhow::{anyhow, Result};
+use web3::ethabi::Token;
+use web3::types::{Address, U256};
+use web3::Web3;
+
+use crate::dto::ecdsa::{MKPosDto, PrivateShare};
+use crate::dto::eth::EthTxRecord;
+use crate::eth::abi::{call, encode_call};
+use crate::eth::address::EthAddress;
+use crate::eth::network::EthNetwork;
+use crate::eth::raw_tx::sign_and_send_on_chain;
+use crate::eth::transport::EthTransport;
+use crate::eth::utils::{establish_web3_connection, get_pos_mk, pubkey_to_eth_address};
+use crate::utilities::err_handling::{error_to_c_string, ErrorFFIKind};
+use crate::utilities::ffi::ffi_utils::{
+    get_addresses_derivation_map_from_raw, get_client_shim_from_raw, get_eth_network_from_raw,
+    get_private_share_from_raw, get_str_from_c_char,
+};
+use crate::utilities::requests::ClientShim;
+use crate::utilities::runtime::block_on;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum NftStandard {
+    Erc721,
+    Erc1155,
+}
+
+/// Transfer of `amount` tokens `token_id` of the collection `contract` to `to`. `amount` is
+/// ignored for ERC-721, and must not be zero for ERC-1155.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NftTransfer {
+    pub standard: NftStandard,
+    pub contract: Address,
+    pub to: Address,
+    pub token_id: U256,
+    #[serde(default)]
+    pub amount: U256,
+}
+
+impl NftTransfer {
+    pub fn erc721(contract: Address, to: Address, token_id: U256) -> Self {
+        NftTransfer {
+            standard: NftStandard::Erc721,
+            contract,
+            to,
+            token_id,
+            amount: U256::one(),
+        }
+    }
+
+    pub fn erc1155(contract: Address, to: Address, token_id: U256, amount: U256) -> Self {
+        NftTransfer {
+            standard: NftStandard::Erc1155,
+            contract,
+            to,
+            token_id,
+            amount,
+        }
+    }
+
+    /// Fail for an ERC-1155 transfer of nothing, e.g. given as JSON without `amount`.
+    pub fn check_amount(&self) -> Result<()> {
+        if self.standard == NftStandard::Erc1155 && self.amount.is_zero() {
+            return Err(anyhow!(
+                "ERC-1155 transfer of token {} needs a non-zero amount",
+                self.token_id
+            ));
+        }
+        Ok(())
+    }
+
+    /// `safeTransferFrom` calldata moving the token out of `from`.
+    pub fn calldata(&self, from: Address) -> Vec<u8> {
+        match self.standard {
+            NftStandard::Erc721 => encode_call(
+                "safeTransferFrom(address,address,uint256)",
+                &[
+                    Token::Address(from),
+                    Token::Address(self.to),
+                    Token::Uint(self.token_id),
+                ],
+            ),
+            NftStandard::Erc1155 => encode_call(
+                "safeTransferFrom(address,address,uint256,uint256,bytes)",
+                &[
+                    Token::Address(from),
+                    Token::Address(self.to),
+                    Token::Uint(self.token_id),
+                    Token::Uint(self.amount),
+                    Token::Bytes(vec![]),
+                ],
+            ),
+        }
+    }
+}
+
+/// `ownerOf(token_id)` of an ERC-721 collection.
+pub async fn erc721_owner_of(
+    web3_connection: &Web3<EthTransport>,
+    contract: Address,
+    token_id: U256,
+) -> Result<Address> {
+    let data = encode_call("ownerOf(uint256)", &[Token::Uint(token_id)]);
+    let result = call(web3_connection, contract, data).await?;
+    if result.0.len() != 32 {
+        return Err(anyhow!("unexpected ownerOf result: {:?}", result));
+    }
+    Ok(Address::from_slice(&result.0[12..]))
+}
+
+/// `balanceOf(owner, token_id)` of an ERC-1155 collection.
+pub async fn erc1155_balance_of(
+    web3_connection: &Web3<EthTransport>,
+    contract: Address,
+    owner: Address,
+    token_id: U256,
+) -> Result<U256> {
+    let data = encode_call(
+        "balanceOf(address,uint256)",
+        &[Token::Address(owner), Token::Uint(token_id)],
+    );
+    let result = call(web3_connection, contract, data).await?;
+    if result.0.len() != 32 {
+        return Err(anyhow!("unexpected balanceOf result: {:?}", result));
+    }
+    Ok(U256::from_big_endian(&result.0))
+}
+
+/// Fail unless `from` holds what `transfer` moves.
+pub async fn check_nft_holding(
+    web3_connection: &Web3<EthTransport>,
+    transfer: &NftTransfer,
+    from: Address,
+) -> Result<()> {
+    transfer.check_amount()?;
+    match transfer.standard {
+        NftStandard::Erc721 => {
+            let owner =
+                erc721_owner_of(web3_connection, transfer.contract, transfer.token_id).await?;
+            if owner != from {
+                return Err(anyhow!(
+                    "token {} is owned by {}, not {}",
+                    transfer.token_id,
+                    EthAddress::from(owner),
+                    EthAddress::from(from)
+                ));
+            }
+        }
+        NftStandard::Erc1155 => {
+            let balance =
+                erc1155_balance_of(web3_connection, transfer.contract, from, transfer.token_id)
+                    .await?;
+            if balance < transfer.amount {
+                return Err(anyhow!(
+                    "{} holds {} of token {}, {} requested",
+                    EthAddress::from(from),
+                    balance,
+                    transfer.token_id,
+                    transfer.amount
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Transfer an NFT held by the derived address `from`.
+pub fn send_nft(
+    from: &str,
+    transfer: &NftTransfer,
+    network: &EthNetwork,
+    client_shim: &ClientShim,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<EthAddress, MKPosDto>,
+) -> Result<EthTxRecord> {
+    let pos_mk = get_pos_mk(from, addresses_derivation_map)?;
+    let from_address = pubkey_to_eth_address(&pos_mk.mk);
+
+    block_on(async {
+        let web3_connection = establish_web3_connection(network.rpc_url()?).await?;
+        check_nft_holding(&web3_connection, transfer, from_address).await
+    })?;
+
+    sign_and_send_on_chain(
+        from,
+        &EthAddress::from(transfer.contract).to_string(),
+        U256::zero(),
+        transfer.calldata(from_address),
+        network,
+        client_shim,
+        private_share,
+        addresses_derivation_map,
+    )
+}
+
+/// Send an `NftTransfer` given as JSON, returning the transaction record.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn send_nft_tx(
+    c_endpoint: *const c_char,
+    c_auth_token: *const c_char,
+    c_user_id: *const c_char,
+    c_from_address: *const c_char,
+    c_nft_transfer_json: *const c_char,
+    c_network: *const c_char,
+    c_private_share_json: *const c_char,
+    c_addresses_derivation_map: *const c_char,
+) -> *mut c_char {
+    let from_address = match get_str_from_c_char(c_from_address, "from_address") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let nft_transfer_json = match get_str_from_c_char(c_nft_transfer_json, "nft_transfer") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let client_shim = match get_client_shim_from_raw(c_endpoint, c_auth_token, c_user_id) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let network = match get_eth_network_from_raw(c_network) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let private_share = match get_private_share_from_raw(c_private_share_json) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let addresses_derivation_map =
+        match get_addresses_derivation_map_from_raw(c_addresses_derivation_map) {
+            Ok(s) => s,
+            Err(e) => return error_to_c_string(e),
+        };
+
+    let nft_transfer: NftTransfer = match serde_json::from_str(&nft_transfer_json) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E104 {
+                msg: "nft_transfer".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let tx_record = match send_nft(
+        &from_address,
+        &nft_transfer,
+        &network,
+        &client_shim,
+        &private_share,
+        &addresses_derivation_map,
+    ) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E103 {
+                msg: "tx_record".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let tx_record_json = match serde_json::to_string(&tx_record) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E102 {
+                msg: "tx_record".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(tx_record_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "tx_record".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
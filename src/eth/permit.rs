@@ -0,0 +1,422 @@
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use serde_json::json;
+use web3::ethabi::{self, ParamType, Token};
+use web3::types::{Address, Bytes, U256};
+use web3::Web3;
+
+use crate::dto::ecdsa::{MKPosDto, PrivateShare};
+use crate::eth::abi::{call, encode_call};
+use crate::eth::address::EthAddress;
+use crate::eth::message::sign_hash;
+use crate::eth::network::EthNetwork;
+use crate::eth::transport::EthTransport;
+use crate::eth::typed_data::TypedData;
+use crate::eth::utils::{establish_web3_connection, get_pos_mk};
+use crate::utilities::err_handling::{error_to_c_string, ErrorFFIKind};
+use crate::utilities::ffi::ffi_utils::{
+    get_addresses_derivation_map_from_raw, get_client_shim_from_raw, get_eth_network_from_raw,
+    get_private_share_from_raw, get_str_from_c_char,
+};
+use crate::utilities::requests::ClientShim;
+use crate::utilities::runtime::block_on;
+
+/// Uniswap's Permit2, deployed at the same address on every chain.
+pub const PERMIT2_ADDRESS: &str = "0x000000000022D473030F116dDEE9F6B43aC78BA3";
+
+/// EIP-712 domain of an ERC-2612 token.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct TokenDomain {
+    pub name: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub verifying_contract: Address,
+}
+
+/// Typed data and signature of a permit, ready to be handed to a dApp or a contract.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SignedPermit {
+    pub typed_data: TypedData,
+    pub signature: Bytes,
+}
+
+/// ERC-2612 `Permit` letting `spender` move `value` tokens of `owner` until `deadline`.
+pub fn permit_typed_data(
+    domain: &TokenDomain,
+    owner: Address,
+    spender: Address,
+    value: U256,
+    nonce: U256,
+    deadline: U256,
+) -> Result<TypedData> {
+    let typed_data = json!({
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" }
+            ],
+            "Permit": [
+                { "name": "owner", "type": "address" },
+                { "name": "spender", "type": "address" },
+                { "name": "value", "type": "uint256" },
+                { "name": "nonce", "type": "uint256" },
+                { "name": "deadline", "type": "uint256" }
+            ]
+        },
+        "primaryType": "Permit",
+        "domain": {
+            "name": domain.name,
+            "version": domain.version,
+            "chainId": domain.chain_id,
+            "verifyingContract": domain.verifying_contract
+        },
+        "message": {
+            "owner": owner,
+            "spender": spender,
+            "value": value,
+            "nonce": nonce,
+            "deadline": deadline
+        }
+    });
+    Ok(serde_json::from_value(typed_data)?)
+}
+
+/// Permit2 `PermitSingle` allowing `spender` to move `amount` of `token` until `expiration`,
+/// the signature itself being valid until `sig_deadline`.
+pub fn permit2_typed_data(
+    chain_id: u64,
+    token: Address,
+    amount: U256,
+    expiration: u64,
+    nonce: U256,
+    spender: Address,
+    sig_deadline: U256,
+) -> Result<TypedData> {
+    let permit2: Address = EthAddress::from_str(PERMIT2_ADDRESS)?.into();
+    let typed_data = json!({
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" }
+            ],
+            "PermitSingle": [
+                { "name": "details", "type": "PermitDetails" },
+                { "name": "spender", "type": "address" },
+                { "name": "sigDeadline", "type": "uint256" }
+            ],
+            "PermitDetails": [
+                { "name": "token", "type": "address" },
+                { "name": "amount", "type": "uint160" },
+                { "name": "expiration", "type": "uint48" },
+                { "name": "nonce", "type": "uint48" }
+            ]
+        },
+        "primaryType": "PermitSingle",
+        "domain": {
+            "name": "Permit2",
+            "chainId": chain_id,
+            "verifyingContract": permit2
+        },
+        "message": {
+            "details": {
+                "token": token,
+                "amount": amount,
+                "expiration": expiration,
+                "nonce": nonce
+            },
+            "spender": spender,
+            "sigDeadline": sig_deadline
+        }
+    });
+    Ok(serde_json::from_value(typed_data)?)
+}
+
+async fn call_uint(
+    web3_connection: &Web3<EthTransport>,
+    to: Address,
+    data: Vec<u8>,
+) -> Result<U256> {
+    let result = call(web3_connection, to, data).await?;
+    match ethabi::decode(&[ParamType::Uint(256)], &result.0)?.pop() {
+        Some(Token::Uint(value)) => Ok(value),
+        _ => Err(anyhow!("expected a uint256 result")),
+    }
+}
+
+async fn call_string(
+    web3_connection: &Web3<EthTransport>,
+    to: Address,
+    data: Vec<u8>,
+) -> Result<String> {
+    let result = call(web3_connection, to, data).await?;
+    match ethabi::decode(&[ParamType::String], &result.0)?.pop() {
+        Some(Token::String(value)) => Ok(value),
+        _ => Err(anyhow!("expected a string result")),
+    }
+}
+
+/// Read the EIP-712 domain of `token` and check it against its `DOMAIN_SEPARATOR()`. Tokens
+/// without `version()` are assumed to use version "1".
+pub async fn get_token_domain(
+    web3_connection: &Web3<EthTransport>,
+    token: Address,
+    chain_id: u64,
+) -> Result<TokenDomain> {
+    let name = call_string(web3_connection, token, encode_call("name()", &[])).await?;
+    let version = call_string(web3_connection, token, encode_call("version()", &[]))
+        .await
+        .unwrap_or_else(|_| "1".to_owned());
+    let domain = TokenDomain {
+        name,
+        version,
+        chain_id,
+        verifying_contract: token,
+    };
+
+    let domain_separator = call_uint(
+        web3_connection,
+        token,
+        encode_call("DOMAIN_SEPARATOR()", &[]),
+    )
+    .await?;
+    let typed_data = permit_typed_data(
+        &domain,
+        Address::zero(),
+        Address::zero(),
+        U256::zero(),
+        U256::zero(),
+        U256::zero(),
+    )?;
+    if U256::from_big_endian(&typed_data.domain_separator()?) != domain_separator {
+        return Err(anyhow!(
+            "cannot rebuild the EIP-712 domain of token {}",
+            EthAddress::from(token)
+        ));
+    }
+    Ok(domain)
+}
+
+/// Build the ERC-2612 permit of `owner` for `token`, reading the domain and nonce on chain.
+pub fn build_permit(
+    network: &EthNetwork,
+    token: Address,
+    owner: Address,
+    spender: Address,
+    value: U256,
+    deadline: U256,
+) -> Result<TypedData> {
+    block_on(async {
+        let web3_connection = establish_web3_connection(network.rpc_url()?).await?;
+        let domain = get_token_domain(&web3_connection, token, network.chain_id).await?;
+        let nonce = call_uint(
+            &web3_connection,
+            token,
+            encode_call("nonces(address)", &[Token::Address(owner)]),
+        )
+        .await?;
+        permit_typed_data(&domain, owner, spender, value, nonce, deadline)
+    })
+}
+
+/// Build the Permit2 `PermitSingle` of `owner`, reading its allowance nonce on chain.
+pub fn build_permit2(
+    network: &EthNetwork,
+    token: Address,
+    owner: Address,
+    spender: Address,
+    amount: U256,
+    expiration: u64,
+    sig_deadline: U256,
+) -> Result<TypedData> {
+    let permit2: Address = EthAddress::from_str(PERMIT2_ADDRESS)?.into();
+    let nonce = block_on(async {
+        let web3_connection = establish_web3_connection(network.rpc_url()?).await?;
+        let data = encode_call(
+            "allowance(address,address,address)",
+            &[
+                Token::Address(owner),
+                Token::Address(token),
+                Token::Address(spender),
+            ],
+        );
+        let result = call(&web3_connection, permit2, data).await?;
+        let allowance = ethabi::decode(
+            &[
+                ParamType::Uint(160),
+                ParamType::Uint(48),
+                ParamType::Uint(48),
+            ],
+            &result.0,
+        )?;
+        match allowance.get(2) {
+            Some(Token::Uint(nonce)) => Ok(*nonce),
+            _ => Err(anyhow!("unexpected Permit2 allowance result")),
+        }
+    })?;
+    permit2_typed_data(
+        network.chain_id,
+        token,
+        amount,
+        expiration,
+        nonce,
+        spender,
+        sig_deadline,
+    )
+}
+
+/// Sign a permit with the derived address `from`, which must be the permit owner.
+pub fn sign_permit(
+    from: &str,
+    typed_data: TypedData,
+    client_shim: &ClientShim,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<EthAddress, MKPosDto>,
+) -> Result<SignedPermit> {
+    let pos_mk = get_pos_mk(from, addresses_derivation_map)?;
+    let signature = sign_hash(
+        typed_data.signing_hash()?,
+        pos_mk,
+        client_shim,
+        private_share,
+    )?;
+    Ok(SignedPermit {
+        typed_data,
+        signature,
+    })
+}
+
+fn parse_amount(amount: &str) -> Result<U256> {
+    U256::from_dec_str(amount).map_err(|e| anyhow!("invalid amount {}: {:?}", amount, e))
+}
+
+fn signed_permit_to_c_string(signed_permit: Result<SignedPermit>) -> *mut c_char {
+    let signed_permit = match signed_permit {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E103 {
+                msg: "permit".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let signed_permit_json = match serde_json::to_string(&signed_permit) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E102 {
+                msg: "permit".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(signed_permit_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "permit".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
+
+/// Sign an ERC-2612 permit of `c_amount` (decimal, in token units) valid until the unix time
+/// `c_deadline`. With `c_use_permit2`, sign a Permit2 allowance expiring at `c_deadline`
+/// instead.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn eth_sign_permit(
+    c_endpoint: *const c_char,
+    c_auth_token: *const c_char,
+    c_user_id: *const c_char,
+    c_from_address: *const c_char,
+    c_token_address: *const c_char,
+    c_spender_address: *const c_char,
+    c_amount: *const c_char,
+    c_deadline: u64,
+    c_use_permit2: bool,
+    c_network: *const c_char,
+    c_private_share_json: *const c_char,
+    c_addresses_derivation_map: *const c_char,
+) -> *mut c_char {
+    let from_address = match get_str_from_c_char(c_from_address, "from_address") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let token_address = match get_str_from_c_char(c_token_address, "token_address") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let spender_address = match get_str_from_c_char(c_spender_address, "spender_address") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let amount = match get_str_from_c_char(c_amount, "amount") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let client_shim = match get_client_shim_from_raw(c_endpoint, c_auth_token, c_user_id) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let network = match get_eth_network_from_raw(c_network) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let private_share = match get_private_share_from_raw(c_private_share_json) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let addresses_derivation_map =
+        match get_addresses_derivation_map_from_raw(c_addresses_derivation_map) {
+            Ok(s) => s,
+            Err(e) => return error_to_c_string(e),
+        };
+
+    signed_permit_to_c_string((|| {
+        let owner: Address = EthAddress::from_str(&from_address)?.into();
+        let token: Address = EthAddress::from_str(&token_address)?.into();
+        let spender: Address = EthAddress::from_str(&spender_address)?.into();
+        let amount = parse_amount(&amount)?;
+        let typed_data = if c_use_permit2 {
+            build_permit2(
+                &network,
+                token,
+                owner,
+                spender,
+                amount,
+                c_deadline,
+                U256::from(c_deadline),
+            )?
+        } else {
+            build_permit(
+                &network,
+                token,
+                owner,
+                spender,
+                amount,
+                U256::from(c_deadline),
+            )?
+        };
+        sign_permit(
+            &from_address,
+            typed_data,
+            &client_shim,
+            &private_share,
+            &addresses_derivation_map,
+        )
+    })())
+}
@@ -0,0 +1,223 @@
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use web3::ethabi::{self, ParamType, Token};
+use web3::types::{Address, U256};
+
+use crate::dto::eth::{EthCallPreview, EthTxPreview};
+use crate::eth::abi::selector;
+use crate::eth::address::EthAddress;
+use crate::eth::raw_tx::fetch_tx_params;
+use crate::eth::transaction::Transaction;
+use crate::eth::utils::{get_pos_mk, pubkey_to_eth_address};
+use crate::utilities::err_handling::{error_to_c_string, ErrorFFIKind};
+use crate::utilities::ffi::ffi_utils::{
+    get_addresses_derivation_map_from_raw, get_client_shim_from_raw, get_eth_network_from_raw,
+    get_str_from_c_char,
+};
+
+/// Decode the ERC-20 `transfer`, `transferFrom` and `approve` calls, other calls are only
+/// identified by their selector.
+pub fn decode_call(to: Option<Address>, data: &[u8]) -> EthCallPreview {
+    let to = match to {
+        Some(to) => to,
+        None => return EthCallPreview::ContractCreation,
+    };
+    if data.is_empty() {
+        return EthCallPreview::Transfer;
+    }
+
+    let (call_selector, args) = data.split_at(data.len().min(4));
+    let decode = |types: &[ParamType]| ethabi::decode(types, args).ok();
+    let address_uint = [ParamType::Address, ParamType::Uint(256)];
+
+    if call_selector == selector("transfer(address,uint256)") {
+        if let Some([Token::Address(recipient), Token::Uint(amount)]) =
+            decode(&address_uint).as_deref()
+        {
+            return EthCallPreview::Erc20Transfer {
+                token: to,
+                to: *recipient,
+                amount: *amount,
+            };
+        }
+    } else if call_selector == selector("approve(address,uint256)") {
+        if let Some([Token::Address(spender), Token::Uint(amount)]) =
+            decode(&address_uint).as_deref()
+        {
+            return EthCallPreview::Erc20Approve {
+                token: to,
+                spender: *spender,
+                amount: *amount,
+            };
+        }
+    } else if call_selector == selector("transferFrom(address,address,uint256)") {
+        if let Some([Token::Address(from), Token::Address(recipient), Token::Uint(amount)]) =
+            decode(&[ParamType::Address, ParamType::Address, ParamType::Uint(256)]).as_deref()
+        {
+            return EthCallPreview::Erc20TransferFrom {
+                token: to,
+                from: *from,
+                to: *recipient,
+                amount: *amount,
+            };
+        }
+    }
+
+    EthCallPreview::ContractCall {
+        contract: to,
+        selector: call_selector.to_vec().into(),
+    }
+}
+
+/// Fails if the maximum fee of `tx` overflows, its gas and fee come from the server.
+pub fn preview_eth_tx(tx: &Transaction, chain_id: u64, from: Address) -> Result<EthTxPreview> {
+    let max_fee = tx
+        .gas
+        .checked_mul(tx.gas_price)
+        .ok_or_else(|| anyhow!("gas {} at {} per gas overflows", tx.gas, tx.gas_price))?;
+    Ok(EthTxPreview {
+        chain_id,
+        from,
+        to: tx.to,
+        nonce: tx.nonce,
+        value: tx.value,
+        gas: tx.gas,
+        max_fee_per_gas: tx.gas_price,
+        max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+        max_fee,
+        call: decode_call(tx.to, &tx.data),
+    })
+}
+
+/// Fail unless `preview` is a plain transfer of `value` (give or take `tolerance`) to `to`.
+pub fn check_native_transfer(
+    preview: &EthTxPreview,
+    to: Address,
+    value: U256,
+    tolerance: U256,
+) -> Result<()> {
+    if preview.call != EthCallPreview::Transfer {
+        return Err(anyhow!(
+            "expected a plain transfer, the transaction is {:?}",
+            preview.call
+        ));
+    }
+    if preview.to != Some(to) {
+        return Err(anyhow!(
+            "expected a transfer to {}, the transaction pays {:?}",
+            EthAddress::from(to),
+            preview.to
+        ));
+    }
+    let difference = if preview.value > value {
+        preview.value - value
+    } else {
+        value - preview.value
+    };
+    if difference > tolerance {
+        return Err(anyhow!(
+            "expected a transfer of {} wei, the transaction sends {}",
+            value,
+            preview.value
+        ));
+    }
+    Ok(())
+}
+
+/// Fail unless `preview` is an ERC-20 transfer of `amount` of `token` to `to`.
+pub fn check_erc20_transfer(
+    preview: &EthTxPreview,
+    token: Address,
+    to: Address,
+    amount: U256,
+) -> Result<()> {
+    let expected = EthCallPreview::Erc20Transfer { token, to, amount };
+    if preview.call != expected || !preview.value.is_zero() {
+        return Err(anyhow!(
+            "expected {:?}, the transaction is {:?} with value {}",
+            expected,
+            preview.call,
+            preview.value
+        ));
+    }
+    Ok(())
+}
+
+/// Preview the transaction `send_eth_tx_on_network` would sign for the same arguments, without
+/// signing.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn get_eth_tx_preview(
+    c_endpoint: *const c_char,
+    c_auth_token: *const c_char,
+    c_user_id: *const c_char,
+    c_from_address: *const c_char,
+    c_to_address: *const c_char,
+    c_eth_value: f64,
+    c_network: *const c_char,
+    c_addresses_derivation_map: *const c_char,
+) -> *mut c_char {
+    let from_address = match get_str_from_c_char(c_from_address, "from_address") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let to_address = match get_str_from_c_char(c_to_address, "to_address") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let client_shim = match get_client_shim_from_raw(c_endpoint, c_auth_token, c_user_id) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let network = match get_eth_network_from_raw(c_network) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let addresses_derivation_map =
+        match get_addresses_derivation_map_from_raw(c_addresses_derivation_map) {
+            Ok(s) => s,
+            Err(e) => return error_to_c_string(e),
+        };
+
+    let preview = get_pos_mk(&from_address, &addresses_derivation_map).and_then(|pos_mk| {
+        let from = pubkey_to_eth_address(&pos_mk.mk);
+        let to: Address = EthAddress::from_str(&to_address)?.into();
+        let tx = fetch_tx_params(from, to, c_eth_value, &network, &client_shim)?;
+        preview_eth_tx(&tx, network.chain_id, from)
+    });
+
+    let preview = match preview {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E103 {
+                msg: "eth_tx_preview".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let preview_json = match serde_json::to_string(&preview) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E102 {
+                msg: "eth_tx_preview".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(preview_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "eth_tx_preview".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
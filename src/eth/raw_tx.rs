@@ -3,15 +3,26 @@ use std::str::FromStr;
 
 use crate::dto::ecdsa::MKPosDto;
 use crate::dto::ecdsa::PrivateShare;
-use crate::dto::eth::{EthSendTxReqBody, EthSendTxResp, EthTxParamsReqBody, EthTxParamsResp};
-use crate::ecdsa::sign::sign;
-use crate::eth::transaction::Transaction;
-use crate::eth::utils::pubkey_to_eth_address;
+use crate::dto::eth::{
+    EthSendTxReqBody, EthSendTxResp, EthTxParamsReqBody, EthTxParamsResp, EthTxRecord,
+};
+use crate::ecdsa::sign::a_sign;
+use crate::eth::access_list::find_gas_saving_access_list;
+use crate::eth::address::EthAddress;
+use crate::eth::network::{get_eth_network, EthNetwork, SEPOLIA};
+use crate::eth::preview::{check_native_transfer, preview_eth_tx};
+use crate::eth::transaction::{Transaction, ACCESSLISTS_TX_ID, EIP1559_TX_ID};
+use crate::eth::transport::EthTransport;
+use crate::eth::utils::{establish_web3_connection, get_pos_mk, pubkey_to_eth_address};
+use crate::utilities::a_requests::{self, AsyncClientShim};
 use crate::utilities::err_handling::{error_to_c_string, ErrorFFIKind};
 use crate::utilities::ffi::ffi_utils::{
-    get_addresses_derivation_map_from_raw, get_client_shim_from_raw, get_private_share_from_raw,
+    get_addresses_derivation_map_from_raw, get_client_shim_from_raw, get_eth_network_from_raw,
+    get_private_share_from_raw, get_str_from_c_char,
 };
-use crate::utilities::requests::{self, ClientShim};
+use crate::utilities::requests::ClientShim;
+use crate::utilities::runtime::block_on;
+use crate::utilities::to_bytes32;
 
 use anyhow::{anyhow, Result};
 use curv::arithmetic::traits::Converter;
@@ -19,31 +30,125 @@ use curv::BigInt;
 use hex;
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
-use web3::types::{Address, H256};
-use web3::{self, signing::Signature};
+use web3::types::{Address, BlockNumber, Bytes, CallRequest, H256, U256, U64};
+use web3::{self, signing::Signature, Web3};
+
+/// Number of recent blocks whose priority fees are used to pick the tip of a new transaction.
+pub const FEE_HISTORY_BLOCKS: u64 = 10;
+/// How far above the node's own estimate the gas and fee proposed by the server may be.
+pub const SERVER_FEE_MARGIN_PERCENT: u64 = 20;
 
 pub fn sign_and_send(
     from: &str,
     to: &str,
     eth_value: f64,
+    network: &EthNetwork,
     client_shim: &ClientShim,
     private_share: &PrivateShare,
-    addresses_derivation_map: &HashMap<String, MKPosDto>,
-) -> Result<H256> {
-    let pos_mk = match addresses_derivation_map.get(from.to_lowercase().as_str()) {
-        Some(pos_mk) => pos_mk,
-        None => {
-            return Err(anyhow!(
-                "from address not found in addresses_derivation_map"
-            ))
-        }
-    };
-    let mk = &pos_mk.mk;
-    let pos = pos_mk.pos;
+    addresses_derivation_map: &HashMap<EthAddress, MKPosDto>,
+) -> Result<EthTxRecord> {
+    block_on(a_sign_and_send(
+        from,
+        to,
+        eth_value,
+        network,
+        client_shim,
+        private_share,
+        addresses_derivation_map,
+    ))
+}
+
+pub async fn a_sign_and_send(
+    from: &str,
+    to: &str,
+    eth_value: f64,
+    network: &EthNetwork,
+    client_shim: &AsyncClientShim,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<EthAddress, MKPosDto>,
+) -> Result<EthTxRecord> {
+    let pos_mk = get_pos_mk(from, addresses_derivation_map)?;
+
+    let from_address = pubkey_to_eth_address(&pos_mk.mk);
+    let to_address: Address = EthAddress::from_str(to)?.into();
+
+    let tx = a_fetch_tx_params(from_address, to_address, eth_value, network, client_shim).await?;
+
+    // Never sign what the server built without checking it is what was asked for. The
+    // server converts `eth_value` itself, allow for its float rounding.
+    let expected_value = network.from_native(eth_value)?;
+    check_native_transfer(
+        &preview_eth_tx(&tx, network.chain_id, from_address)?,
+        to_address,
+        expected_value,
+        expected_value / U256::exp10(12) + 1,
+    )?;
+    let web3_connection = establish_web3_connection(network.rpc_url()?).await?;
+    network.check_chain_id(&web3_connection).await?;
+
+    // Nor with a gas or fee far above what the node itself would pay
+    let (_, gas_price, _) = suggest_fees(&web3_connection, network).await?;
+    let gas = web3_connection
+        .eth()
+        .estimate_gas(
+            CallRequest {
+                from: Some(from_address),
+                to: Some(to_address),
+                value: Some(tx.value),
+                ..Default::default()
+            },
+            None,
+        )
+        .await?;
+    check_tx_fees(&tx, with_margin(gas), with_margin(gas_price))?;
+
+    a_sign_and_send_tx(tx, network.chain_id, pos_mk, client_shim, private_share).await
+}
+
+fn with_margin(amount: U256) -> U256 {
+    amount.saturating_mul(U256::from(100 + SERVER_FEE_MARGIN_PERCENT)) / 100
+}
+
+/// Fail if `tx` asks for more than `max_gas`, or pays more than `max_gas_price` per gas.
+pub fn check_tx_fees(tx: &Transaction, max_gas: U256, max_gas_price: U256) -> Result<()> {
+    if tx.gas > max_gas {
+        return Err(anyhow!("gas {} is above the limit of {}", tx.gas, max_gas));
+    }
+    if tx.gas_price > max_gas_price || tx.max_priority_fee_per_gas > max_gas_price {
+        return Err(anyhow!(
+            "fee of {} per gas (priority fee {}) is above the limit of {}",
+            tx.gas_price,
+            tx.max_priority_fee_per_gas,
+            max_gas_price
+        ));
+    }
+    Ok(())
+}
 
-    let from_address = pubkey_to_eth_address(mk);
-    let to_address = Address::from_str(to)?;
+/// Nonce, gas and fees of a transfer of `eth_value`, as chosen by the server.
+pub fn fetch_tx_params(
+    from_address: Address,
+    to_address: Address,
+    eth_value: f64,
+    network: &EthNetwork,
+    client_shim: &ClientShim,
+) -> Result<Transaction> {
+    block_on(a_fetch_tx_params(
+        from_address,
+        to_address,
+        eth_value,
+        network,
+        client_shim,
+    ))
+}
 
+pub async fn a_fetch_tx_params(
+    from_address: Address,
+    to_address: Address,
+    eth_value: f64,
+    network: &EthNetwork,
+    client_shim: &AsyncClientShim,
+) -> Result<Transaction> {
     let tx_params_body = EthTxParamsReqBody {
         from_address,
         to_address,
@@ -51,12 +156,21 @@ pub fn sign_and_send(
     };
 
     let tx_params: EthTxParamsResp =
-        match requests::postb(client_shim, "eth/tx/params", tx_params_body)? {
+        match a_requests::a_postb_idempotent(client_shim, "eth/tx/params", tx_params_body).await? {
             Some(s) => s,
             None => return Err(anyhow!("get ETH tx params request failed")),
         };
 
-    let tx = Transaction {
+    if tx_params.chain_id != network.chain_id {
+        return Err(anyhow!(
+            "tx params chain id {} does not match network {} (chain id {})",
+            tx_params.chain_id,
+            network.name,
+            network.chain_id
+        ));
+    }
+
+    Ok(Transaction {
         to: tx_params.to,
         nonce: tx_params.nonce,
         gas: tx_params.gas,
@@ -66,38 +180,292 @@ pub fn sign_and_send(
         transaction_type: tx_params.transaction_type,
         access_list: tx_params.access_list,
         max_priority_fee_per_gas: tx_params.max_priority_fee_per_gas,
-    };
-    let chain_id = tx_params.chain_id;
+    })
+}
+
+/// Sign `tx` with the key at `pos_mk` and broadcast it through the server.
+pub fn sign_and_send_tx(
+    tx: Transaction,
+    chain_id: u64,
+    pos_mk: &MKPosDto,
+    client_shim: &ClientShim,
+    private_share: &PrivateShare,
+) -> Result<EthTxRecord> {
+    block_on(a_sign_and_send_tx(
+        tx,
+        chain_id,
+        pos_mk,
+        client_shim,
+        private_share,
+    ))
+}
+
+pub async fn a_sign_and_send_tx(
+    tx: Transaction,
+    chain_id: u64,
+    pos_mk: &MKPosDto,
+    client_shim: &AsyncClientShim,
+    private_share: &PrivateShare,
+) -> Result<EthTxRecord> {
+    let (raw_tx, record) = a_sign_tx(tx, chain_id, pos_mk, client_shim, private_share).await?;
+
+    let tx_send_body = EthSendTxReqBody { raw_tx };
+
+    let transaction_result: EthSendTxResp =
+        match a_requests::a_postb(client_shim, "eth/tx/send", tx_send_body).await? {
+            Some(s) => s,
+            None => return Err(anyhow!("send ETH tx request failed")),
+        };
+
+    Ok(EthTxRecord {
+        tx_hash: transaction_result.tx_hash,
+        ..record
+    })
+}
+
+/// Run the two-party signing protocol over `tx`, returning the raw signed transaction.
+pub fn sign_tx(
+    tx: Transaction,
+    chain_id: u64,
+    pos_mk: &MKPosDto,
+    client_shim: &ClientShim,
+    private_share: &PrivateShare,
+) -> Result<(Bytes, EthTxRecord)> {
+    block_on(a_sign_tx(tx, chain_id, pos_mk, client_shim, private_share))
+}
+
+pub async fn a_sign_tx(
+    tx: Transaction,
+    chain_id: u64,
+    pos_mk: &MKPosDto,
+    client_shim: &AsyncClientShim,
+    private_share: &PrivateShare,
+) -> Result<(Bytes, EthTxRecord)> {
     let msg = tx.get_hash(chain_id);
 
-    let sig = sign(
+    let sig = a_sign(
         client_shim,
         BigInt::from_hex(&hex::encode(&msg[..])).unwrap(),
-        mk,
+        &pos_mk.mk,
         BigInt::from(0),
-        BigInt::from(pos),
+        BigInt::from(pos_mk.pos),
         &private_share.id,
-    )?;
+    )
+    .await?;
 
-    let r = H256::from_slice(&BigInt::to_bytes(&sig.r));
-    let s = H256::from_slice(&BigInt::to_bytes(&sig.s));
-    let v = sig.recid as u64 + 35 + chain_id * 2;
+    let r = H256::from(to_bytes32(&sig.r));
+    let s = H256::from(to_bytes32(&sig.s));
+    let v = tx.signature_v(sig.recid, chain_id);
     let signature = Signature { r, s, v };
+    let record = EthTxRecord {
+        tx_hash: H256::zero(),
+        chain_id,
+        from: pubkey_to_eth_address(&pos_mk.mk),
+        to: tx.to,
+        nonce: tx.nonce,
+        gas: tx.gas,
+        gas_price: tx.gas_price,
+        max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+        value: tx.value,
+        data: tx.data.clone().into(),
+        transaction_type: tx.transaction_type,
+        access_list: tx.access_list.clone(),
+        replaces: None,
+        replaced_by: None,
+    };
     let signed = tx.sign(signature, chain_id);
+    check_signed_tx(&signed.raw_transaction.0, record.from, chain_id)?;
 
-    let tx_send_body = EthSendTxReqBody {
-        raw_tx: signed.raw_transaction,
-    };
+    Ok((
+        signed.raw_transaction,
+        EthTxRecord {
+            tx_hash: signed.transaction_hash,
+            ..record
+        },
+    ))
+}
 
-    let transaction_result: EthSendTxResp =
-        match requests::postb(client_shim, "eth/tx/send", tx_send_body)? {
-            Some(s) => s,
-            None => return Err(anyhow!("send ETH tx request failed")),
+/// Fail unless the raw signed transaction `raw_tx` recovers to `from` on `chain_id`, i.e. the
+/// two-party signature is valid for what is about to be broadcast.
+pub fn check_signed_tx(raw_tx: &[u8], from: Address, chain_id: u64) -> Result<()> {
+    let decoded = Transaction::decode(raw_tx)?;
+    if decoded.chain_id != Some(chain_id) {
+        return Err(anyhow!(
+            "signed transaction is for chain id {:?}, expected {}",
+            decoded.chain_id,
+            chain_id
+        ));
+    }
+    let sender = decoded.recover_sender()?;
+    if sender != from {
+        return Err(anyhow!(
+            "signed transaction recovers to {}, expected {}",
+            EthAddress::from(sender),
+            EthAddress::from(from)
+        ));
+    }
+    Ok(())
+}
+
+/// Transaction type, gas price (max fee per gas for EIP-1559) and max priority fee per gas
+/// for a transaction sent now on `network`.
+pub async fn suggest_fees(
+    web3_connection: &Web3<EthTransport>,
+    network: &EthNetwork,
+) -> Result<(Option<U64>, U256, U256)> {
+    let eth = web3_connection.eth();
+    let fees = if network.eip1559 {
+        let history = eth
+            .fee_history(
+                U256::from(FEE_HISTORY_BLOCKS),
+                BlockNumber::Latest,
+                Some(vec![50.0]),
+            )
+            .await?;
+        let base_fee = match history.base_fee_per_gas.last() {
+            Some(f) => *f,
+            None => return Err(anyhow!("no base fee returned by {}", network.name)),
         };
+        let mut tips: Vec<U256> = history
+            .reward
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|r| r.first().copied())
+            .collect();
+        tips.sort();
+        let tip = tips.get(tips.len() / 2).copied().unwrap_or_default();
+        (Some(U64::from(EIP1559_TX_ID)), base_fee * 2 + tip, tip)
+    } else {
+        (None, eth.gas_price().await?, U256::zero())
+    };
+    Ok(fees)
+}
+
+/// Build a transaction from the node of `network`: pending nonce, estimated gas and current
+/// fees, EIP-1559 ones if the chain supports them. Contract calls get an access list when it
+/// lowers their gas.
+pub async fn build_tx(
+    web3_connection: &Web3<EthTransport>,
+    network: &EthNetwork,
+    from: Address,
+    to: Address,
+    value: U256,
+    data: Vec<u8>,
+) -> Result<Transaction> {
+    let eth = web3_connection.eth();
+    let nonce = eth
+        .transaction_count(from, Some(BlockNumber::Pending))
+        .await?;
+    let (transaction_type, gas_price, max_priority_fee_per_gas) =
+        suggest_fees(web3_connection, network).await?;
+
+    let call = CallRequest {
+        from: Some(from),
+        to: Some(to),
+        value: Some(value),
+        data: Some(data.clone().into()),
+        transaction_type,
+        ..Default::default()
+    };
+    let mut gas = eth.estimate_gas(call.clone(), None).await?;
 
-    Ok(transaction_result.tx_hash)
+    // Contract calls may get cheaper by pre-declaring the storage they touch (EIP-2930).
+    // Nodes without `eth_createAccessList` simply get the transaction without a list.
+    let mut access_list = vec![];
+    let mut transaction_type = transaction_type;
+    if !data.is_empty() {
+        if let Ok(Some((list, gas_with_list))) =
+            find_gas_saving_access_list(web3_connection, &call, gas).await
+        {
+            access_list = list;
+            gas = gas_with_list;
+            transaction_type = transaction_type.or(Some(U64::from(ACCESSLISTS_TX_ID)));
+        }
+    }
+
+    Ok(Transaction {
+        to: Some(to),
+        nonce,
+        gas,
+        gas_price,
+        value,
+        data,
+        transaction_type,
+        access_list,
+        max_priority_fee_per_gas,
+    })
 }
 
+/// Send `value` (in the smallest unit of the native currency) and `data` on any EVM chain,
+/// building and broadcasting the transaction through the node of `network` instead of the
+/// server. Only signing goes through the server.
+#[allow(clippy::too_many_arguments)]
+pub fn sign_and_send_on_chain(
+    from: &str,
+    to: &str,
+    value: U256,
+    data: Vec<u8>,
+    network: &EthNetwork,
+    client_shim: &ClientShim,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<EthAddress, MKPosDto>,
+) -> Result<EthTxRecord> {
+    block_on(a_sign_and_send_on_chain(
+        from,
+        to,
+        value,
+        data,
+        network,
+        client_shim,
+        private_share,
+        addresses_derivation_map,
+    ))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn a_sign_and_send_on_chain(
+    from: &str,
+    to: &str,
+    value: U256,
+    data: Vec<u8>,
+    network: &EthNetwork,
+    client_shim: &AsyncClientShim,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<EthAddress, MKPosDto>,
+) -> Result<EthTxRecord> {
+    let pos_mk = get_pos_mk(from, addresses_derivation_map)?;
+    let from_address = pubkey_to_eth_address(&pos_mk.mk);
+    let to_address: Address = EthAddress::from_str(to)?.into();
+
+    let web3_connection = establish_web3_connection(network.rpc_url()?).await?;
+    network.check_chain_id(&web3_connection).await?;
+    let tx = build_tx(
+        &web3_connection,
+        network,
+        from_address,
+        to_address,
+        value,
+        data,
+    )
+    .await?;
+
+    let (raw_tx, record) =
+        a_sign_tx(tx, network.chain_id, pos_mk, client_shim, private_share).await?;
+    web3_connection.eth().send_raw_transaction(raw_tx).await?;
+    Ok(record)
+}
+
+/// Broadcast a raw signed transaction through the node of `network`.
+pub fn broadcast_tx(network: &EthNetwork, raw_tx: Bytes) -> Result<H256> {
+    block_on(async {
+        let web3_connection = establish_web3_connection(network.rpc_url()?).await?;
+        let tx_hash = web3_connection.eth().send_raw_transaction(raw_tx).await?;
+        Ok(tx_hash)
+    })
+}
+
+/// Send `c_amount_eth` on Sepolia, the testnet this entry point has always used. Other
+/// networks go through `send_eth_tx_on_network`.
 #[no_mangle]
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn send_eth_tx(
@@ -109,6 +477,73 @@ pub extern "C" fn send_eth_tx(
     c_amount_eth: f64,
     c_private_share_json: *const c_char,
     c_addresses_derivation_map: *const c_char,
+) -> *mut c_char {
+    let network = match get_eth_network(SEPOLIA) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E104 {
+                msg: "network".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    send_eth_tx_to_c_string(
+        c_endpoint,
+        c_auth_token,
+        c_user_id,
+        c_from_address,
+        c_to_address,
+        c_amount_eth,
+        &network,
+        c_private_share_json,
+        c_addresses_derivation_map,
+    )
+}
+
+/// `send_eth_tx` on `c_network`, a registered network name or an `EthNetwork` JSON object.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn send_eth_tx_on_network(
+    c_endpoint: *const c_char,
+    c_auth_token: *const c_char,
+    c_user_id: *const c_char,
+    c_from_address: *const c_char,
+    c_to_address: *const c_char,
+    c_amount_eth: f64,
+    c_private_share_json: *const c_char,
+    c_addresses_derivation_map: *const c_char,
+    c_network: *const c_char,
+) -> *mut c_char {
+    let network = match get_eth_network_from_raw(c_network) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    send_eth_tx_to_c_string(
+        c_endpoint,
+        c_auth_token,
+        c_user_id,
+        c_from_address,
+        c_to_address,
+        c_amount_eth,
+        &network,
+        c_private_share_json,
+        c_addresses_derivation_map,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn send_eth_tx_to_c_string(
+    c_endpoint: *const c_char,
+    c_auth_token: *const c_char,
+    c_user_id: *const c_char,
+    c_from_address: *const c_char,
+    c_to_address: *const c_char,
+    c_amount_eth: f64,
+    network: &EthNetwork,
+    c_private_share_json: *const c_char,
+    c_addresses_derivation_map: *const c_char,
 ) -> *mut c_char {
     let raw_from_address = unsafe { CStr::from_ptr(c_from_address) };
     let from_address = match raw_from_address.to_str() {
@@ -153,10 +588,11 @@ pub extern "C" fn send_eth_tx(
             Err(e) => return error_to_c_string(e),
         };
 
-    let tx_hash = match sign_and_send(
+    let tx_record = match sign_and_send(
         from_address,
         to_address,
         c_amount_eth,
+        network,
         &client_shim,
         &private_share,
         &addresses_derivation_map,
@@ -170,7 +606,7 @@ pub extern "C" fn send_eth_tx(
         }
     };
 
-    let tx_hash_json = match serde_json::to_string(&tx_hash) {
+    let tx_hash_json = match serde_json::to_string(&tx_record.tx_hash) {
         Ok(tx_resp) => tx_resp,
         Err(e) => {
             return error_to_c_string(ErrorFFIKind::E102 {
@@ -188,3 +624,99 @@ pub extern "C" fn send_eth_tx(
         }),
     }
 }
+
+/// Like `send_eth_tx` on any EVM chain, `c_amount` being in the native currency of
+/// `c_network` and `c_data_hex` optional call data (empty for a plain transfer). Returns the
+/// transaction record.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn send_evm_tx(
+    c_endpoint: *const c_char,
+    c_auth_token: *const c_char,
+    c_user_id: *const c_char,
+    c_from_address: *const c_char,
+    c_to_address: *const c_char,
+    c_amount: f64,
+    c_data_hex: *const c_char,
+    c_network: *const c_char,
+    c_private_share_json: *const c_char,
+    c_addresses_derivation_map: *const c_char,
+) -> *mut c_char {
+    let from_address = match get_str_from_c_char(c_from_address, "from_address") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let to_address = match get_str_from_c_char(c_to_address, "to_address") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let data_hex = match get_str_from_c_char(c_data_hex, "data_hex") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let client_shim = match get_client_shim_from_raw(c_endpoint, c_auth_token, c_user_id) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let network = match get_eth_network_from_raw(c_network) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let private_share = match get_private_share_from_raw(c_private_share_json) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let addresses_derivation_map =
+        match get_addresses_derivation_map_from_raw(c_addresses_derivation_map) {
+            Ok(s) => s,
+            Err(e) => return error_to_c_string(e),
+        };
+
+    let tx_record = network.from_native(c_amount).and_then(|value| {
+        let data = hex::decode(data_hex.trim_start_matches("0x"))?;
+        sign_and_send_on_chain(
+            &from_address,
+            &to_address,
+            value,
+            data,
+            &network,
+            &client_shim,
+            &private_share,
+            &addresses_derivation_map,
+        )
+    });
+
+    let tx_record = match tx_record {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E103 {
+                msg: "tx_record".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let tx_record_json = match serde_json::to_string(&tx_record) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E102 {
+                msg: "tx_record".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(tx_record_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "tx_record".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
@@ -0,0 +1,244 @@
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use futures::StreamExt;
+use web3::types::{H256, U64};
+use web3::Web3;
+
+use crate::dto::eth::{EthTxReceiptReport, EthTxStatus};
+use crate::eth::network::EthNetwork;
+use crate::eth::transport::{get_connection, EthConnection, EthTransport};
+use crate::utilities::err_handling::{error_to_c_string, ErrorFFIKind};
+use crate::utilities::ffi::ffi_utils::{get_eth_network_from_raw, get_str_from_c_char};
+use crate::utilities::runtime::block_on;
+
+pub const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(4);
+pub const DEFAULT_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(600);
+
+pub async fn get_tx_receipt(
+    web3_connection: &Web3<EthTransport>,
+    tx_hash: H256,
+) -> Result<EthTxReceiptReport> {
+    let receipt = match web3_connection.eth().transaction_receipt(tx_hash).await? {
+        Some(r) => r,
+        None => {
+            return Ok(EthTxReceiptReport {
+                tx_hash,
+                status: EthTxStatus::Pending,
+                block_number: None,
+                gas_used: None,
+                effective_gas_price: None,
+                confirmations: 0,
+            })
+        }
+    };
+
+    let block_number = match receipt.block_number {
+        Some(b) => b,
+        None => return Err(anyhow!("receipt of {:?} has no block number", tx_hash)),
+    };
+    let latest_block = web3_connection.eth().block_number().await?;
+    let confirmations = latest_block.saturating_sub(block_number).as_u64() + 1;
+
+    // Receipts without status predate Byzantium, those transactions could not revert
+    let status = match receipt.status {
+        Some(s) if s == U64::zero() => EthTxStatus::Reverted,
+        _ => EthTxStatus::Success,
+    };
+
+    Ok(EthTxReceiptReport {
+        tx_hash,
+        status,
+        block_number: Some(block_number),
+        gas_used: receipt.gas_used,
+        effective_gas_price: receipt.effective_gas_price,
+        confirmations,
+    })
+}
+
+/// Wait until `tx_hash` is mined with at least `confirmations` blocks on top of it (itself
+/// included), 0 being taken as 1: mined. Reverted transactions are returned as soon as they
+/// are mined.
+///
+/// New blocks are awaited through a `newHeads` subscription on WebSocket connections, and by
+/// polling otherwise.
+pub async fn wait_for_confirmations(
+    connection: &EthConnection,
+    tx_hash: H256,
+    confirmations: u64,
+    timeout: Duration,
+) -> Result<EthTxReceiptReport> {
+    let confirmations = confirmations.max(1);
+    let mut new_heads = match connection.subscriptions() {
+        Some(ws) => ws.eth_subscribe().subscribe_new_heads().await.ok(),
+        None => None,
+    };
+
+    let wait = async {
+        loop {
+            let report = get_tx_receipt(connection.web3(), tx_hash).await?;
+            if report.status == EthTxStatus::Reverted || report.confirmations >= confirmations {
+                return Ok::<_, anyhow::Error>(report);
+            }
+
+            let head = match new_heads.as_mut() {
+                Some(heads) => heads.next().await,
+                None => None,
+            };
+            if head.is_none() {
+                new_heads = None;
+                tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+            }
+        }
+    };
+
+    let result = match tokio::time::timeout(timeout, wait).await {
+        Ok(report) => report,
+        Err(_) => Err(anyhow!(
+            "timed out waiting for {} confirmations of {:?}",
+            confirmations,
+            tx_hash
+        )),
+    };
+
+    if let Some(heads) = new_heads {
+        let _ = heads.unsubscribe().await;
+    }
+
+    result
+}
+
+/// Prefer a WebSocket endpoint so that confirmations are pushed rather than polled.
+async fn get_receipt_connection(network: &EthNetwork) -> Result<EthConnection> {
+    let url = match network.ws_url() {
+        Ok(url) => url,
+        Err(_) => network.rpc_url()?,
+    };
+    get_connection(url).await
+}
+
+pub fn get_eth_tx_receipt(network: &EthNetwork, tx_hash: H256) -> Result<EthTxReceiptReport> {
+    block_on(async {
+        let connection = get_connection(network.rpc_url()?).await?;
+        get_tx_receipt(connection.web3(), tx_hash).await
+    })
+}
+
+pub fn wait_for_eth_tx_confirmations(
+    network: &EthNetwork,
+    tx_hash: H256,
+    confirmations: u64,
+    timeout: Duration,
+) -> Result<EthTxReceiptReport> {
+    block_on(a_wait_for_eth_tx_confirmations(
+        network,
+        tx_hash,
+        confirmations,
+        timeout,
+    ))
+}
+
+pub async fn a_wait_for_eth_tx_confirmations(
+    network: &EthNetwork,
+    tx_hash: H256,
+    confirmations: u64,
+    timeout: Duration,
+) -> Result<EthTxReceiptReport> {
+    let connection = get_receipt_connection(network).await?;
+    wait_for_confirmations(&connection, tx_hash, confirmations, timeout).await
+}
+
+fn receipt_report_to_c_string(report: Result<EthTxReceiptReport>) -> *mut c_char {
+    let report = match report {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E103 {
+                msg: "tx_receipt".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let report_json = match serde_json::to_string(&report) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E102 {
+                msg: "tx_receipt".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(report_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "tx_receipt".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
+
+fn get_tx_hash_from_raw(c_tx_hash: *const c_char) -> Result<H256, ErrorFFIKind> {
+    let tx_hash = get_str_from_c_char(c_tx_hash, "tx_hash")?;
+    match H256::from_str(&tx_hash) {
+        Ok(s) => Ok(s),
+        Err(e) => Err(ErrorFFIKind::E104 {
+            msg: "tx_hash".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn get_eth_tx_status(
+    c_network: *const c_char,
+    c_tx_hash: *const c_char,
+) -> *mut c_char {
+    let network = match get_eth_network_from_raw(c_network) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let tx_hash = match get_tx_hash_from_raw(c_tx_hash) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    receipt_report_to_c_string(get_eth_tx_receipt(&network, tx_hash))
+}
+
+/// Blocks the calling thread, `c_timeout_secs` of 0 uses `DEFAULT_CONFIRMATION_TIMEOUT`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn wait_eth_tx_confirmations(
+    c_network: *const c_char,
+    c_tx_hash: *const c_char,
+    c_confirmations: u32,
+    c_timeout_secs: u32,
+) -> *mut c_char {
+    let network = match get_eth_network_from_raw(c_network) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let tx_hash = match get_tx_hash_from_raw(c_tx_hash) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let timeout = match c_timeout_secs {
+        0 => DEFAULT_CONFIRMATION_TIMEOUT,
+        secs => Duration::from_secs(secs as u64),
+    };
+
+    receipt_report_to_c_string(wait_for_eth_tx_confirmations(
+        &network,
+        tx_hash,
+        c_confirmations as u64,
+        timeout,
+    ))
+}
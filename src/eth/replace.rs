@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use web3::types::{BlockNumber, TransactionId, H256, U256};
+
+use crate::dto::ecdsa::{MKPosDto, PrivateShare};
+use crate::dto::eth::EthTxRecord;
+use crate::eth::address::EthAddress;
+use crate::eth::network::EthNetwork;
+use crate::eth::raw_tx::a_sign_tx;
+use crate::eth::transaction::{Transaction, LEGACY_TX_ID};
+use crate::eth::transport::get_connection;
+use crate::eth::utils::get_pos_mk;
+use crate::utilities::a_requests::AsyncClientShim;
+use crate::utilities::err_handling::{error_to_c_string, ErrorFFIKind};
+use crate::utilities::ffi::ffi_utils::{
+    get_addresses_derivation_map_from_raw, get_client_shim_from_raw, get_eth_network_from_raw,
+    get_private_share_from_raw, get_str_from_c_char,
+};
+use crate::utilities::requests::ClientShim;
+use crate::utilities::runtime::block_on;
+
+/// Nodes only accept a replacement whose fees are at least this much higher than the original's.
+pub const MIN_FEE_BUMP_PERCENT: u64 = 10;
+pub const CANCEL_TX_GAS: u64 = 21000;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub enum EthTxReplacement {
+    /// Same transaction, higher fees.
+    SpeedUp,
+    /// Zero-value transfer to self, higher fees.
+    Cancel,
+}
+
+/// Fees of a replacement, `None` meaning the minimum accepted bump of the original fee.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy, Default)]
+pub struct EthReplacementFees {
+    pub max_fee_per_gas: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+}
+
+pub fn min_bumped_fee(fee: U256) -> U256 {
+    let bumped = fee * (100 + MIN_FEE_BUMP_PERCENT);
+    (bumped + 99) / 100
+}
+
+/// Build the transaction replacing `original`, reusing its nonce.
+pub fn build_replacement_tx(
+    original: &EthTxRecord,
+    replacement: EthTxReplacement,
+    fees: &EthReplacementFees,
+) -> Result<Transaction> {
+    let min_fee = min_bumped_fee(original.gas_price);
+    let gas_price = fees.max_fee_per_gas.unwrap_or(min_fee);
+    if gas_price < min_fee {
+        return Err(anyhow!(
+            "max fee per gas {} must be at least {} ({}% above the original {})",
+            gas_price,
+            min_fee,
+            MIN_FEE_BUMP_PERCENT,
+            original.gas_price
+        ));
+    }
+
+    let max_priority_fee_per_gas = match original.transaction_type.map(|t| t.as_u64()) {
+        Some(LEGACY_TX_ID) | None => original.max_priority_fee_per_gas,
+        _ => {
+            let min_tip = min_bumped_fee(original.max_priority_fee_per_gas);
+            let tip = fees.max_priority_fee_per_gas.unwrap_or(min_tip);
+            if tip < min_tip {
+                return Err(anyhow!(
+                    "max priority fee per gas {} must be at least {} ({}% above the original {})",
+                    tip,
+                    min_tip,
+                    MIN_FEE_BUMP_PERCENT,
+                    original.max_priority_fee_per_gas
+                ));
+            }
+            if tip > gas_price {
+                return Err(anyhow!(
+                    "max priority fee per gas {} is above max fee per gas {}",
+                    tip,
+                    gas_price
+                ));
+            }
+            tip
+        }
+    };
+
+    let tx = match replacement {
+        EthTxReplacement::SpeedUp => Transaction {
+            to: original.to,
+            nonce: original.nonce,
+            gas: original.gas,
+            gas_price,
+            value: original.value,
+            data: original.data.0.clone(),
+            transaction_type: original.transaction_type,
+            access_list: original.access_list.clone(),
+            max_priority_fee_per_gas,
+        },
+        EthTxReplacement::Cancel => Transaction {
+            to: Some(original.from),
+            nonce: original.nonce,
+            gas: U256::from(CANCEL_TX_GAS),
+            gas_price,
+            value: U256::zero(),
+            data: vec![],
+            transaction_type: original.transaction_type,
+            access_list: vec![],
+            max_priority_fee_per_gas,
+        },
+    };
+    Ok(tx)
+}
+
+/// Re-sign `original` with the same nonce and higher fees, or cancel it.
+///
+/// Fails if the network already mined a transaction with that nonce.
+pub fn replace_tx(
+    original: &EthTxRecord,
+    replacement: EthTxReplacement,
+    fees: &EthReplacementFees,
+    network: &EthNetwork,
+    client_shim: &ClientShim,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<EthAddress, MKPosDto>,
+) -> Result<EthTxRecord> {
+    block_on(a_replace_tx(
+        original,
+        replacement,
+        fees,
+        network,
+        client_shim,
+        private_share,
+        addresses_derivation_map,
+    ))
+}
+
+pub async fn a_replace_tx(
+    original: &EthTxRecord,
+    replacement: EthTxReplacement,
+    fees: &EthReplacementFees,
+    network: &EthNetwork,
+    client_shim: &AsyncClientShim,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<EthAddress, MKPosDto>,
+) -> Result<EthTxRecord> {
+    if original.chain_id != network.chain_id {
+        return Err(anyhow!(
+            "transaction {:?} was sent on chain id {}, not on network {} (chain id {})",
+            original.tx_hash,
+            original.chain_id,
+            network.name,
+            network.chain_id
+        ));
+    }
+    if let Some(replaced_by) = original.replaced_by {
+        return Err(anyhow!(
+            "transaction {:?} was already replaced by {:?}",
+            original.tx_hash,
+            replaced_by
+        ));
+    }
+
+    let pos_mk = get_pos_mk(
+        &EthAddress::from(original.from).to_string(),
+        addresses_derivation_map,
+    )?;
+    let tx = build_replacement_tx(original, replacement, fees)?;
+
+    let connection = get_connection(network.rpc_url()?).await?;
+    network.check_chain_id(connection.web3()).await?;
+    let mined_nonce = connection
+        .web3()
+        .eth()
+        .transaction_count(original.from, Some(BlockNumber::Latest))
+        .await?;
+    if mined_nonce > original.nonce {
+        return Err(anyhow!(
+            "nonce {} of {} is already mined, transaction {:?} can no longer be replaced",
+            original.nonce,
+            EthAddress::from(original.from),
+            original.tx_hash
+        ));
+    }
+
+    // Broadcast through the node, the server only knows its own chain
+    let (raw_tx, record) =
+        a_sign_tx(tx, original.chain_id, pos_mk, client_shim, private_share).await?;
+    connection.web3().eth().send_raw_transaction(raw_tx).await?;
+    Ok(EthTxRecord {
+        replaces: Some(original.tx_hash),
+        ..record
+    })
+}
+
+/// Rebuild the record of a pending transaction from the node, for transactions that were not
+/// tracked locally.
+pub fn fetch_tx_record(network: &EthNetwork, tx_hash: H256) -> Result<EthTxRecord> {
+    block_on(a_fetch_tx_record(network, tx_hash))
+}
+
+pub async fn a_fetch_tx_record(network: &EthNetwork, tx_hash: H256) -> Result<EthTxRecord> {
+    let connection = get_connection(network.rpc_url()?).await?;
+    let tx = connection
+        .web3()
+        .eth()
+        .transaction(TransactionId::Hash(tx_hash))
+        .await?;
+
+    let tx = match tx {
+        Some(tx) => tx,
+        None => return Err(anyhow!("transaction {:?} not found", tx_hash)),
+    };
+    if tx.block_number.is_some() {
+        return Err(anyhow!("transaction {:?} is already mined", tx_hash));
+    }
+    let from = match tx.from {
+        Some(from) => from,
+        None => return Err(anyhow!("transaction {:?} has no sender", tx_hash)),
+    };
+
+    let (gas_price, max_priority_fee_per_gas) = match (tx.max_fee_per_gas, tx.gas_price) {
+        (Some(max_fee), _) => (max_fee, tx.max_priority_fee_per_gas.unwrap_or_default()),
+        (None, Some(gas_price)) => (gas_price, U256::zero()),
+        (None, None) => return Err(anyhow!("transaction {:?} has no gas price", tx_hash)),
+    };
+
+    Ok(EthTxRecord {
+        tx_hash,
+        chain_id: network.chain_id,
+        from,
+        to: tx.to,
+        nonce: tx.nonce,
+        gas: tx.gas,
+        gas_price,
+        max_priority_fee_per_gas,
+        value: tx.value,
+        data: tx.input,
+        transaction_type: tx.transaction_type,
+        access_list: tx.access_list.unwrap_or_default(),
+        replaces: None,
+        replaced_by: None,
+    })
+}
+
+fn parse_fee(fee: &str) -> Result<Option<U256>> {
+    if fee.is_empty() {
+        return Ok(None);
+    }
+    match U256::from_dec_str(fee) {
+        Ok(fee) => Ok(Some(fee)),
+        Err(e) => Err(anyhow!("invalid fee {}: {:?}", fee, e)),
+    }
+}
+
+/// FFI entry point shared by `speed_up_eth_tx` and `cancel_eth_tx`. Fees are given in wei as
+/// decimal strings, empty for the minimum bump.
+#[allow(clippy::too_many_arguments)]
+fn replace_eth_tx(
+    replacement: EthTxReplacement,
+    c_endpoint: *const c_char,
+    c_auth_token: *const c_char,
+    c_user_id: *const c_char,
+    c_network: *const c_char,
+    c_tx_hash: *const c_char,
+    c_max_fee_per_gas: *const c_char,
+    c_max_priority_fee_per_gas: *const c_char,
+    c_private_share_json: *const c_char,
+    c_addresses_derivation_map: *const c_char,
+) -> *mut c_char {
+    let tx_hash = match get_str_from_c_char(c_tx_hash, "tx_hash") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let max_fee_per_gas = match get_str_from_c_char(c_max_fee_per_gas, "max_fee_per_gas") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let max_priority_fee_per_gas =
+        match get_str_from_c_char(c_max_priority_fee_per_gas, "max_priority_fee_per_gas") {
+            Ok(s) => s,
+            Err(e) => return error_to_c_string(e),
+        };
+
+    let client_shim = match get_client_shim_from_raw(c_endpoint, c_auth_token, c_user_id) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let network = match get_eth_network_from_raw(c_network) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let private_share = match get_private_share_from_raw(c_private_share_json) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let addresses_derivation_map =
+        match get_addresses_derivation_map_from_raw(c_addresses_derivation_map) {
+            Ok(s) => s,
+            Err(e) => return error_to_c_string(e),
+        };
+
+    let record = (|| {
+        let fees = EthReplacementFees {
+            max_fee_per_gas: parse_fee(&max_fee_per_gas)?,
+            max_priority_fee_per_gas: parse_fee(&max_priority_fee_per_gas)?,
+        };
+        let original = fetch_tx_record(&network, H256::from_str(&tx_hash)?)?;
+        replace_tx(
+            &original,
+            replacement,
+            &fees,
+            &network,
+            &client_shim,
+            &private_share,
+            &addresses_derivation_map,
+        )
+    })();
+
+    let record = match record {
+        Ok(r) => r,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E103 {
+                msg: "replace_tx".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let record_json = match serde_json::to_string(&record) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E102 {
+                msg: "tx_record".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(record_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "tx_record".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn speed_up_eth_tx(
+    c_endpoint: *const c_char,
+    c_auth_token: *const c_char,
+    c_user_id: *const c_char,
+    c_network: *const c_char,
+    c_tx_hash: *const c_char,
+    c_max_fee_per_gas: *const c_char,
+    c_max_priority_fee_per_gas: *const c_char,
+    c_private_share_json: *const c_char,
+    c_addresses_derivation_map: *const c_char,
+) -> *mut c_char {
+    replace_eth_tx(
+        EthTxReplacement::SpeedUp,
+        c_endpoint,
+        c_auth_token,
+        c_user_id,
+        c_network,
+        c_tx_hash,
+        c_max_fee_per_gas,
+        c_max_priority_fee_per_gas,
+        c_private_share_json,
+        c_addresses_derivation_map,
+    )
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn cancel_eth_tx(
+    c_endpoint: *const c_char,
+    c_auth_token: *const c_char,
+    c_user_id: *const c_char,
+    c_network: *const c_char,
+    c_tx_hash: *const c_char,
+    c_max_fee_per_gas: *const c_char,
+    c_max_priority_fee_per_gas: *const c_char,
+    c_private_share_json: *const c_char,
+    c_addresses_derivation_map: *const c_char,
+) -> *mut c_char {
+    replace_eth_tx(
+        EthTxReplacement::Cancel,
+        c_endpoint,
+        c_auth_token,
+        c_user_id,
+        c_network,
+        c_tx_hash,
+        c_max_fee_per_gas,
+        c_max_priority_fee_per_gas,
+        c_private_share_json,
+        c_addresses_derivation_map,
+    )
+}
@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use serde_json::json;
+use web3::ethabi::Token;
+use web3::types::{Address, Bytes, U256};
+
+use crate::dto::ecdsa::{MKPosDto, PrivateShare};
+use crate::eth::abi::{call, encode_call};
+use crate::eth::address::EthAddress;
+use crate::eth::message::sign_hash;
+use crate::eth::network::EthNetwork;
+use crate::eth::typed_data::TypedData;
+use crate::eth::utils::{establish_web3_connection, get_pos_mk, pubkey_to_eth_address};
+use crate::utilities::err_handling::{error_to_c_string, ErrorFFIKind};
+use crate::utilities::ffi::ffi_utils::{
+    get_addresses_derivation_map_from_raw, get_client_shim_from_raw, get_eth_network_from_raw,
+    get_private_share_from_raw, get_str_from_c_char,
+};
+use crate::utilities::requests::ClientShim;
+
+pub const SAFE_CALL: u8 = 0;
+pub const SAFE_DELEGATE_CALL: u8 = 1;
+
+/// A transaction of a Safe multisig (v1.3+), in the format of the Safe transaction service.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeTx {
+    pub to: Address,
+    pub value: U256,
+    pub data: Bytes,
+    pub operation: u8,
+    pub safe_tx_gas: U256,
+    pub base_gas: U256,
+    pub gas_price: U256,
+    pub gas_token: Address,
+    pub refund_receiver: Address,
+    pub nonce: U256,
+}
+
+/// Signature of one Safe owner over a `SafeTx` hash.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct SafeSignature {
+    pub owner: Address,
+    pub signature: Bytes,
+}
+
+impl SafeTx {
+    /// The EIP-712 typed data Safe contracts sign, for the Safe at `safe` on `chain_id`.
+    pub fn to_typed_data(&self, safe: Address, chain_id: u64) -> Result<TypedData> {
+        let typed_data = json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" }
+                ],
+                "SafeTx": [
+                    { "name": "to", "type": "address" },
+                    { "name": "value", "type": "uint256" },
+                    { "name": "data", "type": "bytes" },
+                    { "name": "operation", "type": "uint8" },
+                    { "name": "safeTxGas", "type": "uint256" },
+                    { "name": "baseGas", "type": "uint256" },
+                    { "name": "gasPrice", "type": "uint256" },
+                    { "name": "gasToken", "type": "address" },
+                    { "name": "refundReceiver", "type": "address" },
+                    { "name": "nonce", "type": "uint256" }
+                ]
+            },
+            "primaryType": "SafeTx",
+            "domain": {
+                "chainId": chain_id,
+                "verifyingContract": safe
+            },
+            "message": self
+        });
+        Ok(serde_json::from_value(typed_data)?)
+    }
+
+    /// `Safe.getTransactionHash`, the hash every owner signs.
+    pub fn hash(&self, safe: Address, chain_id: u64) -> Result<[u8; 32]> {
+        self.to_typed_data(safe, chain_id)?.signing_hash()
+    }
+
+    /// `execTransaction` calldata carrying at least `threshold` owner signatures.
+    pub fn exec_transaction_data(
+        &self,
+        signatures: &[SafeSignature],
+        threshold: usize,
+    ) -> Result<Bytes> {
+        let signatures = encode_signatures(signatures)?;
+        let owners = signatures.0.len() / 65;
+        if owners < threshold {
+            return Err(anyhow!(
+                "{} owner signatures collected, the Safe threshold is {}",
+                owners,
+                threshold
+            ));
+        }
+
+        Ok(encode_call(
+            "execTransaction(address,uint256,bytes,uint8,uint256,uint256,uint256,address,address,bytes)",
+            &[
+                Token::Address(self.to),
+                Token::Uint(self.value),
+                Token::Bytes(self.data.0.clone()),
+                Token::Uint(U256::from(self.operation)),
+                Token::Uint(self.safe_tx_gas),
+                Token::Uint(self.base_gas),
+                Token::Uint(self.gas_price),
+                Token::Address(self.gas_token),
+                Token::Address(self.refund_receiver),
+                Token::Bytes(signatures.0),
+            ],
+        )
+        .into())
+    }
+}
+
+/// Concatenate owner signatures in the ascending owner order Safe contracts require.
+pub fn encode_signatures(signatures: &[SafeSignature]) -> Result<Bytes> {
+    let mut signatures = signatures.to_vec();
+    signatures.sort_by_key(|s| s.owner);
+    signatures.dedup_by_key(|s| s.owner);
+
+    let mut encoded = Vec::new();
+    for signature in signatures {
+        if signature.signature.0.len() != 65 {
+            return Err(anyhow!(
+                "signature of owner {} must be 65 bytes",
+                EthAddress::from(signature.owner)
+            ));
+        }
+        encoded.extend(signature.signature.0);
+    }
+    Ok(encoded.into())
+}
+
+/// Sign `safe_tx` as the owner `from`. Safe accepts the raw `SafeTx` hash signed with
+/// v = 27/28, which is what `sign_hash` returns.
+pub fn sign_safe_tx(
+    from: &str,
+    safe_tx: &SafeTx,
+    safe: Address,
+    chain_id: u64,
+    client_shim: &ClientShim,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<EthAddress, MKPosDto>,
+) -> Result<SafeSignature> {
+    let pos_mk = get_pos_mk(from, addresses_derivation_map)?;
+    let signature = sign_hash(
+        safe_tx.hash(safe, chain_id)?,
+        pos_mk,
+        client_shim,
+        private_share,
+    )?;
+
+    Ok(SafeSignature {
+        owner: pubkey_to_eth_address(&pos_mk.mk),
+        signature,
+    })
+}
+
+/// Current nonce and threshold of the Safe at `safe`.
+pub async fn get_safe_nonce_and_threshold(
+    network: &EthNetwork,
+    safe: Address,
+) -> Result<(U256, usize)> {
+    let web3_connection = establish_web3_connection(network.rpc_url()?).await?;
+    let nonce = call(&web3_connection, safe, encode_call("nonce()", &[])).await?;
+    let threshold = call(&web3_connection, safe, encode_call("getThreshold()", &[])).await?;
+    if nonce.0.len() != 32 || threshold.0.len() != 32 {
+        return Err(anyhow!("{} is not a Safe", EthAddress::from(safe)));
+    }
+    Ok((
+        U256::from_big_endian(&nonce.0),
+        U256::from_big_endian(&threshold.0).as_usize(),
+    ))
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn eth_sign_safe_tx(
+    c_endpoint: *const c_char,
+    c_auth_token: *const c_char,
+    c_user_id: *const c_char,
+    c_from_address: *const c_char,
+    c_safe_tx_json: *const c_char,
+    c_safe_address: *const c_char,
+    c_network: *const c_char,
+    c_private_share_json: *const c_char,
+    c_addresses_derivation_map: *const c_char,
+) -> *mut c_char {
+    let from_address = match get_str_from_c_char(c_from_address, "from_address") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let safe_tx_json = match get_str_from_c_char(c_safe_tx_json, "safe_tx") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let safe_address = match get_str_from_c_char(c_safe_address, "safe_address") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let client_shim = match get_client_shim_from_raw(c_endpoint, c_auth_token, c_user_id) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let network = match get_eth_network_from_raw(c_network) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let private_share = match get_private_share_from_raw(c_private_share_json) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let addresses_derivation_map =
+        match get_addresses_derivation_map_from_raw(c_addresses_derivation_map) {
+            Ok(s) => s,
+            Err(e) => return error_to_c_string(e),
+        };
+
+    let safe_tx: SafeTx = match serde_json::from_str(&safe_tx_json) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E104 {
+                msg: "safe_tx".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let signature = EthAddress::from_str(&safe_address).and_then(|safe| {
+        sign_safe_tx(
+            &from_address,
+            &safe_tx,
+            safe.into(),
+            network.chain_id,
+            &client_shim,
+            &private_share,
+            &addresses_derivation_map,
+        )
+    });
+
+    let signature = match signature {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E103 {
+                msg: "safe_signature".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let signature_json = match serde_json::to_string(&signature) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E102 {
+                msg: "safe_signature".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(signature_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "safe_signature".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
+
+/// `execTransaction` calldata from a `SafeTx` and a JSON array of `SafeSignature`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn eth_safe_exec_transaction_data(
+    c_safe_tx_json: *const c_char,
+    c_signatures_json: *const c_char,
+    c_threshold: u32,
+) -> *mut c_char {
+    let safe_tx_json = match get_str_from_c_char(c_safe_tx_json, "safe_tx") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let signatures_json = match get_str_from_c_char(c_signatures_json, "signatures") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let safe_tx: SafeTx = match serde_json::from_str(&safe_tx_json) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E104 {
+                msg: "safe_tx".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let signatures: Vec<SafeSignature> = match serde_json::from_str(&signatures_json) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E104 {
+                msg: "signatures".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let data = match safe_tx.exec_transaction_data(&signatures, c_threshold as usize) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E103 {
+                msg: "exec_transaction_data".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let data_json = match serde_json::to_string(&data) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E102 {
+                msg: "exec_transaction_data".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(data_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "exec_transaction_data".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::os::raw::c_char;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, FixedOffset, SecondsFormat, Utc};
+use web3::types::{Address, Bytes};
+
+use crate::dto::ecdsa::{MKPosDto, PrivateShare};
+use crate::eth::address::EthAddress;
+use crate::eth::message::{
+    personal_sign, signature_to_c_string, verification_to_c_string, verify_personal_sign,
+};
+use crate::utilities::err_handling::error_to_c_string;
+use crate::utilities::ffi::ffi_utils::{
+    get_addresses_derivation_map_from_raw, get_client_shim_from_raw, get_private_share_from_raw,
+    get_str_from_c_char,
+};
+use crate::utilities::requests::ClientShim;
+
+pub const SIWE_VERSION: &str = "1";
+const PREAMBLE: &str = " wants you to sign in with your Ethereum account:";
+const URI_TAG: &str = "URI: ";
+const VERSION_TAG: &str = "Version: ";
+const CHAIN_ID_TAG: &str = "Chain ID: ";
+const NONCE_TAG: &str = "Nonce: ";
+const ISSUED_AT_TAG: &str = "Issued At: ";
+const EXPIRATION_TIME_TAG: &str = "Expiration Time: ";
+const NOT_BEFORE_TAG: &str = "Not Before: ";
+const REQUEST_ID_TAG: &str = "Request ID: ";
+const RESOURCES_TAG: &str = "Resources:";
+
+/// A Sign-In with Ethereum (EIP-4361) message. Timestamps are kept as given so that a parsed
+/// message renders to the exact text that was signed.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct SiweMessage {
+    pub scheme: Option<String>,
+    pub domain: String,
+    pub address: EthAddress,
+    pub statement: Option<String>,
+    pub uri: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub nonce: String,
+    pub issued_at: String,
+    pub expiration_time: Option<String>,
+    pub not_before: Option<String>,
+    pub request_id: Option<String>,
+    pub resources: Vec<String>,
+}
+
+impl SiweMessage {
+    /// A message issued now, without statement, expiry or resources.
+    pub fn new(
+        domain: &str,
+        address: Address,
+        uri: &str,
+        chain_id: u64,
+        nonce: &str,
+    ) -> SiweMessage {
+        SiweMessage {
+            scheme: None,
+            domain: domain.to_owned(),
+            address: address.into(),
+            statement: None,
+            uri: uri.to_owned(),
+            version: SIWE_VERSION.to_owned(),
+            chain_id,
+            nonce: nonce.to_owned(),
+            issued_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            expiration_time: None,
+            not_before: None,
+            request_id: None,
+            resources: vec![],
+        }
+    }
+
+    pub fn with_statement(mut self, statement: &str) -> SiweMessage {
+        self.statement = Some(statement.to_owned());
+        self
+    }
+
+    pub fn with_expiration_time(mut self, expiration_time: DateTime<Utc>) -> SiweMessage {
+        self.expiration_time = Some(expiration_time.to_rfc3339_opts(SecondsFormat::Secs, true));
+        self
+    }
+
+    pub fn with_not_before(mut self, not_before: DateTime<Utc>) -> SiweMessage {
+        self.not_before = Some(not_before.to_rfc3339_opts(SecondsFormat::Secs, true));
+        self
+    }
+
+    pub fn with_request_id(mut self, request_id: &str) -> SiweMessage {
+        self.request_id = Some(request_id.to_owned());
+        self
+    }
+
+    pub fn with_resource(mut self, resource: &str) -> SiweMessage {
+        self.resources.push(resource.to_owned());
+        self
+    }
+
+    /// Check the fields against the EIP-4361 grammar.
+    pub fn validate(&self) -> Result<()> {
+        if self.domain.is_empty() || self.domain.contains(char::is_whitespace) {
+            return Err(anyhow!("invalid SIWE domain: {:?}", self.domain));
+        }
+        if let Some(statement) = &self.statement {
+            if statement.contains('\n') {
+                return Err(anyhow!("SIWE statement must be a single line"));
+            }
+        }
+        if self.version != SIWE_VERSION {
+            return Err(anyhow!("unsupported SIWE version: {}", self.version));
+        }
+        if self.nonce.len() < 8 || !self.nonce.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(anyhow!(
+                "SIWE nonce must be at least 8 alphanumeric characters"
+            ));
+        }
+        parse_time(&self.issued_at)?;
+        if let Some(t) = &self.expiration_time {
+            parse_time(t)?;
+        }
+        if let Some(t) = &self.not_before {
+            parse_time(t)?;
+        }
+        Ok(())
+    }
+
+    /// Whether `now` lies between `Not Before` and `Expiration Time`.
+    pub fn is_valid_at(&self, now: DateTime<Utc>) -> Result<bool> {
+        if let Some(t) = &self.expiration_time {
+            if now >= parse_time(t)? {
+                return Ok(false);
+            }
+        }
+        if let Some(t) = &self.not_before {
+            if now < parse_time(t)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Verify `signature` over this message, and that it is meant for `domain` with `nonce`
+    /// and currently valid.
+    pub fn verify(&self, signature: &[u8], domain: &str, nonce: &str) -> Result<bool> {
+        self.validate()?;
+        if self.domain != domain || self.nonce != nonce || !self.is_valid_at(Utc::now())? {
+            return Ok(false);
+        }
+        verify_personal_sign(self.address.into(), self.to_string().as_bytes(), signature)
+    }
+}
+
+fn parse_time(time: &str) -> Result<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(time)
+        .map_err(|e| anyhow!("invalid SIWE timestamp {}: {}", time, e))
+}
+
+impl fmt::Display for SiweMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(scheme) = &self.scheme {
+            write!(f, "{}://", scheme)?;
+        }
+        writeln!(f, "{}{}", self.domain, PREAMBLE)?;
+        writeln!(f, "{}", self.address)?;
+        writeln!(f)?;
+        if let Some(statement) = &self.statement {
+            writeln!(f, "{}", statement)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{}{}", URI_TAG, self.uri)?;
+        writeln!(f, "{}{}", VERSION_TAG, self.version)?;
+        writeln!(f, "{}{}", CHAIN_ID_TAG, self.chain_id)?;
+        writeln!(f, "{}{}", NONCE_TAG, self.nonce)?;
+        write!(f, "{}{}", ISSUED_AT_TAG, self.issued_at)?;
+        if let Some(t) = &self.expiration_time {
+            write!(f, "\n{}{}", EXPIRATION_TIME_TAG, t)?;
+        }
+        if let Some(t) = &self.not_before {
+            write!(f, "\n{}{}", NOT_BEFORE_TAG, t)?;
+        }
+        if let Some(id) = &self.request_id {
+            write!(f, "\n{}{}", REQUEST_ID_TAG, id)?;
+        }
+        if !self.resources.is_empty() {
+            write!(f, "\n{}", RESOURCES_TAG)?;
+            for resource in &self.resources {
+                write!(f, "\n- {}", resource)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SiweMessage {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.split('\n');
+        let mut next_line = |what: &str| match lines.next() {
+            Some(line) => Ok(line),
+            None => Err(anyhow!("SIWE message ends before {}", what)),
+        };
+
+        let header = next_line("the preamble")?;
+        let origin = match header.strip_suffix(PREAMBLE) {
+            Some(origin) => origin,
+            None => return Err(anyhow!("invalid SIWE preamble: {}", header)),
+        };
+        let (scheme, domain) = match origin.split_once("://") {
+            Some((scheme, domain)) => (Some(scheme.to_owned()), domain.to_owned()),
+            None => (None, origin.to_owned()),
+        };
+
+        let address = EthAddress::from_str(next_line("the address")?)?;
+        if !next_line("the statement")?.is_empty() {
+            return Err(anyhow!("expected an empty line after the SIWE address"));
+        }
+        let statement = match next_line("the statement")? {
+            "" => None,
+            statement => {
+                if !next_line("the URI")?.is_empty() {
+                    return Err(anyhow!("expected an empty line after the SIWE statement"));
+                }
+                Some(statement.to_owned())
+            }
+        };
+
+        let uri = tagged(next_line("the URI")?, URI_TAG)?.to_owned();
+        let version = tagged(next_line("the version")?, VERSION_TAG)?.to_owned();
+        let chain_id = tagged(next_line("the chain id")?, CHAIN_ID_TAG)?.parse()?;
+        let nonce = tagged(next_line("the nonce")?, NONCE_TAG)?.to_owned();
+        let issued_at = tagged(next_line("the issued-at time")?, ISSUED_AT_TAG)?.to_owned();
+
+        let mut message = SiweMessage {
+            scheme,
+            domain,
+            address,
+            statement,
+            uri,
+            version,
+            chain_id,
+            nonce,
+            issued_at,
+            expiration_time: None,
+            not_before: None,
+            request_id: None,
+            resources: vec![],
+        };
+
+        let mut line = lines.next();
+        if let Some(t) = line.and_then(|l| l.strip_prefix(EXPIRATION_TIME_TAG)) {
+            message.expiration_time = Some(t.to_owned());
+            line = lines.next();
+        }
+        if let Some(t) = line.and_then(|l| l.strip_prefix(NOT_BEFORE_TAG)) {
+            message.not_before = Some(t.to_owned());
+            line = lines.next();
+        }
+        if let Some(id) = line.and_then(|l| l.strip_prefix(REQUEST_ID_TAG)) {
+            message.request_id = Some(id.to_owned());
+            line = lines.next();
+        }
+        if line == Some(RESOURCES_TAG) {
+            line = lines.next();
+            while let Some(resource) = line.and_then(|l| l.strip_prefix("- ")) {
+                message.resources.push(resource.to_owned());
+                line = lines.next();
+            }
+        }
+        if let Some(line) = line {
+            return Err(anyhow!("unexpected line in SIWE message: {}", line));
+        }
+
+        message.validate()?;
+        Ok(message)
+    }
+}
+
+fn tagged<'a>(line: &'a str, tag: &str) -> Result<&'a str> {
+    match line.strip_prefix(tag) {
+        Some(value) => Ok(value),
+        None => Err(anyhow!(
+            "expected SIWE field {:?}, got: {}",
+            tag.trim(),
+            line
+        )),
+    }
+}
+
+/// Sign a SIWE message with the derived address it names, returning the EIP-191 signature.
+pub fn sign_siwe_message(
+    message: &SiweMessage,
+    client_shim: &ClientShim,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<EthAddress, MKPosDto>,
+) -> Result<Bytes> {
+    message.validate()?;
+    if !message.is_valid_at(Utc::now())? {
+        return Err(anyhow!("SIWE message is expired or not yet valid"));
+    }
+    personal_sign(
+        &message.address.to_string(),
+        message.to_string().as_bytes(),
+        client_shim,
+        private_share,
+        addresses_derivation_map,
+    )
+}
+
+/// Sign the SIWE message text `c_message` with the address it names.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn eth_sign_siwe_message(
+    c_endpoint: *const c_char,
+    c_auth_token: *const c_char,
+    c_user_id: *const c_char,
+    c_message: *const c_char,
+    c_private_share_json: *const c_char,
+    c_addresses_derivation_map: *const c_char,
+) -> *mut c_char {
+    let message = match get_str_from_c_char(c_message, "message") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let client_shim = match get_client_shim_from_raw(c_endpoint, c_auth_token, c_user_id) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let private_share = match get_private_share_from_raw(c_private_share_json) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let addresses_derivation_map =
+        match get_addresses_derivation_map_from_raw(c_addresses_derivation_map) {
+            Ok(s) => s,
+            Err(e) => return error_to_c_string(e),
+        };
+
+    signature_to_c_string(SiweMessage::from_str(&message).and_then(|message| {
+        sign_siwe_message(
+            &message,
+            &client_shim,
+            &private_share,
+            &addresses_derivation_map,
+        )
+    }))
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn eth_verify_siwe_message(
+    c_message: *const c_char,
+    c_signature: *const c_char,
+    c_domain: *const c_char,
+    c_nonce: *const c_char,
+) -> *mut c_char {
+    let message = match get_str_from_c_char(c_message, "message") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let signature = match get_str_from_c_char(c_signature, "signature") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let domain = match get_str_from_c_char(c_domain, "domain") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let nonce = match get_str_from_c_char(c_nonce, "nonce") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    verification_to_c_string((|| {
+        let message = SiweMessage::from_str(&message)?;
+        let signature = hex::decode(signature.trim_start_matches("0x"))?;
+        message.verify(&signature, &domain, &nonce)
+    })())
+}
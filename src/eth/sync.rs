@@ -0,0 +1,363 @@
+use std::cmp::min;
+use std::collections::{HashMap, HashSet};
+use std::ffi::CString;
+use std::os::raw::c_char;
+
+use anyhow::{anyhow, Result};
+use futures::{stream, StreamExt, TryStreamExt};
+use web3::signing::keccak256;
+use web3::types::{Address, BlockId, BlockNumber, FilterBuilder, Log, H256, U256, U64};
+use web3::Web3;
+
+use crate::dto::ecdsa::MKPosDto;
+use crate::dto::eth::{EthTransfer, EthTransferHistory, EthTransferKind};
+use crate::eth::address::EthAddress;
+use crate::eth::network::EthNetwork;
+use crate::eth::transport::{get_connection, EthConnection, EthTransport};
+use crate::utilities::err_handling::{error_to_c_string, ErrorFFIKind};
+use crate::utilities::ffi::ffi_utils::{
+    get_addresses_derivation_map_from_raw, get_eth_network_from_raw, get_str_from_c_char,
+};
+use crate::utilities::runtime::block_on;
+
+pub const TRANSFER_EVENT: &str = "Transfer(address,address,uint256)";
+/// Widest block range of one `eth_getLogs` request, most providers refuse more.
+pub const LOG_BLOCK_RANGE: u64 = 2000;
+/// Blocks scanned again on every sync, so that transfers undone by a reorg disappear.
+pub const REORG_DEPTH: u64 = 12;
+/// How far back the first sync of a chain goes.
+pub const DEFAULT_SYNC_BLOCKS: u64 = 1000;
+const BLOCK_FETCH_CONCURRENCY: usize = 8;
+
+type TransferKey = (H256, Option<U256>);
+
+fn transfer_key(transfer: &EthTransfer) -> TransferKey {
+    (transfer.tx_hash, transfer.log_index)
+}
+
+fn address_topic(address: Address) -> H256 {
+    let mut topic = H256::zero();
+    topic.0[12..].copy_from_slice(address.as_bytes());
+    topic
+}
+
+/// The ERC-20 `Transfer` carried by `log`, if any. ERC-721 transfers share the event
+/// signature but also index the token id, they are skipped.
+pub fn parse_erc20_transfer(log: &Log) -> Option<EthTransfer> {
+    if log.topics.len() != 3
+        || log.topics[0] != H256::from(keccak256(TRANSFER_EVENT.as_bytes()))
+        || log.data.0.len() != 32
+        || log.removed == Some(true)
+    {
+        return None;
+    }
+
+    Some(EthTransfer {
+        tx_hash: log.transaction_hash?,
+        log_index: log.log_index,
+        block_number: log.block_number?,
+        kind: EthTransferKind::Erc20,
+        token: Some(log.address),
+        from: Address::from_slice(&log.topics[1].0[12..]),
+        to: Address::from_slice(&log.topics[2].0[12..]),
+        value: U256::from_big_endian(&log.data.0),
+        confirmations: 0,
+    })
+}
+
+async fn get_erc20_transfers(
+    web3_connection: &Web3<EthTransport>,
+    addresses: &[Address],
+    from_block: U64,
+    to_block: U64,
+) -> Result<Vec<EthTransfer>> {
+    // An empty topic list matches any address on most nodes
+    if addresses.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let transfer_topic = H256::from(keccak256(TRANSFER_EVENT.as_bytes()));
+    let address_topics: Vec<H256> = addresses.iter().map(|a| address_topic(*a)).collect();
+
+    // Topics are AND-ed across positions, sent and received transfers need a filter each
+    let mut transfers = Vec::new();
+    for (senders, receivers) in [
+        (Some(address_topics.clone()), None),
+        (None, Some(address_topics)),
+    ] {
+        let filter = FilterBuilder::default()
+            .from_block(BlockNumber::Number(from_block))
+            .to_block(BlockNumber::Number(to_block))
+            .topics(Some(vec![transfer_topic]), senders, receivers, None)
+            .build();
+        let logs = web3_connection.eth().logs(filter).await?;
+        transfers.extend(logs.iter().filter_map(parse_erc20_transfer));
+    }
+    Ok(transfers)
+}
+
+/// Successful top-level transactions moving native currency from or to `addresses`.
+/// Transfers made by contracts (internal transactions) are not visible this way.
+async fn get_native_transfers(
+    web3_connection: &Web3<EthTransport>,
+    addresses: &HashSet<Address>,
+    from_block: U64,
+    to_block: U64,
+) -> Result<Vec<EthTransfer>> {
+    let blocks: Vec<_> = stream::iter(from_block.as_u64()..=to_block.as_u64())
+        .map(|n| {
+            web3_connection
+                .eth()
+                .block_with_txs(BlockId::Number(BlockNumber::Number(n.into())))
+        })
+        .buffered(BLOCK_FETCH_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    let mut transfers = Vec::new();
+    for block in blocks.into_iter().flatten() {
+        let block_number = match block.number {
+            Some(n) => n,
+            None => continue,
+        };
+        for tx in block.transactions {
+            let (from, to) = match (tx.from, tx.to) {
+                (Some(from), Some(to)) => (from, to),
+                _ => continue,
+            };
+            if tx.value.is_zero() || !(addresses.contains(&from) || addresses.contains(&to)) {
+                continue;
+            }
+
+            // Reverted transactions keep their value in the block but move nothing
+            let receipt = web3_connection.eth().transaction_receipt(tx.hash).await?;
+            if receipt.and_then(|r| r.status) == Some(U64::zero()) {
+                continue;
+            }
+
+            transfers.push(EthTransfer {
+                tx_hash: tx.hash,
+                log_index: None,
+                block_number,
+                kind: EthTransferKind::Native,
+                token: None,
+                from,
+                to,
+                value: tx.value,
+                confirmations: 0,
+            });
+        }
+    }
+    Ok(transfers)
+}
+
+/// Scan the blocks mined since the last sync of `history` (the last `DEFAULT_SYNC_BLOCKS`
+/// blocks, or from `from_block`, on the first one) and refresh confirmations. Returns the
+/// transfers that were not in `history` yet.
+pub async fn sync_transfers(
+    web3_connection: &Web3<EthTransport>,
+    addresses: &[Address],
+    history: &mut EthTransferHistory,
+    from_block: Option<U64>,
+) -> Result<Vec<EthTransfer>> {
+    let latest_block = web3_connection.eth().block_number().await?;
+    let start = match history.synced_block {
+        Some(synced) => (synced + U64::one()).saturating_sub(U64::from(REORG_DEPTH)),
+        None => from_block
+            .unwrap_or_else(|| latest_block.saturating_sub(U64::from(DEFAULT_SYNC_BLOCKS))),
+    };
+
+    let known: HashSet<TransferKey> = history.transfers.iter().map(transfer_key).collect();
+    history.transfers.retain(|t| t.block_number < start);
+    let mut kept: HashSet<TransferKey> = history.transfers.iter().map(transfer_key).collect();
+    let address_set: HashSet<Address> = addresses.iter().copied().collect();
+
+    let mut from = start;
+    while from <= latest_block {
+        let to = min(from + U64::from(LOG_BLOCK_RANGE - 1), latest_block);
+        let mut scanned = get_erc20_transfers(web3_connection, addresses, from, to).await?;
+        scanned.extend(get_native_transfers(web3_connection, &address_set, from, to).await?);
+        for transfer in scanned {
+            if kept.insert(transfer_key(&transfer)) {
+                history.transfers.push(transfer);
+            }
+        }
+        history.synced_block = Some(to);
+        from = to + U64::one();
+    }
+
+    for transfer in history.transfers.iter_mut() {
+        transfer.confirmations = latest_block.saturating_sub(transfer.block_number).as_u64() + 1;
+    }
+    history
+        .transfers
+        .sort_by_key(|t| (t.block_number, t.log_index));
+
+    Ok(history
+        .transfers
+        .iter()
+        .filter(|t| !known.contains(&transfer_key(t)))
+        .cloned()
+        .collect())
+}
+
+/// Sync `history` on every new block, calling `on_transfer` for each new transfer, until the
+/// `newHeads` subscription ends.
+pub async fn watch_transfers<F>(
+    connection: &EthConnection,
+    addresses: &[Address],
+    history: &mut EthTransferHistory,
+    mut on_transfer: F,
+) -> Result<()>
+where
+    F: FnMut(&EthTransfer),
+{
+    let ws = connection
+        .subscriptions()
+        .ok_or_else(|| anyhow!("watching transfers needs a WebSocket endpoint"))?;
+    let mut new_heads = ws.eth_subscribe().subscribe_new_heads().await?;
+
+    while let Some(head) = new_heads.next().await {
+        head?;
+        for transfer in sync_transfers(connection.web3(), addresses, history, None).await? {
+            on_transfer(&transfer);
+        }
+    }
+    Ok(())
+}
+
+fn check_history_chain(network: &EthNetwork, history: &mut EthTransferHistory) -> Result<()> {
+    if history.synced_block.is_none() {
+        history.chain_id = network.chain_id;
+    } else if history.chain_id != network.chain_id {
+        return Err(anyhow!(
+            "history of chain {} cannot be synced on {} (chain {})",
+            history.chain_id,
+            network.name,
+            network.chain_id
+        ));
+    }
+    Ok(())
+}
+
+pub fn sync_eth_transfers(
+    network: &EthNetwork,
+    addresses: &[Address],
+    history: &mut EthTransferHistory,
+) -> Result<Vec<EthTransfer>> {
+    block_on(a_sync_eth_transfers(network, addresses, history))
+}
+
+pub async fn a_sync_eth_transfers(
+    network: &EthNetwork,
+    addresses: &[Address],
+    history: &mut EthTransferHistory,
+) -> Result<Vec<EthTransfer>> {
+    check_history_chain(network, history)?;
+    let connection = get_connection(network.rpc_url()?).await?;
+    network.check_chain_id(connection.web3()).await?;
+    sync_transfers(connection.web3(), addresses, history, None).await
+}
+
+/// Blocking `watch_transfers` over the WebSocket endpoint of `network`.
+pub fn watch_eth_transfers<F>(
+    network: &EthNetwork,
+    addresses: &[Address],
+    history: &mut EthTransferHistory,
+    on_transfer: F,
+) -> Result<()>
+where
+    F: FnMut(&EthTransfer),
+{
+    block_on(a_watch_eth_transfers(
+        network,
+        addresses,
+        history,
+        on_transfer,
+    ))
+}
+
+/// `watch_transfers` over the WebSocket endpoint of `network`.
+pub async fn a_watch_eth_transfers<F>(
+    network: &EthNetwork,
+    addresses: &[Address],
+    history: &mut EthTransferHistory,
+    on_transfer: F,
+) -> Result<()>
+where
+    F: FnMut(&EthTransfer),
+{
+    check_history_chain(network, history)?;
+    let connection = get_connection(network.ws_url()?).await?;
+    network.check_chain_id(connection.web3()).await?;
+    watch_transfers(&connection, addresses, history, on_transfer).await
+}
+
+/// Sync the transfer history given as JSON (empty for a first sync) for every address of the
+/// derivation map, returning the updated history.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn eth_sync_transfers(
+    c_network: *const c_char,
+    c_addresses_derivation_map: *const c_char,
+    c_history_json: *const c_char,
+) -> *mut c_char {
+    let network = match get_eth_network_from_raw(c_network) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let addresses_derivation_map: HashMap<EthAddress, MKPosDto> =
+        match get_addresses_derivation_map_from_raw(c_addresses_derivation_map) {
+            Ok(s) => s,
+            Err(e) => return error_to_c_string(e),
+        };
+
+    let history_json = match get_str_from_c_char(c_history_json, "history") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let mut history: EthTransferHistory = if history_json.is_empty() {
+        EthTransferHistory::default()
+    } else {
+        match serde_json::from_str(&history_json) {
+            Ok(s) => s,
+            Err(e) => {
+                return error_to_c_string(ErrorFFIKind::E104 {
+                    msg: "history".to_owned(),
+                    e: e.to_string(),
+                })
+            }
+        }
+    };
+
+    let addresses: Vec<Address> = addresses_derivation_map
+        .keys()
+        .map(|a| (*a).into())
+        .collect();
+    if let Err(e) = sync_eth_transfers(&network, &addresses, &mut history) {
+        return error_to_c_string(ErrorFFIKind::E103 {
+            msg: "history".to_owned(),
+            e: e.to_string(),
+        });
+    }
+
+    let history_json = match serde_json::to_string(&history) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E102 {
+                msg: "history".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(history_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "history".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
@@ -1,14 +1,17 @@
-use rlp::RlpStream;
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+use rlp::{Rlp, RlpStream};
 use web3::{
-    signing::{self, Signature},
-    types::{AccessList, Address, SignedTransaction, U256, U64},
+    signing::{self, recover, Signature},
+    types::{AccessList, AccessListItem, Address, SignedTransaction, H256, U256, U64},
 };
 
 pub const LEGACY_TX_ID: u64 = 0;
 pub const ACCESSLISTS_TX_ID: u64 = 1;
 pub const EIP1559_TX_ID: u64 = 2;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct Transaction {
     pub to: Option<Address>,
     pub nonce: U256,
@@ -52,6 +55,14 @@ impl Transaction {
         stream
     }
 
+    /// Pre-EIP-155 signing payload, without chain id.
+    fn encode_unprotected(&self) -> RlpStream {
+        let mut stream = RlpStream::new();
+        stream.begin_list(6);
+        self.rlp_append_legacy(&mut stream);
+        stream
+    }
+
     fn encode_access_list_payload(
         &self,
         chain_id: u64,
@@ -148,6 +159,15 @@ impl Transaction {
         }
     }
 
+    /// `v` for a signature with the given recovery id: EIP-155 encoded for legacy
+    /// transactions, the bare y parity for typed ones.
+    pub fn signature_v(&self, recovery_id: u8, chain_id: u64) -> u64 {
+        match self.transaction_type.map(|t| t.as_u64()) {
+            Some(LEGACY_TX_ID) | None => recovery_id as u64 + 35 + chain_id * 2,
+            _ => recovery_id as u64,
+        }
+    }
+
     /// Sign and return a raw signed transaction.
     pub fn sign(self, signature: Signature, chain_id: u64) -> SignedTransaction {
         let hash = self.get_hash(chain_id);
@@ -164,8 +184,155 @@ impl Transaction {
         }
     }
 
+    /// Hash to sign. `chain_id` is part of the signed payload for every transaction type
+    /// (EIP-155 for legacy ones), so a signature is only valid on that chain.
     pub fn get_hash(&self, chain_id: u64) -> [u8; 32] {
         let encoded = self.encode(chain_id, None);
         signing::keccak256(encoded.as_ref())
     }
+
+    /// Parse a raw signed transaction, legacy or EIP-2718 typed (access list or EIP-1559).
+    pub fn decode(raw: &[u8]) -> Result<DecodedTransaction> {
+        let (transaction_type, payload) = match raw.first() {
+            Some(b) if *b >= 0xc0 => (None, raw),
+            Some(b) if *b as u64 == ACCESSLISTS_TX_ID || *b as u64 == EIP1559_TX_ID => {
+                (Some(U64::from(*b)), &raw[1..])
+            }
+            Some(b) => return Err(anyhow!("unsupported transaction type: {}", b)),
+            None => return Err(anyhow!("empty transaction")),
+        };
+
+        let rlp = Rlp::new(payload);
+        if rlp.payload_info()?.total() != payload.len() {
+            return Err(anyhow!("trailing bytes after the transaction"));
+        }
+        let expected_items = match transaction_type.map(|t| t.as_u64()) {
+            None => 9,
+            Some(ACCESSLISTS_TX_ID) => 11,
+            _ => 12,
+        };
+        if rlp.item_count()? != expected_items {
+            return Err(anyhow!(
+                "expected {} fields, the transaction has {}",
+                expected_items,
+                rlp.item_count()?
+            ));
+        }
+
+        // Typed transactions start with the chain id, EIP-1559 ones have a priority fee
+        // between the nonce and the (max) gas price
+        let (chain_id, offset) = match transaction_type {
+            None => (None, 0),
+            Some(_) => (Some(rlp.val_at::<u64>(0)?), 1),
+        };
+        let nonce = rlp.val_at(offset)?;
+        let (max_priority_fee_per_gas, offset) = match transaction_type.map(|t| t.as_u64()) {
+            Some(EIP1559_TX_ID) => (rlp.val_at::<U256>(offset + 1)?, offset + 2),
+            _ => (U256::zero(), offset + 1),
+        };
+
+        let to = rlp.at(offset + 2)?;
+        let transaction = Transaction {
+            nonce,
+            gas_price: rlp.val_at(offset)?,
+            gas: rlp.val_at(offset + 1)?,
+            to: if to.is_empty() {
+                None
+            } else {
+                Some(to.as_val()?)
+            },
+            value: rlp.val_at(offset + 3)?,
+            data: rlp.val_at(offset + 4)?,
+            transaction_type,
+            access_list: match transaction_type {
+                None => vec![],
+                Some(_) => decode_access_list(&rlp.at(offset + 5)?)?,
+            },
+            max_priority_fee_per_gas,
+        };
+
+        let signature_offset = expected_items - 3;
+        let v: u64 = rlp.val_at(signature_offset)?;
+        let signature = Signature {
+            v,
+            r: u256_to_h256(rlp.val_at(signature_offset + 1)?),
+            s: u256_to_h256(rlp.val_at(signature_offset + 2)?),
+        };
+
+        let (chain_id, recovery_id) = match chain_id {
+            Some(chain_id) if v <= 1 => (Some(chain_id), v as i32),
+            Some(_) => return Err(anyhow!("invalid y parity: {}", v)),
+            // EIP-155: v = recovery id + 35 + 2 * chain id
+            None if v >= 35 => (Some((v - 35) / 2), ((v - 35) % 2) as i32),
+            None if v == 27 || v == 28 => (None, (v - 27) as i32),
+            None => return Err(anyhow!("invalid signature v: {}", v)),
+        };
+
+        Ok(DecodedTransaction {
+            transaction,
+            chain_id,
+            signature,
+            recovery_id,
+        })
+    }
+}
+
+fn decode_access_list(rlp: &Rlp) -> Result<AccessList> {
+    rlp.iter()
+        .map(|item| {
+            Ok(AccessListItem {
+                address: item.val_at(0)?,
+                storage_keys: item.list_at(1)?,
+            })
+        })
+        .collect()
+}
+
+fn u256_to_h256(n: U256) -> H256 {
+    let mut bytes = [0u8; 32];
+    n.to_big_endian(&mut bytes);
+    H256::from(bytes)
+}
+
+/// A transaction parsed back from its raw signed form.
+pub struct DecodedTransaction {
+    pub transaction: Transaction,
+    /// Unset for legacy transactions signed without EIP-155 replay protection.
+    pub chain_id: Option<u64>,
+    pub signature: Signature,
+    recovery_id: i32,
+}
+
+// `Signature` does not implement `Debug`
+impl fmt::Debug for DecodedTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DecodedTransaction")
+            .field("transaction", &self.transaction)
+            .field("chain_id", &self.chain_id)
+            .field("v", &self.signature.v)
+            .field("r", &self.signature.r)
+            .field("s", &self.signature.s)
+            .field("recovery_id", &self.recovery_id)
+            .finish()
+    }
+}
+
+impl DecodedTransaction {
+    /// Hash the signature covers.
+    pub fn signing_hash(&self) -> [u8; 32] {
+        match self.chain_id {
+            Some(chain_id) => self.transaction.get_hash(chain_id),
+            None => signing::keccak256(self.transaction.encode_unprotected().as_raw()),
+        }
+    }
+
+    /// Address whose key signed the transaction.
+    pub fn recover_sender(&self) -> Result<Address> {
+        let mut signature = self.signature.r.as_bytes().to_vec();
+        signature.extend_from_slice(self.signature.s.as_bytes());
+        match recover(&self.signing_hash(), &signature, self.recovery_id) {
+            Ok(address) => Ok(address),
+            Err(e) => Err(anyhow!("signature recovery failed: {:?}", e)),
+        }
+    }
 }
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use anyhow::Result;
+use futures::future::{BoxFuture, FutureExt};
+use jsonrpc_core::{Call, Value};
+use web3::{
+    transports::{Either, Http, Ipc, WebSocket},
+    RequestId, Transport, Web3,
+};
+
+use crate::utilities::runtime::on_shared_runtime;
+
+/// Connections are bound to the runtime that opened them, only those of the shared runtime,
+/// which lives as long as the process, are cached.
+static CONNECTIONS: OnceLock<Mutex<HashMap<String, EthConnection>>> = OnceLock::new();
+
+/// One transport type for every Ethereum call, whatever the endpoint scheme. A call failing
+/// at the transport level drops the cached connection it went through, so that the next
+/// `get_connection` reconnects.
+#[derive(Debug, Clone)]
+pub struct EthTransport {
+    inner: Either<WebSocket, Either<Http, Ipc>>,
+    url: String,
+    connection_id: u64,
+}
+
+impl Transport for EthTransport {
+    type Out = BoxFuture<'static, web3::Result<Value>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        self.inner.prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: Call) -> Self::Out {
+        let url = self.url.clone();
+        let connection_id = self.connection_id;
+        self.inner
+            .send(id, request)
+            .map(move |res| {
+                if let Err(
+                    web3::Error::Transport(_) | web3::Error::Unreachable | web3::Error::Io(_),
+                ) = &res
+                {
+                    evict_connection(&url, connection_id);
+                }
+                res
+            })
+            .boxed()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportKind {
+    WebSocket,
+    Http,
+    Ipc,
+}
+
+impl TransportKind {
+    /// `ws(s)://` and `http(s)://` URLs select the matching transport, anything else is
+    /// taken as an IPC socket path (optionally prefixed with `ipc://`).
+    pub fn from_url(url: &str) -> TransportKind {
+        if url.starts_with("ws://") || url.starts_with("wss://") {
+            TransportKind::WebSocket
+        } else if url.starts_with("http://") || url.starts_with("https://") {
+            TransportKind::Http
+        } else {
+            TransportKind::Ipc
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EthConnection {
+    web3: Web3<EthTransport>,
+    ws: Option<Web3<WebSocket>>,
+}
+
+impl EthConnection {
+    /// Open a new connection to `url` on the current runtime.
+    pub async fn connect(url: &str) -> Result<EthConnection> {
+        static CONNECTION_ID: AtomicU64 = AtomicU64::new(0);
+
+        let (inner, ws) = match TransportKind::from_url(url) {
+            TransportKind::WebSocket => {
+                let ws = WebSocket::new(url).await?;
+                (Either::Left(ws.clone()), Some(Web3::new(ws)))
+            }
+            TransportKind::Http => (Either::Right(Either::Left(Http::new(url)?)), None),
+            TransportKind::Ipc => {
+                let ipc = Ipc::new(url.trim_start_matches("ipc://")).await?;
+                (Either::Right(Either::Right(ipc)), None)
+            }
+        };
+        let transport = EthTransport {
+            inner,
+            url: url.to_owned(),
+            connection_id: CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
+        };
+        Ok(EthConnection {
+            web3: Web3::new(transport),
+            ws,
+        })
+    }
+
+    fn id(&self) -> u64 {
+        self.web3.transport().connection_id
+    }
+
+    pub fn web3(&self) -> &Web3<EthTransport> {
+        &self.web3
+    }
+
+    /// Pub/sub access, only available over WebSocket.
+    pub fn subscriptions(&self) -> Option<&Web3<WebSocket>> {
+        self.ws.as_ref()
+    }
+}
+
+/// Return a connection to `url`. On the shared runtime of the blocking wrappers it is
+/// cached and reused, async callers on a runtime of their own get a new one, which goes
+/// away with their runtime.
+pub async fn get_connection(url: &str) -> Result<EthConnection> {
+    if !on_shared_runtime() {
+        return EthConnection::connect(url).await;
+    }
+
+    let connections = CONNECTIONS.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(connection) = connections.lock().unwrap().get(url) {
+        return Ok(connection.clone());
+    }
+
+    let connection = EthConnection::connect(url).await?;
+    connections
+        .lock()
+        .unwrap()
+        .insert(url.to_owned(), connection.clone());
+    Ok(connection)
+}
+
+/// Forget the cached connection for `url`, e.g. after the node went away.
+pub fn drop_connection(url: &str) {
+    if let Some(connections) = CONNECTIONS.get() {
+        connections.lock().unwrap().remove(url);
+    }
+}
+
+/// Forget the connection cached for `url` if it is still `connection_id`, a newer one may
+/// already have replaced it.
+fn evict_connection(url: &str, connection_id: u64) {
+    if let Some(connections) = CONNECTIONS.get() {
+        let mut connections = connections.lock().unwrap();
+        if connections.get(url).map(|c| c.id()) == Some(connection_id) {
+            connections.remove(url);
+        }
+    }
+}
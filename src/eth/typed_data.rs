@@ -0,0 +1,255 @@
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::str::FromStr;
+use web3::{
+    signing::keccak256,
+    types::{Address, U256},
+};
+
+pub const EIP712_DOMAIN: &str = "EIP712Domain";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TypedDataField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: String,
+}
+
+/// EIP-712 typed data, as accepted by `eth_signTypedData_v4`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedData {
+    pub types: HashMap<String, Vec<TypedDataField>>,
+    pub primary_type: String,
+    pub domain: Value,
+    pub message: Value,
+}
+
+impl TypedData {
+    pub fn from_json(typed_data_json: &str) -> Result<TypedData> {
+        let typed_data: TypedData = serde_json::from_str(typed_data_json)?;
+        Ok(typed_data)
+    }
+
+    /// `keccak256("\x19\x01" ‖ domainSeparator ‖ hashStruct(message))`
+    pub fn signing_hash(&self) -> Result<[u8; 32]> {
+        let mut encoded = vec![0x19, 0x01];
+        encoded.extend_from_slice(&self.domain_separator()?);
+        if self.primary_type != EIP712_DOMAIN {
+            encoded.extend_from_slice(&self.struct_hash()?);
+        }
+        Ok(keccak256(&encoded))
+    }
+
+    pub fn domain_separator(&self) -> Result<[u8; 32]> {
+        self.hash_struct(EIP712_DOMAIN, &self.domain)
+    }
+
+    pub fn struct_hash(&self) -> Result<[u8; 32]> {
+        self.hash_struct(&self.primary_type, &self.message)
+    }
+
+    pub fn hash_struct(&self, type_name: &str, data: &Value) -> Result<[u8; 32]> {
+        let mut encoded = self.type_hash(type_name)?.to_vec();
+        encoded.extend(self.encode_data(type_name, data)?);
+        Ok(keccak256(&encoded))
+    }
+
+    pub fn type_hash(&self, type_name: &str) -> Result<[u8; 32]> {
+        Ok(keccak256(self.encode_type(type_name)?.as_bytes()))
+    }
+
+    /// Encode a struct type and its referenced struct types, the latter sorted by name.
+    pub fn encode_type(&self, type_name: &str) -> Result<String> {
+        let mut deps = BTreeSet::new();
+        self.find_dependencies(type_name, &mut deps)?;
+        deps.remove(type_name);
+
+        let mut encoded = String::new();
+        for name in std::iter::once(type_name).chain(deps.iter().map(|d| d.as_str())) {
+            let fields = self.struct_fields(name)?;
+            let members: Vec<String> = fields
+                .iter()
+                .map(|f| format!("{} {}", f.field_type, f.name))
+                .collect();
+            encoded.push_str(&format!("{}({})", name, members.join(",")));
+        }
+        Ok(encoded)
+    }
+
+    fn find_dependencies(&self, type_name: &str, deps: &mut BTreeSet<String>) -> Result<()> {
+        let base = base_type(type_name);
+        if deps.contains(base) || !self.is_struct(base) {
+            return Ok(());
+        }
+        deps.insert(base.to_owned());
+        for field in self.struct_fields(base)? {
+            self.find_dependencies(&field.field_type, deps)?;
+        }
+        Ok(())
+    }
+
+    fn is_struct(&self, type_name: &str) -> bool {
+        type_name == EIP712_DOMAIN || self.types.contains_key(type_name)
+    }
+
+    fn struct_fields(&self, type_name: &str) -> Result<Vec<TypedDataField>> {
+        if let Some(fields) = self.types.get(type_name) {
+            return Ok(fields.clone());
+        }
+        if type_name == EIP712_DOMAIN {
+            return Ok(infer_domain_fields(&self.domain));
+        }
+        Err(anyhow!("unknown EIP-712 struct type: {}", type_name))
+    }
+
+    fn encode_data(&self, type_name: &str, data: &Value) -> Result<Vec<u8>> {
+        let mut encoded = Vec::new();
+        for field in self.struct_fields(type_name)? {
+            let value = data.get(&field.name).unwrap_or(&Value::Null);
+            encoded.extend_from_slice(&self.encode_field(&field.field_type, value)?);
+        }
+        Ok(encoded)
+    }
+
+    fn encode_field(&self, field_type: &str, value: &Value) -> Result<[u8; 32]> {
+        if let Some(item_type) = array_item_type(field_type) {
+            let items = match value {
+                Value::Array(items) => items,
+                _ => return Err(anyhow!("expected array for EIP-712 type {}", field_type)),
+            };
+            let mut encoded = Vec::new();
+            for item in items {
+                encoded.extend_from_slice(&self.encode_field(item_type, item)?);
+            }
+            return Ok(keccak256(&encoded));
+        }
+
+        if self.is_struct(field_type) {
+            if value.is_null() {
+                return Ok([0u8; 32]);
+            }
+            return self.hash_struct(field_type, value);
+        }
+
+        if value.is_null() {
+            return Err(anyhow!("missing value for EIP-712 type {}", field_type));
+        }
+
+        match field_type {
+            "string" => match value {
+                Value::String(s) => Ok(keccak256(s.as_bytes())),
+                _ => Err(anyhow!("expected string value")),
+            },
+            "bytes" => Ok(keccak256(&parse_hex_value(value)?)),
+            "bool" => match value {
+                Value::Bool(b) => Ok(u256_to_word(U256::from(*b as u8))),
+                _ => Err(anyhow!("expected bool value")),
+            },
+            "address" => match value {
+                Value::String(s) => {
+                    let address = Address::from_str(s.trim_start_matches("0x"))?;
+                    let mut word = [0u8; 32];
+                    word[12..].copy_from_slice(address.as_bytes());
+                    Ok(word)
+                }
+                _ => Err(anyhow!("expected address string")),
+            },
+            t if t.starts_with("uint") => Ok(u256_to_word(parse_uint_value(value)?)),
+            t if t.starts_with("int") => Ok(u256_to_word(parse_int_value(value)?)),
+            t if t.starts_with("bytes") => {
+                let size: usize = t["bytes".len()..].parse()?;
+                let bytes = parse_hex_value(value)?;
+                if size == 0 || size > 32 || bytes.len() > size {
+                    return Err(anyhow!("invalid value for EIP-712 type {}", t));
+                }
+                let mut word = [0u8; 32];
+                word[..bytes.len()].copy_from_slice(&bytes);
+                Ok(word)
+            }
+            t => Err(anyhow!("unsupported EIP-712 type: {}", t)),
+        }
+    }
+}
+
+fn infer_domain_fields(domain: &Value) -> Vec<TypedDataField> {
+    [
+        ("name", "string"),
+        ("version", "string"),
+        ("chainId", "uint256"),
+        ("verifyingContract", "address"),
+        ("salt", "bytes32"),
+    ]
+    .iter()
+    .filter(|(name, _)| domain.get(name).is_some())
+    .map(|(name, field_type)| TypedDataField {
+        name: name.to_string(),
+        field_type: field_type.to_string(),
+    })
+    .collect()
+}
+
+fn base_type(field_type: &str) -> &str {
+    match field_type.find('[') {
+        Some(i) => &field_type[..i],
+        None => field_type,
+    }
+}
+
+fn array_item_type(field_type: &str) -> Option<&str> {
+    if field_type.ends_with(']') {
+        field_type.rfind('[').map(|i| &field_type[..i])
+    } else {
+        None
+    }
+}
+
+fn u256_to_word(value: U256) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    word
+}
+
+fn parse_hex_value(value: &Value) -> Result<Vec<u8>> {
+    match value {
+        Value::String(s) => Ok(hex::decode(s.trim_start_matches("0x"))?),
+        _ => Err(anyhow!("expected hex string value")),
+    }
+}
+
+pub fn parse_uint_value(value: &Value) -> Result<U256> {
+    match value {
+        Value::Number(n) => match n.as_u64() {
+            Some(n) => Ok(U256::from(n)),
+            None => Err(anyhow!("integer {} must be passed as a string", n)),
+        },
+        Value::String(s) if s.starts_with("0x") => Ok(U256::from_str(&s[2..])?),
+        Value::String(s) => {
+            U256::from_dec_str(s).map_err(|e| anyhow!("invalid integer {}: {:?}", s, e))
+        }
+        _ => Err(anyhow!("expected integer value")),
+    }
+}
+
+/// Parse a signed integer into its 256-bit two's complement representation.
+fn parse_int_value(value: &Value) -> Result<U256> {
+    let (negative, magnitude) = match value {
+        Value::Number(n) => match n.as_i64() {
+            Some(n) => (n < 0, U256::from(n.unsigned_abs())),
+            None => return Err(anyhow!("integer {} must be passed as a string", n)),
+        },
+        Value::String(s) => match s.strip_prefix('-') {
+            Some(abs) => (true, parse_uint_value(&Value::String(abs.to_owned()))?),
+            None => (false, parse_uint_value(value)?),
+        },
+        _ => return Err(anyhow!("expected integer value")),
+    };
+
+    if negative {
+        Ok((!magnitude).overflowing_add(U256::one()).0)
+    } else {
+        Ok(magnitude)
+    }
+}
@@ -0,0 +1,330 @@
+use std::ffi::CString;
+use std::fmt;
+use std::os::raw::c_char;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use web3::ethabi::Token;
+use web3::types::{Address, U256};
+
+use crate::dto::ecdsa::{MKPosAddressDto, PaymentRequestDto};
+use crate::eth::abi::encode_call;
+use crate::eth::address::EthAddress;
+use crate::eth::network::EthNetwork;
+use crate::eth::utils::pubkey_to_eth_address;
+use crate::utilities::derive_new_key;
+use crate::utilities::err_handling::{error_to_c_string, ErrorFFIKind};
+use crate::utilities::ffi::ffi_utils::{
+    get_eth_network_from_raw, get_private_share_from_raw, get_str_from_c_char,
+};
+use crate::utilities::uri::{parse_units, percent_encode, split_uri};
+
+/// An EIP-681 `ethereum:` payment URI. With `token`, it requests an ERC-20 transfer of
+/// `value` token units to `to`, otherwise `value` is in wei.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct EthPaymentUri {
+    pub to: Address,
+    pub chain_id: Option<u64>,
+    pub value: Option<U256>,
+    pub token: Option<Address>,
+    pub label: Option<String>,
+}
+
+impl EthPaymentUri {
+    pub fn native(to: Address, chain_id: u64, value: Option<U256>) -> EthPaymentUri {
+        EthPaymentUri {
+            to,
+            chain_id: Some(chain_id),
+            value,
+            token: None,
+            label: None,
+        }
+    }
+
+    pub fn erc20(
+        token: Address,
+        to: Address,
+        chain_id: u64,
+        amount: Option<U256>,
+    ) -> EthPaymentUri {
+        EthPaymentUri {
+            token: Some(token),
+            ..EthPaymentUri::native(to, chain_id, amount)
+        }
+    }
+
+    pub fn with_label(mut self, label: Option<String>) -> EthPaymentUri {
+        self.label = label;
+        self
+    }
+
+    /// Parse `uri`, failing if it names a chain other than `network`. ENS names and
+    /// functions other than ERC-20 `transfer` are not supported.
+    pub fn parse(uri: &str, network: &EthNetwork) -> Result<EthPaymentUri> {
+        let (path, params) = split_uri(uri, "ethereum")?;
+        let path = path.strip_prefix("pay-").unwrap_or(path);
+
+        let (target, function) = match path.split_once('/') {
+            Some((target, function)) => (target, Some(function)),
+            None => (path, None),
+        };
+        let (target, chain_id) = match target.split_once('@') {
+            Some((target, chain_id)) => (target, Some(chain_id.parse::<u64>()?)),
+            None => (target, None),
+        };
+        if !target.starts_with("0x") {
+            return Err(anyhow!("unsupported payment target: {}", target));
+        }
+        let target: Address = EthAddress::from_str(target)?.into();
+
+        if let Some(chain_id) = chain_id {
+            if chain_id != network.chain_id {
+                return Err(anyhow!(
+                    "the URI is for chain id {}, network {} has chain id {}",
+                    chain_id,
+                    network.name,
+                    network.chain_id
+                ));
+            }
+        }
+
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let label = param("label").map(|l| l.to_owned());
+
+        let payment_uri = match function {
+            None => EthPaymentUri {
+                to: target,
+                chain_id,
+                value: param("value").map(parse_number).transpose()?,
+                token: None,
+                label,
+            },
+            Some("transfer") => EthPaymentUri {
+                to: param("address")
+                    .map(|a| EthAddress::from_str(a).map(Address::from))
+                    .transpose()?
+                    .ok_or_else(|| anyhow!("ERC-20 transfer without recipient address"))?,
+                chain_id,
+                value: param("uint256").map(parse_number).transpose()?,
+                token: Some(target),
+                label,
+            },
+            Some(function) => return Err(anyhow!("unsupported function: {}", function)),
+        };
+        Ok(payment_uri)
+    }
+
+    /// Calldata of the requested ERC-20 transfer, empty for native payments.
+    pub fn calldata(&self) -> Vec<u8> {
+        match self.token {
+            Some(_) => encode_call(
+                "transfer(address,uint256)",
+                &[
+                    Token::Address(self.to),
+                    Token::Uint(self.value.unwrap_or_default()),
+                ],
+            ),
+            None => vec![],
+        }
+    }
+}
+
+/// EIP-681 numbers are decimals with an optional exponent, e.g. `2.014e18`.
+fn parse_number(s: &str) -> Result<U256> {
+    let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<u32>()?),
+        None => (s, 0),
+    };
+    parse_units(mantissa, exponent)
+}
+
+impl fmt::Display for EthPaymentUri {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut params = Vec::new();
+        match self.token {
+            Some(token) => {
+                write!(f, "ethereum:{}", EthAddress::from(token))?;
+                params.push(format!("address={}", EthAddress::from(self.to)));
+                if let Some(value) = self.value {
+                    params.push(format!("uint256={}", value));
+                }
+            }
+            None => {
+                write!(f, "ethereum:{}", EthAddress::from(self.to))?;
+                if let Some(value) = self.value {
+                    params.push(format!("value={}", value));
+                }
+            }
+        }
+        if let Some(chain_id) = self.chain_id {
+            write!(f, "@{}", chain_id)?;
+        }
+        if self.token.is_some() {
+            write!(f, "/transfer")?;
+        }
+        if let Some(label) = &self.label {
+            params.push(format!("label={}", percent_encode(label)));
+        }
+
+        if !params.is_empty() {
+            write!(f, "?{}", params.join("&"))?;
+        }
+        Ok(())
+    }
+}
+
+/// URI of a payment request from FFI strings, empty ones meaning unset.
+fn request_uri(
+    to: Address,
+    network: &EthNetwork,
+    token: &str,
+    amount: &str,
+    label: &str,
+) -> Result<EthPaymentUri> {
+    let amount = match amount {
+        "" => None,
+        amount => Some(U256::from_dec_str(amount).map_err(|e| anyhow!("{:?}", e))?),
+    };
+    let uri = match token {
+        "" => EthPaymentUri::native(to, network.chain_id, amount),
+        token => EthPaymentUri::erc20(
+            EthAddress::from_str(token)?.into(),
+            to,
+            network.chain_id,
+            amount,
+        ),
+    };
+    Ok(uri.with_label(Some(label.to_owned()).filter(|l| !l.is_empty())))
+}
+
+/// Parse an `ethereum:` URI for `c_network`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn parse_eth_payment_uri(
+    c_uri: *const c_char,
+    c_network: *const c_char,
+) -> *mut c_char {
+    let uri = match get_str_from_c_char(c_uri, "uri") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let network = match get_eth_network_from_raw(c_network) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let payment_uri = match EthPaymentUri::parse(&uri, &network) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E103 {
+                msg: "eth_payment_uri".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let payment_uri_json = match serde_json::to_string(&payment_uri) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E102 {
+                msg: "eth_payment_uri".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(payment_uri_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "eth_payment_uri".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
+
+/// Derive a new address, like `get_eth_addrs`, and an `ethereum:` URI requesting `c_amount`
+/// (decimal, in wei or token units) of the native currency or of `c_token` to it on
+/// `c_network`. `c_token`, `c_amount` and `c_label` may be empty.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn get_eth_payment_request(
+    c_private_share_json: *const c_char,
+    c_last_derived_pos: u32,
+    c_network: *const c_char,
+    c_token: *const c_char,
+    c_amount: *const c_char,
+    c_label: *const c_char,
+) -> *mut c_char {
+    let private_share = match get_private_share_from_raw(c_private_share_json) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let network = match get_eth_network_from_raw(c_network) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let token = match get_str_from_c_char(c_token, "token") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let amount = match get_str_from_c_char(c_amount, "amount") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let label = match get_str_from_c_char(c_label, "label") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let (pos, mk) = derive_new_key(&private_share, c_last_derived_pos);
+    let address = pubkey_to_eth_address(&mk);
+
+    let uri = request_uri(address, &network, &token, &amount, &label);
+
+    let uri = match uri {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E103 {
+                msg: "payment_request".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let payment_request = PaymentRequestDto {
+        uri: uri.to_string(),
+        mk_pos_address: MKPosAddressDto {
+            address: EthAddress::from(address).to_string(),
+            pos,
+            mk,
+        },
+    };
+
+    let payment_request_json = match serde_json::to_string(&payment_request) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E102 {
+                msg: "payment_request".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(payment_request_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "payment_request".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
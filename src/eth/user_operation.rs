@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use web3::ethabi::{self, Token};
+use web3::signing::keccak256;
+use web3::types::{Address, Bytes, H256, U256};
+use web3::Transport;
+
+use crate::dto::ecdsa::{MKPosDto, PrivateShare};
+use crate::eth::abi::{call, encode_call};
+use crate::eth::address::EthAddress;
+use crate::eth::message::{hash_personal_message, sign_hash};
+use crate::eth::network::EthNetwork;
+use crate::eth::transport::get_connection;
+use crate::eth::utils::{establish_web3_connection, get_pos_mk, pubkey_to_eth_address};
+use crate::utilities::err_handling::{error_to_c_string, ErrorFFIKind};
+use crate::utilities::ffi::ffi_utils::{
+    get_addresses_derivation_map_from_raw, get_client_shim_from_raw, get_eth_network_from_raw,
+    get_private_share_from_raw, get_str_from_c_char,
+};
+use crate::utilities::requests::ClientShim;
+use crate::utilities::runtime::block_on;
+
+/// Canonical ERC-4337 v0.6 EntryPoint, deployed at the same address on every chain.
+pub const ENTRY_POINT_V06: &str = "0x5FF137D4b0FDCD49DcA30c7CF57E578a026d2789";
+
+/// An ERC-4337 v0.6 user operation, serialized the way bundlers expect it.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOperation {
+    pub sender: Address,
+    pub nonce: U256,
+    pub init_code: Bytes,
+    pub call_data: Bytes,
+    pub call_gas_limit: U256,
+    pub verification_gas_limit: U256,
+    pub pre_verification_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub paymaster_and_data: Bytes,
+    pub signature: Bytes,
+}
+
+impl UserOperation {
+    /// ABI encoding of every field but the signature, dynamic fields being hashed.
+    pub fn pack(&self) -> Vec<u8> {
+        ethabi::encode(&[
+            Token::Address(self.sender),
+            Token::Uint(self.nonce),
+            Token::FixedBytes(keccak256(&self.init_code.0).to_vec()),
+            Token::FixedBytes(keccak256(&self.call_data.0).to_vec()),
+            Token::Uint(self.call_gas_limit),
+            Token::Uint(self.verification_gas_limit),
+            Token::Uint(self.pre_verification_gas),
+            Token::Uint(self.max_fee_per_gas),
+            Token::Uint(self.max_priority_fee_per_gas),
+            Token::FixedBytes(keccak256(&self.paymaster_and_data.0).to_vec()),
+        ])
+    }
+
+    /// `EntryPoint.getUserOpHash`: the pack hash bound to the entry point and the chain.
+    pub fn hash(&self, entry_point: Address, chain_id: u64) -> [u8; 32] {
+        keccak256(&ethabi::encode(&[
+            Token::FixedBytes(keccak256(&self.pack()).to_vec()),
+            Token::Address(entry_point),
+            Token::Uint(U256::from(chain_id)),
+        ]))
+    }
+}
+
+/// Sign `user_operation` as the owner `from` of a SimpleAccount-style smart account, which
+/// checks an EIP-191 signature of the user operation hash.
+pub fn sign_user_operation(
+    from: &str,
+    user_operation: &UserOperation,
+    entry_point: Address,
+    chain_id: u64,
+    client_shim: &ClientShim,
+    private_share: &PrivateShare,
+    addresses_derivation_map: &HashMap<EthAddress, MKPosDto>,
+) -> Result<UserOperation> {
+    let pos_mk = get_pos_mk(from, addresses_derivation_map)?;
+    let hash = user_operation.hash(entry_point, chain_id);
+    let signature = sign_hash(
+        hash_personal_message(&hash),
+        pos_mk,
+        client_shim,
+        private_share,
+    )?;
+
+    Ok(UserOperation {
+        signature,
+        ..user_operation.clone()
+    })
+}
+
+/// Address of a contract deployed with CREATE2 (EIP-1014).
+pub fn create2_address(deployer: Address, salt: H256, init_code_hash: [u8; 32]) -> Address {
+    let mut preimage = vec![0xff];
+    preimage.extend_from_slice(deployer.as_bytes());
+    preimage.extend_from_slice(salt.as_bytes());
+    preimage.extend_from_slice(&init_code_hash);
+    Address::from_slice(&keccak256(&preimage)[12..])
+}
+
+/// `initCode` deploying the account of `owner` through a SimpleAccountFactory.
+pub fn account_init_code(factory: Address, owner: Address, salt: U256) -> Bytes {
+    let mut init_code = factory.as_bytes().to_vec();
+    init_code.extend(encode_call(
+        "createAccount(address,uint256)",
+        &[Token::Address(owner), Token::Uint(salt)],
+    ));
+    init_code.into()
+}
+
+/// Counterfactual address of the account of `owner`, as computed by the factory.
+pub async fn get_account_address(
+    network: &EthNetwork,
+    factory: Address,
+    owner: Address,
+    salt: U256,
+) -> Result<Address> {
+    let web3_connection = establish_web3_connection(network.rpc_url()?).await?;
+    let data = encode_call(
+        "getAddress(address,uint256)",
+        &[Token::Address(owner), Token::Uint(salt)],
+    );
+    let result = call(&web3_connection, factory, data).await?;
+    if result.0.len() != 32 {
+        return Err(anyhow!("unexpected getAddress result: {:?}", result));
+    }
+    Ok(Address::from_slice(&result.0[12..]))
+}
+
+/// Counterfactual smart account address of a derived key.
+pub fn get_smart_account_address(
+    network: &EthNetwork,
+    factory: Address,
+    pos_mk: &MKPosDto,
+    salt: U256,
+) -> Result<Address> {
+    block_on(get_account_address(
+        network,
+        factory,
+        pubkey_to_eth_address(&pos_mk.mk),
+        salt,
+    ))
+}
+
+/// Submit a signed user operation to a bundler, returning the user operation hash.
+pub async fn send_user_operation(
+    bundler_url: &str,
+    user_operation: &UserOperation,
+    entry_point: Address,
+) -> Result<H256> {
+    let connection = get_connection(bundler_url).await?;
+    let params = vec![
+        serde_json::to_value(user_operation)?,
+        serde_json::to_value(entry_point)?,
+    ];
+    let result = connection
+        .web3()
+        .transport()
+        .execute("eth_sendUserOperation", params)
+        .await?;
+    Ok(serde_json::from_value(result)?)
+}
+
+fn entry_point_from_str(entry_point: &str) -> Result<Address> {
+    let entry_point = if entry_point.is_empty() {
+        ENTRY_POINT_V06
+    } else {
+        entry_point
+    };
+    Ok(EthAddress::from_str(entry_point)?.into())
+}
+
+/// Sign a user operation given as JSON. An empty `c_entry_point` means `ENTRY_POINT_V06`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn eth_sign_user_operation(
+    c_endpoint: *const c_char,
+    c_auth_token: *const c_char,
+    c_user_id: *const c_char,
+    c_from_address: *const c_char,
+    c_user_operation_json: *const c_char,
+    c_entry_point: *const c_char,
+    c_network: *const c_char,
+    c_private_share_json: *const c_char,
+    c_addresses_derivation_map: *const c_char,
+) -> *mut c_char {
+    let from_address = match get_str_from_c_char(c_from_address, "from_address") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let user_operation_json = match get_str_from_c_char(c_user_operation_json, "user_operation") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let entry_point = match get_str_from_c_char(c_entry_point, "entry_point") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let client_shim = match get_client_shim_from_raw(c_endpoint, c_auth_token, c_user_id) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let network = match get_eth_network_from_raw(c_network) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let private_share = match get_private_share_from_raw(c_private_share_json) {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let addresses_derivation_map =
+        match get_addresses_derivation_map_from_raw(c_addresses_derivation_map) {
+            Ok(s) => s,
+            Err(e) => return error_to_c_string(e),
+        };
+
+    let user_operation: UserOperation = match serde_json::from_str(&user_operation_json) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E104 {
+                msg: "user_operation".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let signed = entry_point_from_str(&entry_point).and_then(|entry_point| {
+        sign_user_operation(
+            &from_address,
+            &user_operation,
+            entry_point,
+            network.chain_id,
+            &client_shim,
+            &private_share,
+            &addresses_derivation_map,
+        )
+    });
+
+    let signed = match signed {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E103 {
+                msg: "user_operation".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let signed_json = match serde_json::to_string(&signed) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E102 {
+                msg: "user_operation".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(signed_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "user_operation".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
+
+/// Submit a signed user operation to `c_bundler_url`, returning the user operation hash.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn eth_send_user_operation(
+    c_bundler_url: *const c_char,
+    c_user_operation_json: *const c_char,
+    c_entry_point: *const c_char,
+) -> *mut c_char {
+    let bundler_url = match get_str_from_c_char(c_bundler_url, "bundler_url") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let user_operation_json = match get_str_from_c_char(c_user_operation_json, "user_operation") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let entry_point = match get_str_from_c_char(c_entry_point, "entry_point") {
+        Ok(s) => s,
+        Err(e) => return error_to_c_string(e),
+    };
+
+    let user_operation: UserOperation = match serde_json::from_str(&user_operation_json) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E104 {
+                msg: "user_operation".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let user_op_hash = entry_point_from_str(&entry_point).and_then(|entry_point| {
+        block_on(send_user_operation(
+            &bundler_url,
+            &user_operation,
+            entry_point,
+        ))
+    });
+
+    let user_op_hash = match user_op_hash {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E103 {
+                msg: "user_op_hash".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    let user_op_hash_json = match serde_json::to_string(&user_op_hash) {
+        Ok(s) => s,
+        Err(e) => {
+            return error_to_c_string(ErrorFFIKind::E102 {
+                msg: "user_op_hash".to_owned(),
+                e: e.to_string(),
+            })
+        }
+    };
+
+    match CString::new(user_op_hash_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => error_to_c_string(ErrorFFIKind::E101 {
+            msg: "user_op_hash".to_owned(),
+            e: e.to_string(),
+        }),
+    }
+}
@@ -1,29 +1,43 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use curv::{elliptic::curves::traits::ECPoint, BigInt};
 use futures::future::try_join_all;
 use kms::ecdsa::two_party::MasterKey2;
+use std::collections::HashMap;
 use std::str::FromStr;
 use web3::{
     self,
     signing::keccak256,
-    transports::{self, WebSocket},
     types::{Address, U256},
     Web3,
 };
 
-use crate::dto::ecdsa::PrivateShare;
+use crate::dto::ecdsa::{MKPosDto, PrivateShare};
+use crate::eth::address::EthAddress;
+use crate::eth::transport::{get_connection, EthTransport};
 
 pub async fn get_all_addresses_balance(
     web3_connection_url: &str,
     last_derived_pos: u32,
     private_share: &PrivateShare,
 ) -> Result<Vec<f64>> {
+    let balances =
+        get_all_addresses_balance_in_wei(web3_connection_url, last_derived_pos, private_share)
+            .await?;
+    Ok(balances.into_iter().map(wei_to_eth).collect())
+}
+
+/// Balances in the smallest unit of the chain's native currency.
+pub async fn get_all_addresses_balance_in_wei(
+    web3_connection_url: &str,
+    last_derived_pos: u32,
+    private_share: &PrivateShare,
+) -> Result<Vec<U256>> {
     let web3_connection = establish_web3_connection(web3_connection_url).await?;
     let addresses = get_all_addresses(last_derived_pos, private_share).unwrap();
-    let result: Vec<f64> = try_join_all(
+    let result: Vec<U256> = try_join_all(
         addresses
             .iter()
-            .map(|a| get_balance_in_eth(format!("{:?}", a), &web3_connection)),
+            .map(|a| get_balance(format!("{:?}", a), &web3_connection)),
     )
     .await?;
     Ok(result)
@@ -56,15 +70,28 @@ pub fn pubkey_to_eth_address(mk: &MasterKey2) -> Address {
     Address::from_slice(&hash[12..])
 }
 
+pub fn get_pos_mk<'a>(
+    address: &str,
+    addresses_derivation_map: &'a HashMap<EthAddress, MKPosDto>,
+) -> Result<&'a MKPosDto> {
+    let address = EthAddress::from_str(address)?;
+    match addresses_derivation_map.get(&address) {
+        Some(pos_mk) => Ok(pos_mk),
+        None => Err(anyhow!(
+            "from address not found in addresses_derivation_map"
+        )),
+    }
+}
+
 pub async fn get_balance_in_eth(
     public_address: String,
-    web3_connection: &Web3<transports::WebSocket>,
+    web3_connection: &Web3<EthTransport>,
 ) -> Result<f64> {
     let wei_balance = get_balance(public_address, web3_connection).await?;
     Ok(wei_to_eth(wei_balance))
 }
 
-async fn get_balance(public_address: String, web3_connection: &Web3<WebSocket>) -> Result<U256> {
+async fn get_balance(public_address: String, web3_connection: &Web3<EthTransport>) -> Result<U256> {
     let wallet_address = Address::from_str(public_address.as_str())?;
     let balance = web3_connection.eth().balance(wallet_address, None).await?;
     Ok(balance)
@@ -75,7 +102,8 @@ pub fn wei_to_eth(wei_val: U256) -> f64 {
     res / 1_000_000_000_000_000_000.0
 }
 
-pub async fn establish_web3_connection(url: &str) -> Result<Web3<transports::WebSocket>> {
-    let transport = transports::WebSocket::new(url).await?;
-    Ok(Web3::new(transport))
+/// Connect over WebSocket, HTTP(S) or IPC depending on `url`, reusing an open connection.
+pub async fn establish_web3_connection(url: &str) -> Result<Web3<EthTransport>> {
+    let connection = get_connection(url).await?;
+    Ok(connection.web3().clone())
 }
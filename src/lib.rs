@@ -17,7 +17,8 @@ pub mod wallet;
 // pub mod eddsa;
 // pub mod schnorr;
 
-pub mod tests;
+#[cfg(test)]
+mod tests;
 
 // pub use multi_party_eddsa::protocols::aggsig::*;
 pub use curv::{arithmetic::traits::Converter, BigInt};
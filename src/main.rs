@@ -5,11 +5,15 @@ extern crate clap;
 use clap::App;
 
 use client::escrow;
+use client::eth::replace::{EthReplacementFees, EthTxReplacement};
+use client::utilities::a_requests::RequestConfig;
 use client::utilities::requests::ClientShim;
+use client::utilities::uri::parse_units;
 use client::wallet::{self, WALLET_FILENAME};
 use floating_duration::TimeFormat;
 use std::collections::HashMap;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use web3::types::U256;
 
 fn main() {
     let yaml = load_yaml!("../cli.yml");
@@ -24,10 +28,19 @@ fn main() {
     let hm = settings.try_into::<HashMap<String, String>>().unwrap();
     let endpoint = hm.get("endpoint").unwrap();
 
-    let mut client_shim = ClientShim::new(
+    let mut request_config = RequestConfig::default();
+    if let Some(timeout) = hm.get("request_timeout_secs") {
+        request_config.timeout = Duration::from_secs(timeout.parse().unwrap());
+    }
+    if let Some(retries) = hm.get("request_retries") {
+        request_config.retries = retries.parse().unwrap();
+    }
+
+    let mut client_shim = ClientShim::with_config(
         endpoint.to_string(),
         Some("cli_token".to_owned()),
         "cli_app".to_owned(),
+        request_config,
     );
 
     // For async tests
@@ -39,6 +52,7 @@ fn main() {
 
     let network = "testnet".to_string();
     if let Some(matches) = matches.subcommand_matches("create-wallet") {
+        let network = matches.value_of("network").unwrap_or(&network).to_owned();
         println!("Network: [{}], Creating wallet", network);
         let coin_type: &str = matches.value_of("coin-type").unwrap();
 
@@ -60,7 +74,14 @@ fn main() {
             wallet.get_crypto_address();
             wallet.save();
         } else if matches.is_present("get-balance") {
-            wallet.get_balance();
+            match matches.value_of("chain") {
+                Some(chain) => {
+                    wallet.get_evm_balance(Some(chain)).unwrap();
+                }
+                None => {
+                    wallet.get_balance().unwrap();
+                }
+            }
         } else if matches.is_present("backup") {
             println!("Backup private share pending (it can take some time)...");
             let start = Instant::now();
@@ -105,25 +126,107 @@ fn main() {
             );
         } else if matches.is_present("send") {
             if let Some(matches) = matches.subcommand_matches("send") {
-                let from: &str = matches.value_of("from").unwrap();
-                let to: &str = matches.value_of("to").unwrap();
-                let amount: &str = matches.value_of("amount").unwrap();
+                let from = matches.value_of("from");
                 let token: &str = matches.value_of("token").unwrap();
                 client_shim.auth_token = Some(token.to_owned());
 
                 // a_client_shim.auth_token = Some(token.to_owned());
 
-                wallet.send(
-                    from,
-                    to,
-                    amount.to_string().parse::<f64>().unwrap(),
-                    &client_shim,
-                );
-
-                if wallet.coin_type == "btc" {
-                    wallet.save();
+                let chain = matches.value_of("chain");
+                let sweep = matches.is_present("sweep");
+                if let Some(uri) = matches.value_of("uri") {
+                    wallet
+                        .pay_uri(uri, from, chain, sweep, &client_shim)
+                        .unwrap();
+                } else {
+                    let to: &str = matches.value_of("to").unwrap();
+                    let amount: &str = matches.value_of("amount").unwrap();
+                    let amount = amount.to_string().parse::<f64>().unwrap();
+                    match (from, chain) {
+                        (None, _) if wallet.coin_type == "eth" => {
+                            wallet
+                                .send_from_any(chain, to, amount, sweep, &client_shim)
+                                .unwrap();
+                        }
+                        (None, Some(_)) => usage_error("--chain needs a sender (-f)"),
+                        (Some(from), Some(chain)) => {
+                            wallet
+                                .send_on_chain(chain, from, to, amount, &client_shim)
+                                .unwrap();
+                        }
+                        (from, None) => {
+                            // BTC picks its inputs itself
+                            wallet
+                                .send(from.unwrap_or(""), to, amount, &client_shim)
+                                .unwrap();
+                        }
+                    }
                 }
+
+                // BTC change addresses and ETH transactions are tracked in the wallet
+                wallet.save();
             }
+        } else if let Some(matches) = matches.subcommand_matches("request") {
+            wallet
+                .request_payment(
+                    matches.value_of("amount"),
+                    matches.value_of("label"),
+                    matches.value_of("chain"),
+                )
+                .unwrap();
+            wallet.save();
+        } else if let Some(matches) = matches.subcommand_matches("wait-tx") {
+            let tx_hash: &str = matches.value_of("hash").unwrap();
+            let confirmations: u64 = matches
+                .value_of("confirmations")
+                .unwrap_or("1")
+                .parse::<u64>()
+                .unwrap();
+
+            let start = Instant::now();
+            wallet
+                .wait_for_eth_tx(tx_hash, confirmations, matches.value_of("chain"))
+                .unwrap();
+
+            println!(" (Took: {})", TimeFormat(start.elapsed()));
+        } else if let Some(matches) = matches.subcommand_matches("sync") {
+            let chain = matches.value_of("chain");
+            wallet.sync_eth_transfers(chain).unwrap();
+            wallet.save();
+
+            if matches.is_present("watch") {
+                wallet.watch_eth_transfers(chain).unwrap();
+                wallet.save();
+            }
+        } else if let Some((replacement, matches)) = match matches.subcommand() {
+            ("speed-up", Some(matches)) => Some((EthTxReplacement::SpeedUp, matches)),
+            ("cancel", Some(matches)) => Some((EthTxReplacement::Cancel, matches)),
+            _ => None,
+        } {
+            let tx_hash: &str = matches.value_of("hash").unwrap();
+            let fees = EthReplacementFees {
+                max_fee_per_gas: matches.value_of("max-fee").map(gwei_to_wei),
+                max_priority_fee_per_gas: matches.value_of("priority-fee").map(gwei_to_wei),
+            };
+            let token: &str = matches.value_of("token").unwrap();
+            client_shim.auth_token = Some(token.to_owned());
+
+            let start = Instant::now();
+            wallet
+                .replace_eth_tx(tx_hash, replacement, &fees, &client_shim)
+                .unwrap();
+            wallet.save();
+
+            println!(" (Took: {})", TimeFormat(start.elapsed()));
         }
     }
 }
+
+fn gwei_to_wei(gwei: &str) -> U256 {
+    parse_units(gwei, 9)
+        .unwrap_or_else(|e| usage_error(&format!("invalid fee of {} gwei: {}", gwei, e)))
+}
+
+fn usage_error(description: &str) -> ! {
+    clap::Error::with_description(description, clap::ErrorKind::InvalidValue).exit()
+}
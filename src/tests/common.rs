@@ -1,10 +1,18 @@
-use serde_json::json;
+use serde_json::{json, Value};
 
-use crate::{dto::ecdsa::PrivateShare, utilities::requests::ClientShim};
-use std::{collections::HashMap, fs};
+use crate::{
+    dto::ecdsa::PrivateShare,
+    eth::network::{get_eth_network, EthNetwork, SEPOLIA},
+    utilities::requests::ClientShim,
+};
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    thread::{self, JoinHandle},
+};
 
-pub const RINKEBY_TEST_API: &str =
-    "wss://eth-rinkeby.alchemyapi.io/v2/UmSDyVix3dL4CtIxC2zlKkSuk2UoRw1J";
 pub const PRIVATE_SHARE_FILENAME: &str = "test-assets/private_share.json";
 
 #[derive(Debug, Deserialize)]
@@ -68,6 +76,12 @@ pub fn mock_client_shim(test_email: &str, test_pw: &str) -> ClientShim {
     )
 }
 
+/// Network of the ETH test suite, `ETH_TEST_NETWORK=dev` points it at a local dev node.
+pub fn get_test_eth_network() -> EthNetwork {
+    let name = env::var("ETH_TEST_NETWORK").unwrap_or_else(|_| SEPOLIA.to_owned());
+    get_eth_network(&name).unwrap()
+}
+
 pub fn print_balance(balance: usize) {
     println!("Balance to test {:?}", balance);
 }
@@ -75,3 +89,107 @@ pub fn print_balance(balance: usize) {
 pub fn print_tx_hash(hash: &str) {
     println!("Transaction hash: {:?}", hash);
 }
+
+/// Read one HTTP request from `reader`, returning its headers (names lowercased) and body.
+fn read_http_request(reader: &mut BufReader<TcpStream>) -> (HashMap<String, String>, Vec<u8>) {
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line == "\r\n" {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.to_ascii_lowercase(), value.trim().to_owned());
+        }
+    }
+    let content_length = headers
+        .get("content-length")
+        .map_or(0, |l| l.parse().unwrap());
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).unwrap();
+    (headers, body)
+}
+
+fn write_http_response(stream: &mut TcpStream, status: u16, body: &str) {
+    write!(
+        stream,
+        "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+    .unwrap();
+}
+
+/// Local stand-in for a JSON-RPC endpoint (node, bundler): answers a single request with
+/// `result`. The handle returns the request it received.
+pub fn spawn_json_rpc_stub(result: Value) -> (String, JoinHandle<Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let (_, body) = read_http_request(&mut reader);
+        let request: Value = serde_json::from_slice(&body).unwrap();
+
+        let response =
+            json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }).to_string();
+        write_http_response(reader.get_mut(), 200, &response);
+        request
+    });
+
+    (url, handle)
+}
+
+/// Local stand-in for a node answering every JSON-RPC request with `respond(method, params)`,
+/// `Err` being sent back as the JSON-RPC error. It serves until the tests exit.
+pub fn spawn_json_rpc_node<F>(mut respond: F) -> String
+where
+    F: FnMut(&str, &Value) -> Result<Value, Value> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut reader = BufReader::new(stream.unwrap());
+            let (_, body) = read_http_request(&mut reader);
+            let request: Value = serde_json::from_slice(&body).unwrap();
+
+            let method = request["method"].as_str().unwrap_or_default();
+            let response = match respond(method, &request["params"]) {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+                Err(error) => json!({ "jsonrpc": "2.0", "id": request["id"], "error": error }),
+            };
+            write_http_response(reader.get_mut(), 200, &response.to_string());
+        }
+    });
+
+    url
+}
+
+/// Local HTTP server answering one request per entry of `responses` (status and body), in
+/// order. The handle returns the headers of the requests it received.
+pub fn spawn_http_stub(
+    responses: Vec<(u16, &'static str)>,
+) -> (String, JoinHandle<Vec<HashMap<String, String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        responses
+            .into_iter()
+            .map(|(status, body)| {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let (headers, _) = read_http_request(&mut reader);
+                write_http_response(reader.get_mut(), status, body);
+                headers
+            })
+            .collect()
+    });
+
+    (url, handle)
+}
@@ -1,11 +1,48 @@
 pub mod common;
+pub mod party_one;
 
 #[cfg(test)]
 mod ecdsa_test_suite {
+    use anyhow::{anyhow, Result};
+    use async_trait::async_trait;
+    use centipede::juggling::proof_system::{Helgamalsegmented, Proof};
+    use centipede::juggling::segmentation::Msegmentation;
+    use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+    use curv::cryptographic_primitives::twoparty::coin_flip_optimal_rounds;
+    use curv::cryptographic_primitives::twoparty::dh_key_exchange_variant_with_pok_comm::{
+        Party1FirstMessage, Party1SecondMessage,
+    };
+    use curv::elliptic::curves::secp256_k1::{FE, GE};
+    use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+    use curv::BigInt;
+    use kms::ecdsa::two_party::{party1, MasterKey2, Party2Public};
+    use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::party_one::SignatureRecid;
+    use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::{party_one, party_two};
     use serde_json::Value;
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
 
     use crate::{
-        dto::ecdsa::PrivateShare, ecdsa::rotate_private_share, tests::common::mock_client_shim,
+        dto::ecdsa::{PrivateShare, SignSecondMsgRequest},
+        ecdsa::{
+            a_get_private_share, a_rotate_private_share, a_sign,
+            recover::{backup_client_mk, verify_client_backup},
+            rotate_private_share,
+            verify::{verify_signature, SignatureError},
+            Party1Transport,
+        },
+        escrow::{self, Escrow},
+        eth::utils::pubkey_to_eth_address,
+        tests::common::{
+            get_test_private_share, mock_client_shim, spawn_http_stub, PRIVATE_SHARE_FILENAME,
+        },
+        tests::party_one::Party1Simulator,
+        utilities::a_requests::{RequestConfig, RequestError},
+        utilities::auth::{CallbackTokenProvider, TokenProvider},
+        utilities::derive_new_key,
+        utilities::requests::{post, post_idempotent, ClientShim},
         wallet::Wallet,
     };
 
@@ -48,6 +85,370 @@ mod ecdsa_test_suite {
         assert_ne!(new_x2, old_x2);
         assert_ne!(old_paillier_x, new_paillier_x);
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_async_keygen_and_rotate() {
+        let client_shim =
+            tokio::task::block_in_place(|| mock_client_shim("ROTATE_TEST_MAIL", "ROTATE_TEST_PW"));
+        let private_share = a_get_private_share(&client_shim).await.unwrap();
+        let (old_x1, old_x2) = get_coordinates_of_2_mk(&private_share);
+
+        let rotated_private_share = a_rotate_private_share(private_share, &client_shim)
+            .await
+            .unwrap();
+        let (new_x1, new_x2) = get_coordinates_of_2_mk(&rotated_private_share);
+
+        assert_ne!(new_x1, old_x1);
+        assert_ne!(new_x2, old_x2);
+    }
+
+    /// Party one that cannot be reached.
+    struct OfflineParty1;
+
+    #[async_trait]
+    impl Party1Transport for OfflineParty1 {
+        async fn keygen_first(&self) -> Result<(String, party_one::KeyGenFirstMsg)> {
+            Err(anyhow!("party one is offline"))
+        }
+
+        async fn keygen_second(
+            &self,
+            _id: &str,
+            _d_log_proof: &DLogProof<GE>,
+        ) -> Result<party1::KeyGenParty1Message2> {
+            Err(anyhow!("party one is offline"))
+        }
+
+        async fn chaincode_first(&self, _id: &str) -> Result<Party1FirstMessage> {
+            Err(anyhow!("party one is offline"))
+        }
+
+        async fn chaincode_second(
+            &self,
+            _id: &str,
+            _d_log_proof: &DLogProof<GE>,
+        ) -> Result<Party1SecondMessage<GE>> {
+            Err(anyhow!("party one is offline"))
+        }
+
+        async fn sign_first(
+            &self,
+            _id: &str,
+            _eph_key_gen_first_message: &party_two::EphKeyGenFirstMsg,
+        ) -> Result<party_one::EphKeyGenFirstMsg> {
+            Err(anyhow!("party one is offline"))
+        }
+
+        async fn sign_second(
+            &self,
+            _id: &str,
+            _request: &SignSecondMsgRequest,
+        ) -> Result<party_one::SignatureRecid> {
+            Err(anyhow!("party one is offline"))
+        }
+
+        async fn rotate_first(
+            &self,
+            _id: &str,
+        ) -> Result<coin_flip_optimal_rounds::Party1FirstMessage<GE>> {
+            Err(anyhow!("party one is offline"))
+        }
+
+        async fn rotate_second(
+            &self,
+            _id: &str,
+            _coin_flip_party2_first_message: &coin_flip_optimal_rounds::Party2FirstMessage<GE>,
+        ) -> Result<(
+            coin_flip_optimal_rounds::Party1SecondMessage<GE>,
+            party1::RotationParty1Message1,
+        )> {
+            Err(anyhow!("party one is offline"))
+        }
+
+        async fn recover(&self, _id: &str) -> Result<u32> {
+            Err(anyhow!("party one is offline"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transport_errors_are_propagated() {
+        let transport: &dyn Party1Transport = &OfflineParty1;
+        let private_share: PrivateShare = get_test_private_share(PRIVATE_SHARE_FILENAME);
+
+        let keygen = a_get_private_share(transport).await;
+        assert!(keygen.err().unwrap().to_string().contains("offline"));
+
+        let sign = a_sign(
+            transport,
+            BigInt::from(1),
+            &private_share.master_key,
+            BigInt::from(0),
+            BigInt::from(0),
+            &private_share.id,
+        )
+        .await;
+        assert!(sign.err().unwrap().to_string().contains("offline"));
+
+        let rotate = a_rotate_private_share(private_share, transport).await;
+        assert!(rotate.err().unwrap().to_string().contains("offline"));
+    }
+
+    fn stub_client_shim(url: String) -> ClientShim {
+        ClientShim::with_config(
+            url,
+            Some("token".to_owned()),
+            "user".to_owned(),
+            RequestConfig {
+                backoff: Duration::from_millis(1),
+                ..RequestConfig::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_post_retries_server_errors() -> Result<()> {
+        let (url, handle) = spawn_http_stub(vec![(503, "busy"), (500, ""), (200, "7")]);
+        let pos: Option<u32> = post_idempotent(&stub_client_shim(url), "ecdsa/id/recover")?;
+        assert_eq!(pos, Some(7));
+
+        // Every attempt carries the same request id
+        let requests = handle.join().unwrap();
+        assert_eq!(requests.len(), 3);
+        let request_id = requests[0].get("x-request-id").unwrap();
+        assert!(requests
+            .iter()
+            .all(|headers| headers.get("x-request-id") == Some(request_id)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_post_errors_by_status() -> Result<()> {
+        let post_to_stub = |status, body| {
+            let (url, handle) = spawn_http_stub(vec![(status, body)]);
+            let res = post::<u32>(&stub_client_shim(url), "ecdsa/keygen/first");
+            // Only one request, these are not retried
+            assert_eq!(handle.join().unwrap().len(), 1);
+            res
+        };
+
+        // Protocol rounds are not idempotent, server errors are returned rather than retried
+        let err = post_to_stub(503, "busy").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RequestError>(),
+            Some(RequestError::Server { status: 503, .. })
+        ));
+        let err = post_to_stub(401, "invalid token").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RequestError>(),
+            Some(RequestError::Auth { status: 401, .. })
+        ));
+        let err = post_to_stub(400, "bad message").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RequestError>(),
+            Some(RequestError::Rejected { status: 400, .. })
+        ));
+        let err = post_to_stub(200, "{\"unexpected\": true}").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RequestError>(),
+            Some(RequestError::Protocol { .. })
+        ));
+        assert_eq!(post_to_stub(204, "")?, None);
+        Ok(())
+    }
+
+    /// Hands out `token`, replaced by `fresh` once refreshed.
+    struct RefreshingToken {
+        token: Mutex<String>,
+        refreshed: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl TokenProvider for RefreshingToken {
+        async fn token(&self) -> Result<String> {
+            Ok(self.token.lock().unwrap().clone())
+        }
+
+        async fn refresh(&self, expired: &str) -> Result<String> {
+            self.refreshed.lock().unwrap().push(expired.to_owned());
+            *self.token.lock().unwrap() = "fresh".to_owned();
+            Ok("fresh".to_owned())
+        }
+    }
+
+    #[test]
+    fn test_post_refreshes_rejected_token() -> Result<()> {
+        let token_provider = Arc::new(RefreshingToken {
+            token: Mutex::new("expired".to_owned()),
+            refreshed: Mutex::new(vec![]),
+        });
+        let (url, handle) = spawn_http_stub(vec![(401, "token expired"), (200, "7")]);
+        let client_shim = stub_client_shim(url).with_token_provider(token_provider.clone());
+
+        let pos: Option<u32> = post(&client_shim, "ecdsa/id/recover")?;
+        assert_eq!(pos, Some(7));
+        assert_eq!(*token_provider.refreshed.lock().unwrap(), vec!["expired"]);
+
+        let requests = handle.join().unwrap();
+        assert_eq!(requests[0].get("authorization").unwrap(), "Bearer expired");
+        assert_eq!(requests[1].get("authorization").unwrap(), "Bearer fresh");
+
+        // A refreshed token rejected again is an auth error
+        let (url, handle) = spawn_http_stub(vec![(401, "token expired"), (401, "still expired")]);
+        let client_shim = stub_client_shim(url).with_token_provider(token_provider);
+        let err = post::<u32>(&client_shim, "ecdsa/id/recover").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RequestError>(),
+            Some(RequestError::Auth { status: 401, .. })
+        ));
+        assert_eq!(handle.join().unwrap().len(), 2);
+        Ok(())
+    }
+
+    extern "C" fn refresh_token_callback(c_expired_token: *const c_char) -> *const c_char {
+        let expired = unsafe { CStr::from_ptr(c_expired_token) };
+        if expired.to_bytes() == b"expired" {
+            b"fresh\0".as_ptr() as *const c_char
+        } else {
+            std::ptr::null()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_callback_token_provider() -> Result<()> {
+        let token_provider =
+            CallbackTokenProvider::new("expired".to_owned(), refresh_token_callback);
+        assert_eq!(token_provider.token().await?, "expired");
+        assert_eq!(token_provider.refresh("expired").await?, "fresh");
+        assert_eq!(token_provider.token().await?, "fresh");
+        assert!(token_provider.refresh("fresh").await.is_err());
+        Ok(())
+    }
+
+    /// Sign with the child key at `pos`, `a_sign` checks the joint signature.
+    async fn sign_at(
+        party_one: &Party1Simulator,
+        private_share: &PrivateShare,
+        pos: u32,
+    ) -> Result<SignatureRecid> {
+        let mk = private_share
+            .master_key
+            .get_child(vec![BigInt::from(0), BigInt::from(pos)]);
+        a_sign(
+            party_one,
+            BigInt::from(1234),
+            &mk,
+            BigInt::from(0),
+            BigInt::from(pos),
+            &private_share.id,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_simulated_key_lifecycle() -> Result<()> {
+        let party_one = Party1Simulator::new();
+
+        // Keygen and derivation
+        let private_share = a_get_private_share(&party_one).await?;
+        let (pos, mk) = derive_new_key(&private_share, 0);
+        let address = pubkey_to_eth_address(&mk);
+        sign_at(&party_one, &private_share, pos).await?;
+
+        // Rotation changes the shares, not the keys
+        let rotated = a_rotate_private_share(private_share, &party_one).await?;
+        assert_eq!(
+            pubkey_to_eth_address(&derive_new_key(&rotated, 0).1),
+            address
+        );
+        sign_at(&party_one, &rotated, pos).await?;
+
+        // Backup with the escrow and recovery
+        let backup = backup_client_mk(&rotated).map_err(|e| anyhow!(e.to_string()))?;
+        let escrow_service = Escrow::load();
+        assert!(verify_client_backup(escrow_service.get_public_key(), &backup).is_ok());
+
+        let (encryptions, _proof, public_data, chain_code2, key_id): (
+            Helgamalsegmented,
+            Proof,
+            Party2Public,
+            BigInt,
+            String,
+        ) = serde_json::from_str(&backup)?;
+        let g: GE = ECPoint::generator();
+        let sk = Msegmentation::decrypt(
+            &encryptions,
+            &g,
+            &escrow_service.get_private_key(),
+            &escrow::SEGMENT_SIZE,
+        )
+        .map_err(|_| anyhow!("unable to decrypt the backup"))?;
+        let recovered = PrivateShare {
+            id: key_id,
+            master_key: MasterKey2::recover_master_key(sk, public_data, chain_code2),
+        };
+
+        let last_pos = party_one.recover(&recovered.id).await?;
+        assert_eq!(last_pos, pos);
+        assert_eq!(
+            pubkey_to_eth_address(&derive_new_key(&recovered, 0).1),
+            address
+        );
+        sign_at(&party_one, &recovered, last_pos).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_verify_signature_normalises_high_s() -> Result<()> {
+        let party_one = Party1Simulator::new();
+        let private_share = a_get_private_share(&party_one).await?;
+        let signature = sign_at(&party_one, &private_share, 1).await?;
+        let order = FE::q();
+        assert!(signature.s <= &order / &BigInt::from(2));
+
+        // Same signature in high-S form, with the recovery id of the low-S one flipped
+        let high_s = SignatureRecid {
+            r: signature.r.clone(),
+            s: &order - &signature.s,
+            recid: signature.recid ^ 1,
+        };
+        let mk = private_share
+            .master_key
+            .get_child(vec![BigInt::from(0), BigInt::from(1)]);
+        let normalised = verify_signature(&BigInt::from(1234), &high_s, &mk)?;
+        assert_eq!(normalised.r, signature.r);
+        assert_eq!(normalised.s, signature.s);
+        assert_eq!(normalised.recid, signature.recid);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_signature_rejects_bad_signature() {
+        let private_share: PrivateShare = get_test_private_share(PRIVATE_SHARE_FILENAME);
+        let mk = private_share
+            .master_key
+            .get_child(vec![BigInt::from(0), BigInt::from(1)]);
+        let message = BigInt::from(1);
+
+        let out_of_range = SignatureRecid {
+            r: BigInt::from(0),
+            s: BigInt::from(1),
+            recid: 0,
+        };
+        assert_eq!(
+            verify_signature(&message, &out_of_range, &mk).unwrap_err(),
+            SignatureError::OutOfRange
+        );
+
+        let forged = SignatureRecid {
+            r: BigInt::from(12345),
+            s: BigInt::from(67890),
+            recid: 0,
+        };
+        assert_eq!(
+            verify_signature(&message, &forged, &mk).unwrap_err(),
+            SignatureError::Invalid
+        );
+    }
 }
 
 #[cfg(test)]
@@ -55,10 +456,12 @@ mod btc_test_suite {
 
     use crate::{
         btc::{
+            preview::{check_btc_payment, preview_btc_tx},
             raw_tx::select_tx_in,
+            uri::{btc_to_satoshi, BtcPaymentUri},
             utils::{get_all_addresses, get_bitcoin_network, get_new_address, BTC_TESTNET},
         },
-        dto::ecdsa::PrivateShare,
+        dto::{btc::UtxoAggregator, ecdsa::PrivateShare},
         tests::common::{
             get_test_private_share, mock_client_shim, print_balance, print_tx_hash,
             PRIVATE_SHARE_FILENAME,
@@ -67,8 +470,9 @@ mod btc_test_suite {
         wallet::Wallet,
     };
     use anyhow::Result;
-    use bitcoin::Network;
+    use bitcoin::{Network, OutPoint, TxIn, TxOut};
     use curv::elliptic::curves::traits::ECPoint;
+    use std::{collections::HashSet, str::FromStr};
     const SENT_BTC: f64 = 0.000001; // 100 satoshi
     #[test]
     fn test_get_bitcoin_network() -> Result<()> {
@@ -120,13 +524,114 @@ mod btc_test_suite {
         Ok(())
     }
 
+    #[test]
+    fn test_preview_btc_tx() -> Result<()> {
+        let own = "tb1qxyjt450heqv4ql8k7rp2qfmd4vrmncaquzw37r";
+        let to = "tb1qz4lma0u0xyepgkzlsegxfxw7e65ue7azhkck5m";
+        let output = |address: &str, value| -> Result<TxOut> {
+            Ok(TxOut {
+                value,
+                script_pubkey: bitcoin::Address::from_str(address)?.script_pubkey(),
+            })
+        };
+        let tx = bitcoin::Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::default(),
+                script_sig: bitcoin::Script::default(),
+                sequence: 0xFFFFFFFF,
+                witness: Vec::default(),
+            }],
+            output: vec![output(to, 30_000)?, output(own, 60_000)?],
+        };
+        let spent = [UtxoAggregator {
+            height: 1,
+            tx_hash: OutPoint::default().txid.to_string(),
+            tx_pos: 0,
+            value: 100_000,
+            address: own.to_owned(),
+        }];
+        let own_addresses: HashSet<String> = [own.to_owned()].into_iter().collect();
+
+        let preview = preview_btc_tx(&tx, &spent, Network::Testnet, &own_addresses)?;
+        assert_eq!(preview.sent, 30_000);
+        assert_eq!(preview.change, 60_000);
+        assert_eq!(preview.fee, 10_000);
+        assert!(preview.outputs[1].is_change);
+        check_btc_payment(&preview, to, 30_000)?;
+        assert!(check_btc_payment(&preview, to, 20_000).is_err());
+        assert!(check_btc_payment(&preview, own, 60_000).is_err());
+
+        // Change paid elsewhere is caught as an extra payment
+        let preview = preview_btc_tx(&tx, &spent, Network::Testnet, &HashSet::new())?;
+        assert!(check_btc_payment(&preview, to, 30_000).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_btc_payment_uri() -> Result<()> {
+        let uri = "bitcoin:tb1qz4lma0u0xyepgkzlsegxfxw7e65ue7azhkck5m?amount=0.0015&label=Coffee%20shop&foo=bar";
+        let payment_uri = BtcPaymentUri::parse(uri, Network::Testnet)?;
+        assert_eq!(
+            payment_uri,
+            BtcPaymentUri::new(
+                "tb1qz4lma0u0xyepgkzlsegxfxw7e65ue7azhkck5m",
+                Some(150_000),
+                Some("Coffee shop".to_owned())
+            )
+        );
+        assert_eq!(
+            payment_uri.to_string(),
+            "bitcoin:tb1qz4lma0u0xyepgkzlsegxfxw7e65ue7azhkck5m?amount=0.0015&label=Coffee%20shop"
+        );
+        assert_eq!(
+            BtcPaymentUri::parse(&payment_uri.to_string(), Network::Testnet)?,
+            payment_uri
+        );
+
+        // QR codes carry bech32 addresses in upper case
+        let uri = "BITCOIN:TB1QZ4LMA0U0XYEPGKZLSEGXFXW7E65UE7AZHKCK5M?amount=0.0015";
+        assert_eq!(
+            BtcPaymentUri::parse(uri, Network::Testnet)?.address,
+            "tb1qz4lma0u0xyepgkzlsegxfxw7e65ue7azhkck5m"
+        );
+
+        // Mainnet address, unknown required parameter, sub-satoshi amount
+        for uri in [
+            "bitcoin:bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "bitcoin:tb1qz4lma0u0xyepgkzlsegxfxw7e65ue7azhkck5m?req-foo=1",
+            "bitcoin:tb1qz4lma0u0xyepgkzlsegxfxw7e65ue7azhkck5m?amount=0.000000001",
+            "ethereum:tb1qz4lma0u0xyepgkzlsegxfxw7e65ue7azhkck5m",
+        ] {
+            assert!(
+                BtcPaymentUri::parse(uri, Network::Testnet).is_err(),
+                "{}",
+                uri
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_btc_to_satoshi() -> Result<()> {
+        assert_eq!(btc_to_satoshi(0.0015)?, 150_000);
+        // 0.29 * 1e8 is just below 29_000_000 as a float
+        assert_eq!(btc_to_satoshi(0.29)?, 29_000_000);
+        assert_eq!(btc_to_satoshi(21e6)?, 2_100_000_000_000_000);
+        for amount in [0.000000001, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(btc_to_satoshi(amount).is_err(), "{}", amount);
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_send_btc_w_rotate() {
         // expect the server running
         let w_file: &str = "test-assets/btc_w.json";
         let client_shim = mock_client_shim("BTC_TEST_MAIL", "BTC_TEST_PW");
         let mut w: Wallet = Wallet::load_from(w_file);
-        let unspent_amount = w.get_balance();
+        let unspent_amount = w.get_balance().unwrap();
         print_balance(unspent_amount);
         if unspent_amount <= 10000 {
             return;
@@ -135,12 +640,14 @@ mod btc_test_suite {
         w.rotate(&client_shim, w_file);
         let mut new_w = Wallet::load_from(w_file);
         let to_send = SENT_BTC;
-        let txid = new_w.send(
-            "",                                           // btc doesn't need to specify from address
-            "tb1qz4lma0u0xyepgkzlsegxfxw7e65ue7azhkck5m", // to address in our btc_w
-            to_send,
-            &client_shim,
-        );
+        let txid = new_w
+            .send(
+                "",                                           // btc doesn't need to specify from address
+                "tb1qz4lma0u0xyepgkzlsegxfxw7e65ue7azhkck5m", // to address in our btc_w
+                to_send,
+                &client_shim,
+            )
+            .unwrap();
         print_tx_hash(&txid);
         new_w.save_to(w_file);
         assert!(!txid.is_empty());
@@ -150,18 +657,63 @@ mod btc_test_suite {
 #[cfg(test)]
 mod eth_test_suite {
     use anyhow::Result;
+    use chrono::{Duration, Utc};
     use curv::BigInt;
-    use web3::types::U256;
+    use serde_json::{json, Value};
+    use std::{
+        collections::HashMap,
+        str::FromStr,
+        sync::{Arc, Mutex},
+    };
+    use web3::ethabi::Token;
+    use web3::signing::{keccak256, Key, SecretKey, SecretKeyRef};
+    use web3::types::{
+        AccessListItem, Address, CallRequest, Log, TransactionParameters, H2048, H256, U256, U64,
+    };
 
     use crate::{
-        dto::ecdsa::PrivateShare,
-        eth::utils::{
-            get_all_addresses, get_all_addresses_balance, pubkey_to_eth_address, wei_to_eth,
+        dto::{
+            ecdsa::PrivateShare,
+            eth::{EthCallPreview, EthTransferHistory, EthTransferKind, EthTxRecord, EthTxStatus},
+        },
+        eth::{
+            abi::encode_call,
+            access_list::{find_gas_saving_access_list, AccessListWithGasUsed},
+            address::EthAddress,
+            funding::{a_plan_eth_funding, plan_funding},
+            key::TssKey,
+            message::{hash_personal_message, recover_signer},
+            network::{
+                get_eth_network, get_eth_network_by_chain_id, EthNetwork, ARBITRUM, BSC, DEV,
+                DEV_CHAIN_ID, POLYGON, SEPOLIA,
+            },
+            nft::{NftStandard, NftTransfer},
+            permit::{permit2_typed_data, permit_typed_data, TokenDomain},
+            preview::{check_erc20_transfer, check_native_transfer, decode_call, preview_eth_tx},
+            raw_tx::{build_tx, check_signed_tx, check_tx_fees},
+            receipt::{a_wait_for_eth_tx_confirmations, get_tx_receipt, wait_for_confirmations},
+            replace::{build_replacement_tx, EthReplacementFees, EthTxReplacement, CANCEL_TX_GAS},
+            safe::{encode_signatures, SafeSignature, SafeTx},
+            siwe::SiweMessage,
+            sync::{parse_erc20_transfer, sync_transfers, LOG_BLOCK_RANGE, REORG_DEPTH},
+            transaction::Transaction,
+            transport::{get_connection, TransportKind},
+            typed_data::TypedData,
+            uri::EthPaymentUri,
+            user_operation::{
+                account_init_code, create2_address, send_user_operation, UserOperation,
+                ENTRY_POINT_V06,
+            },
+            utils::{
+                establish_web3_connection, get_all_addresses, get_all_addresses_balance,
+                pubkey_to_eth_address, wei_to_eth,
+            },
         },
         tests::common::{
-            get_test_private_share, mock_client_shim, print_balance, print_tx_hash,
-            PRIVATE_SHARE_FILENAME, RINKEBY_TEST_API,
+            get_test_eth_network, get_test_private_share, mock_client_shim, print_balance,
+            print_tx_hash, spawn_json_rpc_node, spawn_json_rpc_stub, PRIVATE_SHARE_FILENAME,
         },
+        utilities::runtime::{block_on, on_shared_runtime},
         wallet::Wallet,
     };
     const ADDRESS_FROM_PUBKEY_OF_MK2: &str = "0xa83b17156ce2a750e7550d3b00d7968463bd759a";
@@ -169,6 +721,38 @@ mod eth_test_suite {
     const SENT_ETH: f64 = 0.001; // 1_000_000_000_000_000 wei
     const FROM_ADDRESS: &str = "0x6f976d41d02a024a03c554dba0f5eda4afaace3f";
     const TO_ADDRESS: &str = "0x9f3ea7e363dc7d4afc089ed4d3e0b9f15ebc2319";
+    // Example from the EIP-712 specification
+    const MAIL_TYPED_DATA: &str = r#"{
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" }
+            ],
+            "Person": [
+                { "name": "name", "type": "string" },
+                { "name": "wallet", "type": "address" }
+            ],
+            "Mail": [
+                { "name": "from", "type": "Person" },
+                { "name": "to", "type": "Person" },
+                { "name": "contents", "type": "string" }
+            ]
+        },
+        "primaryType": "Mail",
+        "domain": {
+            "name": "Ether Mail",
+            "version": "1",
+            "chainId": 1,
+            "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+        },
+        "message": {
+            "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+            "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+            "contents": "Hello, Bob!"
+        }
+    }"#;
     #[test]
     fn test_pubkey_to_eth_address() -> Result<()> {
         let private_share: PrivateShare = get_test_private_share(PRIVATE_SHARE_FILENAME);
@@ -182,6 +766,35 @@ mod eth_test_suite {
         Ok(())
     }
 
+    #[test]
+    fn test_eth_address_checksum() -> Result<()> {
+        // Test vectors from EIP-55
+        for checksummed in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            let address = EthAddress::from_str(checksummed)?;
+            assert_eq!(address.to_string(), checksummed);
+            assert_eq!(EthAddress::from_str(&checksummed.to_lowercase())?, address);
+        }
+        assert!(EthAddress::from_str("0x5aaeb6053F3E94C9b9A09f33669435E7Ef1BeAed").is_err());
+        assert!(EthAddress::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_eth_address_map_key() -> Result<()> {
+        let mut map = HashMap::new();
+        map.insert(EthAddress::from_str(ADDRESS_FROM_PUBKEY_OF_MK2)?, 1u32);
+        let json = serde_json::to_string(&map)?;
+        assert_eq!(json, r#"{"0xA83b17156Ce2A750e7550d3B00D7968463bD759a":1}"#);
+        let decoded: HashMap<EthAddress, u32> = serde_json::from_str(&json)?;
+        assert_eq!(decoded, map);
+        Ok(())
+    }
+
     #[test]
     fn test_get_all_addresses() -> Result<()> {
         let private_share: PrivateShare = get_test_private_share(PRIVATE_SHARE_FILENAME);
@@ -198,10 +811,952 @@ mod eth_test_suite {
         Ok(())
     }
 
+    #[test]
+    fn test_get_eth_network() -> Result<()> {
+        assert_eq!(get_eth_network("testnet")?, get_eth_network(SEPOLIA)?);
+        assert_eq!(get_eth_network(SEPOLIA)?.chain_id, 11155111);
+        assert_eq!(get_eth_network(DEV)?.chain_id, DEV_CHAIN_ID);
+        assert!(get_eth_network("rinkeby").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_evm_networks() -> Result<()> {
+        let bsc = get_eth_network(BSC)?;
+        assert_eq!(bsc.chain_id, 56);
+        assert_eq!(bsc.currency_symbol, "BNB");
+        assert!(!bsc.eip1559);
+        assert_eq!(get_eth_network(POLYGON)?.currency_symbol, "POL");
+        assert_eq!(get_eth_network_by_chain_id(42161)?.name, ARBITRUM);
+        assert!(get_eth_network_by_chain_id(4).is_err());
+
+        // Networks given as JSON default to an EIP-1559 chain with 18 decimals
+        let custom: EthNetwork =
+            serde_json::from_str(r#"{"name":"base","chain_id":8453,"rpc_urls":[]}"#)?;
+        assert_eq!(custom.decimals, 18);
+        assert!(custom.eip1559);
+        assert_eq!(
+            custom.from_native(0.5)?,
+            U256::from(500_000_000_000_000_000u64)
+        );
+        assert_eq!(
+            custom.to_native(U256::from(250_000_000_000_000_000u64)),
+            0.25
+        );
+        assert!(custom.from_native(-1.0).is_err());
+        assert!(custom.from_native(f64::NAN).is_err());
+        assert!(custom.from_native(f64::INFINITY).is_err());
+        assert!(custom.from_native(1e30).is_err());
+
+        // Amounts above u128::MAX convert too
+        assert_eq!(custom.to_native(U256::exp10(40)), 1e22);
+        assert!(custom.to_native(U256::MAX).is_finite());
+        Ok(())
+    }
+
+    #[test]
+    fn test_access_list_response() -> Result<()> {
+        // Example response of geth's eth_createAccessList
+        let response = r#"{
+            "accessList": [{
+                "address": "0xa02457e5dfd32bda5fc7e1f1b008aa5979568150",
+                "storageKeys": [
+                    "0x0000000000000000000000000000000000000000000000000000000000000081"
+                ]
+            }],
+            "gasUsed": "0x125f8"
+        }"#;
+        let access_list: AccessListWithGasUsed = serde_json::from_str(response)?;
+        assert_eq!(access_list.access_list.len(), 1);
+        assert_eq!(access_list.access_list[0].storage_keys.len(), 1);
+        assert_eq!(access_list.gas_used, U256::from(0x125f8));
+        assert_eq!(access_list.error, None);
+        Ok(())
+    }
+
+    /// Legacy-fee node estimating 50000 gas for a contract call, `gas_with_list` with the
+    /// access list it returns, or without `eth_createAccessList` if `gas_with_list` is unset.
+    fn spawn_access_list_node(gas_with_list: Option<u64>) -> String {
+        spawn_json_rpc_node(move |method, params| match (method, gas_with_list) {
+            ("eth_getTransactionCount", _) => Ok(json!("0x5")),
+            ("eth_gasPrice", _) => Ok(json!("0x3b9aca00")),
+            ("eth_estimateGas", Some(gas)) if !params[0]["accessList"].is_null() => {
+                Ok(json!(U256::from(gas)))
+            }
+            ("eth_estimateGas", _) => Ok(json!(U256::from(50_000))),
+            ("eth_createAccessList", Some(_)) => Ok(json!({
+                "accessList": [{
+                    "address": Address::repeat_byte(0x0c),
+                    "storageKeys": [H256::repeat_byte(0x01)]
+                }],
+                "gasUsed": "0xc350"
+            })),
+            _ => Err(json!({ "code": -32601, "message": "method not found" })),
+        })
+    }
+
+    #[test]
+    fn test_build_tx_access_list() -> Result<()> {
+        let (from, to) = (Address::repeat_byte(0x0a), Address::repeat_byte(0x0b));
+        let data = vec![0xa9, 0x05, 0x9c, 0xbb];
+        let build = |url: &str| {
+            let network = EthNetwork::new("stub", 1, &[url]).without_eip1559();
+            block_on(async {
+                let web3_connection = establish_web3_connection(url).await?;
+                let call = CallRequest {
+                    from: Some(from),
+                    to: Some(to),
+                    data: Some(data.clone().into()),
+                    ..Default::default()
+                };
+                let found =
+                    find_gas_saving_access_list(&web3_connection, &call, U256::from(50_000))
+                        .await?;
+                let tx = build_tx(
+                    &web3_connection,
+                    &network,
+                    from,
+                    to,
+                    U256::zero(),
+                    data.clone(),
+                )
+                .await?;
+                Ok::<_, anyhow::Error>((found, tx))
+            })
+        };
+
+        // The list saves gas: attached, with its gas limit, as an access list transaction
+        let (found, tx) = build(&spawn_access_list_node(Some(48_000)))?;
+        let (access_list, gas) = found.unwrap();
+        assert_eq!(access_list[0].address, Address::repeat_byte(0x0c));
+        assert_eq!(gas, U256::from(48_000));
+        assert_eq!(tx.access_list, access_list);
+        assert_eq!(tx.gas, U256::from(48_000));
+        assert_eq!(tx.transaction_type, Some(U64::from(1)));
+        assert_eq!(tx.nonce, U256::from(5));
+
+        // The list costs more: skipped
+        let (found, tx) = build(&spawn_access_list_node(Some(52_000)))?;
+        assert_eq!(found, None);
+        assert!(tx.access_list.is_empty());
+        assert_eq!(tx.gas, U256::from(50_000));
+        assert_eq!(tx.transaction_type, None);
+
+        // Nodes without eth_createAccessList still get the transaction
+        let url = spawn_access_list_node(None);
+        let network = EthNetwork::new("stub", 1, &[&url]).without_eip1559();
+        let tx = block_on(async {
+            let web3_connection = establish_web3_connection(&url).await?;
+            build_tx(
+                &web3_connection,
+                &network,
+                from,
+                to,
+                U256::zero(),
+                data.clone(),
+            )
+            .await
+        })?;
+        assert!(tx.access_list.is_empty());
+        assert_eq!(tx.gas, U256::from(50_000));
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_funding() -> Result<()> {
+        let eth = U256::exp10(18);
+        let gas_price = U256::exp10(9);
+        let gas = U256::from(21000);
+        let (a, b, c) = (
+            Address::repeat_byte(0x0a),
+            Address::repeat_byte(0x0b),
+            Address::repeat_byte(0x0c),
+        );
+        let to = Address::repeat_byte(0x01);
+        let balances = [(a, eth), (b, eth * 3 / 10), (c, eth / 2)];
+
+        // Smallest balance that covers value and fee
+        let plan = plan_funding(&balances, to, eth * 4 / 10, gas, gas_price, false)?;
+        assert!(plan.sweeps.is_empty());
+        assert_eq!(plan.payment.from, c);
+        assert_eq!(plan.payment.max_fee, gas * gas_price);
+
+        let value = eth * 15 / 10;
+        assert!(plan_funding(&balances, to, value, gas, gas_price, false).is_err());
+        let plan = plan_funding(&balances, to, value, gas, gas_price, true)?;
+        assert_eq!(plan.payment.from, a);
+        assert_eq!(plan.sweeps.len(), 2);
+        assert_eq!(plan.sweeps[0].from, c);
+        assert_eq!(plan.sweeps[0].to, a);
+        assert_eq!(plan.sweeps[0].value, eth / 2 - gas * gas_price);
+        assert_eq!(plan.sweeps[1].from, b);
+
+        // Fees went up since planning: the sweep still pays the planned ones, so that value
+        // and fee never exceed the swept balance
+        let sweep = &plan.sweeps[0];
+        let built_now = Transaction {
+            to: Some(sweep.to),
+            nonce: U256::from(3),
+            gas: U256::from(25_000),
+            gas_price: gas_price * 3,
+            value: sweep.value,
+            data: vec![],
+            transaction_type: Some(U64::from(2)),
+            access_list: vec![],
+            max_priority_fee_per_gas: gas_price * 2,
+        };
+        let tx = sweep.with_planned_fee(built_now);
+        assert_eq!(tx.nonce, U256::from(3));
+        assert_eq!(tx.gas, U256::from(CANCEL_TX_GAS));
+        assert_eq!(tx.gas_price, gas_price);
+        assert_eq!(tx.max_priority_fee_per_gas, gas_price);
+        assert_eq!(tx.value + tx.gas * tx.gas_price, eth / 2);
+
+        assert!(plan_funding(&balances, to, eth * 2, gas, gas_price, true).is_err());
+        Ok(())
+    }
+
+    /// Legacy-fee node where `payable` needs 45000 gas when paid, 21000 otherwise.
+    fn spawn_funding_node(balances: Vec<(Address, U256)>, payable: Address) -> String {
+        spawn_json_rpc_node(move |method, params| match method {
+            "eth_chainId" => Ok(json!(U256::from(DEV_CHAIN_ID))),
+            "eth_gasPrice" => Ok(json!(U256::exp10(9))),
+            "eth_getBalance" => {
+                let address: Address = serde_json::from_value(params[0].clone()).unwrap();
+                let balance = balances.iter().find(|(a, _)| *a == address).unwrap().1;
+                Ok(json!(balance))
+            }
+            "eth_estimateGas" => {
+                let call = &params[0];
+                let paid = !call["value"].is_null() && !call["from"].is_null();
+                if call["to"] == json!(payable) && paid {
+                    Ok(json!(U256::from(45_000)))
+                } else {
+                    Ok(json!(U256::from(21_000)))
+                }
+            }
+            _ => Err(json!({ "code": -32601, "message": "method not found" })),
+        })
+    }
+
+    #[test]
+    fn test_plan_eth_funding_estimates_paid_call() -> Result<()> {
+        let eth = U256::exp10(18);
+        let (a, b) = (Address::repeat_byte(0x0a), Address::repeat_byte(0x0b));
+        let payable = Address::repeat_byte(0x0c);
+        let url = spawn_funding_node(vec![(a, eth), (b, eth / 2)], payable);
+        let network = EthNetwork::new("stub", DEV_CHAIN_ID, &[&url]).without_eip1559();
+
+        // Estimated from the sender, with the value
+        let plan = block_on(a_plan_eth_funding(
+            &network,
+            &[a, b],
+            payable,
+            eth / 10,
+            false,
+        ))?;
+        assert_eq!(plan.payment.from, b);
+        assert_eq!(plan.payment.gas, U256::from(45_000));
+        assert_eq!(plan.payment.max_fee, U256::from(45_000) * U256::exp10(9));
+
+        let plan = block_on(a_plan_eth_funding(
+            &network,
+            &[a, b],
+            Address::repeat_byte(0x01),
+            eth / 10,
+            false,
+        ))?;
+        assert_eq!(plan.payment.gas, U256::from(21_000));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_erc20_transfer() -> Result<()> {
+        let log = r#"{
+            "address": "0xdac17f958d2ee523a2206206994597c13d831ec7",
+            "topics": [
+                "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+                "0x0000000000000000000000000a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a",
+                "0x0000000000000000000000000b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b"
+            ],
+            "data": "0x00000000000000000000000000000000000000000000000000000000000f4240",
+            "blockNumber": "0x10",
+            "transactionHash": "0x1111111111111111111111111111111111111111111111111111111111111111",
+            "logIndex": "0x2"
+        }"#;
+        let log: Log = serde_json::from_str(log)?;
+        let transfer = parse_erc20_transfer(&log).unwrap();
+        assert_eq!(transfer.kind, EthTransferKind::Erc20);
+        assert_eq!(transfer.token, Some(log.address));
+        assert_eq!(transfer.from, Address::repeat_byte(0x0a));
+        assert_eq!(transfer.to, Address::repeat_byte(0x0b));
+        assert_eq!(transfer.value, U256::from(1_000_000));
+        assert_eq!(transfer.block_number, U64::from(16));
+        assert_eq!(transfer.log_index, Some(U256::from(2)));
+
+        // ERC-721 Transfer: same signature, token id indexed
+        let mut nft_log = log.clone();
+        nft_log.topics.push(H256::from_low_u64_be(42));
+        nft_log.data = vec![].into();
+        assert!(parse_erc20_transfer(&nft_log).is_none());
+
+        let mut removed_log = log;
+        removed_log.removed = Some(true);
+        assert!(parse_erc20_transfer(&removed_log).is_none());
+        Ok(())
+    }
+
+    fn stub_transfer_log(tx: u8, block: u64, from: Address, to: Address) -> Value {
+        json!({
+            "address": Address::repeat_byte(0xcc),
+            "topics": [
+                H256::from(keccak256(b"Transfer(address,address,uint256)")),
+                H256::from(from),
+                H256::from(to),
+            ],
+            "data": H256::from_low_u64_be(1_000),
+            "blockNumber": U64::from(block),
+            "transactionHash": H256::repeat_byte(tx),
+            "logIndex": "0x0",
+        })
+    }
+
+    /// Node at block `latest` holding `logs`, answering `eth_getLogs` by block range and
+    /// topics. The block ranges asked for are recorded in `ranges`.
+    fn spawn_transfer_node(
+        latest: Arc<Mutex<u64>>,
+        logs: Arc<Mutex<Vec<Value>>>,
+        ranges: Arc<Mutex<Vec<(u64, u64)>>>,
+    ) -> String {
+        let block = |v: &Value| u64::from_str_radix(&v.as_str().unwrap()[2..], 16).unwrap();
+        let topic_matches = |filter: &Value, topic: &Value| match filter {
+            Value::Null => true,
+            Value::Array(topics) => topics.contains(topic),
+            filter => filter == topic,
+        };
+        spawn_json_rpc_node(move |method, params| match method {
+            "eth_blockNumber" => Ok(json!(U64::from(*latest.lock().unwrap()))),
+            "eth_getBlockByNumber" => Ok(Value::Null),
+            "eth_getLogs" => {
+                let filter = &params[0];
+                let (from, to) = (block(&filter["fromBlock"]), block(&filter["toBlock"]));
+                ranges.lock().unwrap().push((from, to));
+                let logs = logs.lock().unwrap();
+                Ok(logs
+                    .iter()
+                    .filter(|log| (from..=to).contains(&block(&log["blockNumber"])))
+                    .filter(|log| {
+                        (0..3).all(|i| topic_matches(&filter["topics"][i], &log["topics"][i]))
+                    })
+                    .cloned()
+                    .collect())
+            }
+            _ => Err(json!({ "code": -32601, "message": "method not found" })),
+        })
+    }
+
+    #[test]
+    fn test_sync_transfers_reorg() -> Result<()> {
+        let (ours, other) = (Address::repeat_byte(0x0a), Address::repeat_byte(0x0b));
+        let first_block = 100;
+        let latest_block = first_block + LOG_BLOCK_RANGE;
+        let sent = stub_transfer_log(0x01, first_block + 400, ours, other);
+        let reorged = stub_transfer_log(0x02, latest_block - 5, other, ours);
+        let unrelated = stub_transfer_log(0x03, latest_block - 4, other, other);
+
+        let latest = Arc::new(Mutex::new(latest_block));
+        let logs = Arc::new(Mutex::new(vec![sent, reorged, unrelated]));
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let url = spawn_transfer_node(latest.clone(), logs.clone(), ranges.clone());
+
+        let mut history = EthTransferHistory::default();
+        block_on(async {
+            let connection = get_connection(&url).await?;
+
+            // First sync: two chunks, sent and received transfers found
+            let new = sync_transfers(
+                connection.web3(),
+                &[ours],
+                &mut history,
+                Some(U64::from(first_block)),
+            )
+            .await?;
+            let hashes: Vec<H256> = new.iter().map(|t| t.tx_hash).collect();
+            assert_eq!(hashes, [H256::repeat_byte(0x01), H256::repeat_byte(0x02)]);
+            assert_eq!(history.synced_block, Some(U64::from(latest_block)));
+            assert_eq!(history.transfers[0].confirmations, LOG_BLOCK_RANGE - 399);
+            let mut scanned = ranges.lock().unwrap().clone();
+            scanned.dedup();
+            assert_eq!(
+                scanned,
+                [
+                    (first_block, latest_block - 1),
+                    (latest_block, latest_block)
+                ]
+            );
+
+            // Reorg: the received transfer is replaced by another one, two blocks later
+            *latest.lock().unwrap() = latest_block + 2;
+            logs.lock().unwrap()[1] = stub_transfer_log(0x04, latest_block - 3, other, ours);
+            ranges.lock().unwrap().clear();
+
+            let new = sync_transfers(connection.web3(), &[ours], &mut history, None).await?;
+            assert_eq!(new.len(), 1);
+            assert_eq!(new[0].tx_hash, H256::repeat_byte(0x04));
+            assert_eq!(new[0].confirmations, 6);
+            let hashes: Vec<H256> = history.transfers.iter().map(|t| t.tx_hash).collect();
+            assert_eq!(hashes, [H256::repeat_byte(0x01), H256::repeat_byte(0x04)]);
+            assert_eq!(history.synced_block, Some(U64::from(latest_block + 2)));
+            let rescan_from = latest_block + 1 - REORG_DEPTH;
+            assert!(ranges
+                .lock()
+                .unwrap()
+                .iter()
+                .all(|range| *range == (rescan_from, latest_block + 2)));
+
+            // No address, no log filter that would match everyone's transfers
+            ranges.lock().unwrap().clear();
+            let mut empty_history = EthTransferHistory::default();
+            let new = sync_transfers(
+                connection.web3(),
+                &[],
+                &mut empty_history,
+                Some(U64::from(latest_block)),
+            )
+            .await?;
+            assert!(new.is_empty());
+            assert!(ranges.lock().unwrap().is_empty());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_create2_address() -> Result<()> {
+        // Test vectors from EIP-1014
+        let address = create2_address(Address::zero(), H256::zero(), keccak256(&[0x00]));
+        assert_eq!(
+            EthAddress::from(address).to_string(),
+            "0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38"
+        );
+        let address = create2_address(
+            Address::from_str("00000000000000000000000000000000deadbeef")?,
+            H256::from_low_u64_be(0xcafebabe),
+            keccak256(&[0xde, 0xad, 0xbe, 0xef]),
+        );
+        assert_eq!(
+            EthAddress::from(address).to_string(),
+            "0x60f3f640a8508fC6a86d45DF051962668E1e8AC7"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_user_operation_hash() -> Result<()> {
+        let entry_point: Address = EthAddress::from_str(ENTRY_POINT_V06)?.into();
+        let factory = Address::repeat_byte(0xfa);
+        let owner = Address::from_str(&FROM_ADDRESS[2..])?;
+        let user_operation = UserOperation {
+            sender: Address::repeat_byte(0x5a),
+            init_code: account_init_code(factory, owner, U256::zero()),
+            call_gas_limit: U256::from(100_000),
+            verification_gas_limit: U256::from(500_000),
+            pre_verification_gas: U256::from(50_000),
+            max_fee_per_gas: U256::from(30_000_000_000u64),
+            max_priority_fee_per_gas: U256::from(1_000_000_000),
+            ..Default::default()
+        };
+        assert_eq!(&user_operation.init_code.0[..20], factory.as_bytes());
+        assert_eq!(user_operation.pack().len(), 10 * 32);
+
+        // The hash binds the entry point and the chain, not the signature
+        let hash = user_operation.hash(entry_point, 1);
+        assert_ne!(hash, user_operation.hash(entry_point, 137));
+        assert_ne!(hash, user_operation.hash(factory, 1));
+        let signed = UserOperation {
+            signature: vec![1; 65].into(),
+            ..user_operation.clone()
+        };
+        assert_eq!(signed.hash(entry_point, 1), hash);
+
+        let json = serde_json::to_value(&user_operation)?;
+        assert_eq!(json["callGasLimit"], "0x186a0");
+        assert_eq!(json["paymasterAndData"], "0x");
+        Ok(())
+    }
+
+    #[test]
+    fn test_send_user_operation() -> Result<()> {
+        let user_op_hash = H256::repeat_byte(0xab);
+        let (bundler_url, request) = spawn_json_rpc_stub(serde_json::to_value(user_op_hash)?);
+        let entry_point: Address = EthAddress::from_str(ENTRY_POINT_V06)?.into();
+        let user_operation = UserOperation {
+            sender: Address::repeat_byte(0x5a),
+            signature: vec![1; 65].into(),
+            ..Default::default()
+        };
+
+        let result = block_on(send_user_operation(
+            &bundler_url,
+            &user_operation,
+            entry_point,
+        ))?;
+        assert_eq!(result, user_op_hash);
+
+        let request = request.join().unwrap();
+        assert_eq!(request["method"], "eth_sendUserOperation");
+        assert_eq!(request["params"][0], serde_json::to_value(&user_operation)?);
+        assert_eq!(request["params"][1], serde_json::to_value(entry_point)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_safe_tx() -> Result<()> {
+        let safe = Address::repeat_byte(0x5a);
+        let safe_tx = SafeTx {
+            to: Address::from_str(&TO_ADDRESS[2..])?,
+            value: U256::from(1_000_000_000_000_000u64),
+            nonce: U256::from(3),
+            ..Default::default()
+        };
+
+        // SAFE_TX_TYPEHASH and DOMAIN_SEPARATOR_TYPEHASH of the Safe contracts
+        let typed_data = safe_tx.to_typed_data(safe, 1)?;
+        assert_eq!(
+            hex::encode(typed_data.type_hash("SafeTx")?),
+            "bb8310d486368db6bd6f849402fdd73ad53d316b5a4b2644ad6efe0f941286d8"
+        );
+        assert_eq!(
+            hex::encode(typed_data.type_hash("EIP712Domain")?),
+            "47e79534a245952e8b16893a336b85a3d9ea9fa8c573f3d803afb92a79469218"
+        );
+        assert_ne!(safe_tx.hash(safe, 1)?, safe_tx.hash(safe, 137)?);
+
+        let owner_a = SafeSignature {
+            owner: Address::repeat_byte(0x0a),
+            signature: vec![0xaa; 65].into(),
+        };
+        let owner_b = SafeSignature {
+            owner: Address::repeat_byte(0x0b),
+            signature: vec![0xbb; 65].into(),
+        };
+        let signatures = encode_signatures(&[owner_b.clone(), owner_a.clone()])?;
+        assert_eq!(&signatures.0[..65], &owner_a.signature.0[..]);
+        assert_eq!(&signatures.0[65..], &owner_b.signature.0[..]);
+
+        let data = safe_tx.exec_transaction_data(&[owner_a.clone(), owner_b], 2)?;
+        assert_eq!(hex::encode(&data.0[..4]), "6a761202");
+        assert!(safe_tx
+            .exec_transaction_data(&[owner_a.clone(), owner_a], 2)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_nft_transfer_calldata() -> Result<()> {
+        let from = Address::repeat_byte(0x0a);
+        let to = Address::repeat_byte(0x0b);
+        let contract = Address::repeat_byte(0x0c);
+
+        let erc721 = NftTransfer::erc721(contract, to, U256::from(42));
+        let data = erc721.calldata(from);
+        assert_eq!(hex::encode(&data[..4]), "42842e0e");
+        assert_eq!(data.len(), 4 + 3 * 32);
+        assert_eq!(&data[16..36], from.as_bytes());
+
+        let erc1155 = NftTransfer::erc1155(contract, to, U256::from(42), U256::from(3));
+        let data = erc1155.calldata(from);
+        assert_eq!(hex::encode(&data[..4]), "f242432a");
+        // 4 static words, the bytes offset and an empty bytes length
+        assert_eq!(data.len(), 4 + 6 * 32);
+
+        let parsed: NftTransfer = serde_json::from_str(&format!(
+            r#"{{"standard":"erc721","contract":"{:?}","to":"{:?}","tokenId":"0x2a"}}"#,
+            contract, to
+        ))?;
+        assert_eq!(parsed.standard, NftStandard::Erc721);
+        assert_eq!(parsed.calldata(from), erc721.calldata(from));
+        assert!(parsed.check_amount().is_ok());
+
+        // An ERC-1155 transfer without amount would move nothing
+        let parsed: NftTransfer = serde_json::from_str(&format!(
+            r#"{{"standard":"erc1155","contract":"{:?}","to":"{:?}","tokenId":"0x2a"}}"#,
+            contract, to
+        ))?;
+        assert!(parsed.check_amount().is_err());
+        assert!(erc1155.check_amount().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_call() -> Result<()> {
+        let token = Address::repeat_byte(0x0c);
+        let to = Address::repeat_byte(0x0b);
+        let amount = U256::from(1_000_000);
+
+        let data = encode_call(
+            "transfer(address,uint256)",
+            &[Token::Address(to), Token::Uint(amount)],
+        );
+        let call = decode_call(Some(token), &data);
+        assert_eq!(call, EthCallPreview::Erc20Transfer { token, to, amount });
+
+        let data = encode_call(
+            "approve(address,uint256)",
+            &[Token::Address(to), Token::Uint(U256::MAX)],
+        );
+        assert_eq!(
+            decode_call(Some(token), &data),
+            EthCallPreview::Erc20Approve {
+                token,
+                spender: to,
+                amount: U256::MAX
+            }
+        );
+
+        assert_eq!(decode_call(Some(to), &[]), EthCallPreview::Transfer);
+        assert_eq!(decode_call(None, &data), EthCallPreview::ContractCreation);
+        // Unknown selector, or a known one with truncated arguments
+        for data in [&hex::decode("deadbeef00")?[..], &data[..36]] {
+            assert_eq!(
+                decode_call(Some(token), data),
+                EthCallPreview::ContractCall {
+                    contract: token,
+                    selector: data[..4].to_vec().into()
+                }
+            );
+        }
+
+        let tx = Transaction {
+            to: Some(token),
+            data: encode_call(
+                "transfer(address,uint256)",
+                &[Token::Address(to), Token::Uint(amount)],
+            ),
+            nonce: U256::zero(),
+            gas: U256::from(60_000),
+            gas_price: U256::from(10),
+            value: U256::zero(),
+            transaction_type: None,
+            access_list: vec![],
+            max_priority_fee_per_gas: U256::zero(),
+        };
+        let preview = preview_eth_tx(&tx, DEV_CHAIN_ID, Address::repeat_byte(0x0a))?;
+        assert_eq!(preview.max_fee, U256::from(600_000));
+        check_erc20_transfer(&preview, token, to, amount)?;
+        assert!(check_erc20_transfer(&preview, token, to, amount + 1).is_err());
+        assert!(check_native_transfer(&preview, token, U256::zero(), U256::zero()).is_err());
+
+        check_tx_fees(&tx, U256::from(60_000), U256::from(10))?;
+        assert!(check_tx_fees(&tx, U256::from(59_999), U256::from(10)).is_err());
+        assert!(check_tx_fees(&tx, U256::from(60_000), U256::from(9)).is_err());
+
+        // Gas and fee come from the server, and must not abort the preview
+        let overflowing = Transaction {
+            gas: U256::MAX,
+            gas_price: U256::from(2),
+            ..tx
+        };
+        assert!(preview_eth_tx(&overflowing, DEV_CHAIN_ID, Address::repeat_byte(0x0a)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_eth_payment_uri() -> Result<()> {
+        let mainnet = get_eth_network("mainnet")?;
+        let to = Address::from_str("fb6916095ca1df60bb79ce92ce3ea74c37c5d359")?;
+
+        // Examples from EIP-681
+        let native = EthPaymentUri::parse(
+            "ethereum:0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359@1?value=2.014e18",
+            &mainnet,
+        )?;
+        assert_eq!(
+            native,
+            EthPaymentUri::native(to, 1, Some(U256::from(2_014_000_000_000_000_000u64)))
+        );
+        assert_eq!(EthPaymentUri::parse(&native.to_string(), &mainnet)?, native);
+
+        let token = Address::from_str("89205a3a3b2a69de6dbf7f01ed13b2108b2c43e7")?;
+        let recipient = Address::from_str("8e23ee67d1332ad560396262c48ffbb01f93d052")?;
+        let erc20 = EthPaymentUri::parse(
+            "ethereum:0x89205a3a3b2a69de6dbf7f01ed13b2108b2c43e7/transfer?address=0x8e23ee67d1332ad560396262c48ffbb01f93d052&uint256=1",
+            &mainnet,
+        )?;
+        assert_eq!(erc20.token, Some(token));
+        assert_eq!(erc20.to, recipient);
+        assert_eq!(erc20.value, Some(U256::one()));
+        assert_eq!(erc20.chain_id, None);
+        assert_eq!(hex::encode(&erc20.calldata()[..4]), "a9059cbb");
+
+        let request = EthPaymentUri::erc20(token, recipient, 1, Some(U256::from(5)))
+            .with_label(Some("Invoice #7".to_owned()));
+        assert_eq!(
+            request.to_string(),
+            format!(
+                "ethereum:{}@1/transfer?address={}&uint256=5&label=Invoice%20%237",
+                EthAddress::from(token),
+                EthAddress::from(recipient)
+            )
+        );
+        assert_eq!(
+            EthPaymentUri::parse(&request.to_string(), &mainnet)?,
+            request
+        );
+
+        // Other chain, ENS name, unsupported function, fractional wei
+        for uri in [
+            "ethereum:0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359@11155111?value=1",
+            "ethereum:alice.eth?value=1",
+            "ethereum:0x89205a3a3b2a69de6dbf7f01ed13b2108b2c43e7/approve?address=0x8e23ee67d1332ad560396262c48ffbb01f93d052&uint256=1",
+            "ethereum:0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359?value=1.5",
+        ] {
+            assert!(EthPaymentUri::parse(uri, &mainnet).is_err(), "{}", uri);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_permit_typed_data() -> Result<()> {
+        // USDC on mainnet
+        let domain = TokenDomain {
+            name: "USD Coin".to_owned(),
+            version: "2".to_owned(),
+            chain_id: 1,
+            verifying_contract: Address::from_str("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48")?,
+        };
+        let owner = Address::repeat_byte(0x0a);
+        let spender = Address::repeat_byte(0x0b);
+        let permit = permit_typed_data(
+            &domain,
+            owner,
+            spender,
+            U256::from(1_000_000),
+            U256::zero(),
+            U256::from(1_700_000_000),
+        )?;
+        assert_eq!(
+            hex::encode(permit.type_hash("Permit")?),
+            "6e71edae12b1b97f4d1f60370fef10105fa2faae0126114a169c64845d6126c9"
+        );
+        assert_eq!(
+            hex::encode(permit.domain_separator()?),
+            "06c37168a7db5138defc7866392bb87a741f9b3d104deb5094588ce041cae335"
+        );
+
+        let permit2 = permit2_typed_data(
+            1,
+            domain.verifying_contract,
+            U256::from(1_000_000),
+            1_700_000_000,
+            U256::zero(),
+            spender,
+            U256::from(1_700_000_000),
+        )?;
+        assert_eq!(
+            hex::encode(permit2.type_hash("PermitSingle")?),
+            "f3841cd1ff0085026a6327b620b67997ce40f282c88a8e905a7a5626e310f3d0"
+        );
+        assert_ne!(permit.signing_hash()?, permit2.signing_hash()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_siwe_message() -> Result<()> {
+        // Example from EIP-4361
+        let text = "service.org wants you to sign in with your Ethereum account:\n\
+            0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2\n\
+            \n\
+            I accept the ServiceOrg Terms of Service: https://service.org/tos\n\
+            \n\
+            URI: https://service.org/login\n\
+            Version: 1\n\
+            Chain ID: 1\n\
+            Nonce: 32891756\n\
+            Issued At: 2021-09-30T16:25:24Z\n\
+            Resources:\n\
+            - ipfs://bafybeiemxf5abjwjbikoz4mc3a3dla6ual3jsgpdr4cjr3oz3evfyavhwq/\n\
+            - https://example.com/my-web2-claim.json";
+        let message = SiweMessage::from_str(text)?;
+        assert_eq!(message.domain, "service.org");
+        assert_eq!(message.nonce, "32891756");
+        assert_eq!(message.resources.len(), 2);
+        assert_eq!(message.to_string(), text);
+
+        let minimal = SiweMessage::new(
+            "localhost:3000",
+            Address::from_str(&FROM_ADDRESS[2..])?,
+            "http://localhost:3000",
+            1,
+            "abcdefgh1234",
+        );
+        assert_eq!(SiweMessage::from_str(&minimal.to_string())?, minimal);
+        assert!(SiweMessage::from_str(&text.replace("Nonce: 32891756", "Nonce: 1234")).is_err());
+
+        let key = SecretKey::from_slice(&[0x11; 32])?;
+        let address = SecretKeyRef::new(&key).address();
+        let message =
+            SiweMessage::new("service.org", address, "https://service.org", 1, "32891756")
+                .with_expiration_time(Utc::now() + Duration::minutes(5));
+        let hash = hash_personal_message(message.to_string().as_bytes());
+        let sig = SecretKeyRef::new(&key).sign_message(&hash)?;
+        let mut signature = sig.r.as_bytes().to_vec();
+        signature.extend_from_slice(sig.s.as_bytes());
+        signature.push(sig.v as u8 + 27);
+        assert!(message.verify(&signature, "service.org", "32891756")?);
+        assert!(!message.verify(&signature, "evil.org", "32891756")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_raw_transaction() -> Result<()> {
+        // Example from EIP-155
+        let raw = hex::decode("f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83")?;
+        let decoded = Transaction::decode(&raw)?;
+        assert_eq!(decoded.chain_id, Some(1));
+        assert_eq!(decoded.transaction.nonce, U256::from(9));
+        assert_eq!(decoded.transaction.gas_price, U256::from(20_000_000_000u64));
+        assert_eq!(decoded.transaction.to, Some(Address::repeat_byte(0x35)));
+        assert_eq!(decoded.transaction.value, U256::exp10(18));
+        assert_eq!(
+            hex::encode(decoded.signing_hash()),
+            "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+        );
+        let signer = Address::from_str("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f")?;
+        assert_eq!(decoded.recover_sender()?, signer);
+        check_signed_tx(&raw, signer, 1)?;
+        assert!(check_signed_tx(&raw, signer, 137).is_err());
+        assert!(check_signed_tx(&raw, Address::repeat_byte(0x35), 1).is_err());
+
+        // Every transaction type decodes back to what was signed
+        let key = SecretKey::from_slice(&[0x46; 32])?;
+        let key = SecretKeyRef::new(&key);
+        let chain_id = 11155111;
+        for transaction_type in [None, Some(U64::from(1)), Some(U64::from(2))] {
+            let tx = || Transaction {
+                to: Some(Address::repeat_byte(0x35)),
+                nonce: U256::from(7),
+                gas: U256::from(60_000),
+                gas_price: U256::from(30_000_000_000u64),
+                value: U256::from(12345),
+                data: vec![0xde, 0xad],
+                transaction_type,
+                access_list: match transaction_type {
+                    Some(_) => vec![AccessListItem {
+                        address: Address::repeat_byte(0x0c),
+                        storage_keys: vec![H256::repeat_byte(0x01)],
+                    }],
+                    None => vec![],
+                },
+                max_priority_fee_per_gas: match transaction_type {
+                    Some(t) if t == U64::from(2) => U256::from(1_000_000_000),
+                    _ => U256::zero(),
+                },
+            };
+            let hash = tx().get_hash(chain_id);
+            let signature = match transaction_type {
+                None => key.sign(&hash, Some(chain_id))?,
+                Some(_) => key.sign_message(&hash)?,
+            };
+            let raw = tx().sign(signature, chain_id).raw_transaction.0;
+
+            let decoded = Transaction::decode(&raw)?;
+            assert_eq!(decoded.transaction, tx());
+            assert_eq!(decoded.chain_id, Some(chain_id));
+            assert_eq!(decoded.recover_sender()?, key.address());
+            check_signed_tx(&raw, key.address(), chain_id)?;
+
+            // Changing the value changes the signer
+            let mut tampered = decoded;
+            tampered.transaction.value = U256::from(54321);
+            assert_ne!(tampered.recover_sender().ok(), Some(key.address()));
+        }
+
+        assert!(Transaction::decode(&[]).is_err());
+        assert!(Transaction::decode(&[0x03, 0xc0]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_tx_hash_is_chain_specific() {
+        for transaction_type in [None, Some(U64::from(2))] {
+            let tx = Transaction {
+                to: Some(Address::repeat_byte(1)),
+                nonce: U256::zero(),
+                gas: U256::from(21000),
+                gas_price: U256::from(1_000_000_000),
+                value: U256::one(),
+                data: vec![],
+                transaction_type,
+                access_list: vec![],
+                max_priority_fee_per_gas: U256::zero(),
+            };
+            assert_ne!(tx.get_hash(1), tx.get_hash(137));
+        }
+    }
+
+    #[test]
+    fn test_transport_kind_from_url() {
+        assert_eq!(
+            TransportKind::from_url("wss://ethereum-sepolia-rpc.publicnode.com"),
+            TransportKind::WebSocket
+        );
+        assert_eq!(
+            TransportKind::from_url("http://127.0.0.1:8545"),
+            TransportKind::Http
+        );
+        assert_eq!(TransportKind::from_url("/tmp/geth.ipc"), TransportKind::Ipc);
+    }
+
+    #[test]
+    fn test_on_shared_runtime() {
+        // Connections are only cached for the shared runtime, which outlives every caller
+        assert!(!on_shared_runtime());
+        assert!(block_on(async { on_shared_runtime() }));
+        assert!(block_on(async {
+            tokio::spawn(async { on_shared_runtime() }).await.unwrap()
+        }));
+        assert!(!on_shared_runtime());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_block_on_from_async() {
+        // Blocking wrappers hand the worker off instead of nesting runtimes
+        assert!(block_on(async { on_shared_runtime() }));
+        assert!(!on_shared_runtime());
+    }
+
+    #[test]
+    fn test_hash_personal_message() {
+        let hash = hash_personal_message(b"Hello World");
+        assert_eq!(
+            hex::encode(hash),
+            "a1de988600a42c4b4ab089b619297c17d53cffae5d5120d82d8a92d0bb3b78f2"
+        );
+    }
+
+    #[test]
+    fn test_typed_data_signing_hash() -> Result<()> {
+        let typed_data = TypedData::from_json(MAIL_TYPED_DATA)?;
+        assert_eq!(
+            typed_data.encode_type("Mail")?,
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+        );
+        assert_eq!(
+            hex::encode(typed_data.domain_separator()?),
+            "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+        );
+        assert_eq!(
+            hex::encode(typed_data.signing_hash()?),
+            "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_get_all_addresses_balance() -> Result<()> {
         let private_share: PrivateShare = get_test_private_share(PRIVATE_SHARE_FILENAME);
-        let balance_l = get_all_addresses_balance(RINKEBY_TEST_API, 1, &private_share).await?;
+        let network = get_test_eth_network();
+        let balance_l = get_all_addresses_balance(network.rpc_url()?, 1, &private_share).await?;
         let mut total = 0.0;
         for b in balance_l {
             total += b
@@ -211,6 +1766,57 @@ mod eth_test_suite {
         Ok(())
     }
 
+    #[test]
+    fn test_build_replacement_tx() -> Result<()> {
+        let original = EthTxRecord {
+            tx_hash: H256::repeat_byte(1),
+            chain_id: DEV_CHAIN_ID,
+            from: Address::from_str(FROM_ADDRESS)?,
+            to: Some(Address::from_str(TO_ADDRESS)?),
+            nonce: U256::from(7),
+            gas: U256::from(50_000),
+            gas_price: U256::from(30_000_000_000u64),
+            max_priority_fee_per_gas: U256::from(1_000_000_000),
+            value: U256::from(1_000_000_000_000_000u64),
+            data: vec![1, 2, 3].into(),
+            transaction_type: Some(U64::from(2)),
+            access_list: vec![],
+            replaces: None,
+            replaced_by: None,
+        };
+
+        let speed_up = build_replacement_tx(
+            &original,
+            EthTxReplacement::SpeedUp,
+            &EthReplacementFees::default(),
+        )?;
+        assert_eq!(speed_up.nonce, original.nonce);
+        assert_eq!(speed_up.to, original.to);
+        assert_eq!(speed_up.value, original.value);
+        assert_eq!(speed_up.gas_price, U256::from(33_000_000_000u64));
+        assert_eq!(speed_up.max_priority_fee_per_gas, U256::from(1_100_000_000));
+
+        let cancel = build_replacement_tx(
+            &original,
+            EthTxReplacement::Cancel,
+            &EthReplacementFees {
+                max_fee_per_gas: Some(U256::from(40_000_000_000u64)),
+                max_priority_fee_per_gas: Some(U256::from(2_000_000_000)),
+            },
+        )?;
+        assert_eq!(cancel.nonce, original.nonce);
+        assert_eq!(cancel.to, Some(original.from));
+        assert_eq!(cancel.value, U256::zero());
+        assert!(cancel.data.is_empty());
+
+        let underpriced = EthReplacementFees {
+            max_fee_per_gas: Some(U256::from(32_000_000_000u64)),
+            max_priority_fee_per_gas: None,
+        };
+        assert!(build_replacement_tx(&original, EthTxReplacement::SpeedUp, &underpriced).is_err());
+        Ok(())
+    }
+
     // This test only run at one place, need to be ignore on github action
     // Test it: cargo test test_send_eth_w_rotate -- --nocapture
     #[test]
@@ -227,19 +1833,197 @@ mod eth_test_suite {
         w.rotate(&client_shim, w_file);
         let mut new_w = Wallet::load_from(w_file);
         let to_send = SENT_ETH;
-        let txid = new_w.send(
-            FROM_ADDRESS, // from address in eth_w
-            TO_ADDRESS,   // to address in eth_w
-            to_send,
-            &client_shim,
-        );
+        let txid = new_w
+            .send(
+                FROM_ADDRESS, // from address in eth_w
+                TO_ADDRESS,   // to address in eth_w
+                to_send,
+                &client_shim,
+            )
+            .unwrap();
         print_tx_hash(&txid);
         assert!(!txid.is_empty());
     }
 
+    #[test]
+    fn test_load_legacy_wallet_skips_invalid_address() -> Result<()> {
+        let mut wallet_json: Value =
+            serde_json::from_str(&std::fs::read_to_string("test-assets/eth_w.json")?)?;
+        let legacy_map = &mut wallet_json["addresses_derivation_map"];
+        legacy_map["not-an-address"] = legacy_map[FROM_ADDRESS].clone();
+        let w_file = std::env::temp_dir().join("legacy_eth_w.json");
+        std::fs::write(&w_file, wallet_json.to_string())?;
+
+        let w = Wallet::load_from(w_file.to_str().unwrap());
+        assert!(w.addresses_derivation_map.is_empty());
+        assert_eq!(w.eth_addresses_derivation_map.len(), 2);
+        assert!(w
+            .eth_addresses_derivation_map
+            .contains_key(&EthAddress::from_str(FROM_ADDRESS)?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tss_key_signs_web3_transaction() -> Result<()> {
+        // expect the server running
+        let client_shim = mock_client_shim("ETH_TEST_MAIL", "ETH_TEST_PW");
+        let w: Wallet = Wallet::load_from("test-assets/eth_w.json");
+        let key = TssKey::for_address(
+            FROM_ADDRESS,
+            &client_shim,
+            &w.private_share,
+            &w.eth_addresses_derivation_map,
+        )?;
+        assert_eq!(key.address(), Address::from_str(&FROM_ADDRESS[2..])?);
+
+        // Fully specified, so that web3 only signs
+        let network = get_test_eth_network();
+        let tx = TransactionParameters {
+            nonce: Some(U256::zero()),
+            to: Some(Address::from_str(&TO_ADDRESS[2..])?),
+            gas_price: Some(U256::exp10(9)),
+            chain_id: Some(network.chain_id),
+            value: U256::one(),
+            ..Default::default()
+        };
+        let signed = block_on(async {
+            let web3_connection = establish_web3_connection(network.rpc_url()?).await?;
+            let signed = web3_connection.accounts().sign_transaction(tx, key).await?;
+            Ok::<_, anyhow::Error>(signed)
+        })?;
+        assert!(!signed.raw_transaction.0.is_empty());
+
+        let hash = keccak256(b"tss key");
+        let signature = key.sign(&hash, None).unwrap();
+        let mut bytes = signature.r.as_bytes().to_vec();
+        bytes.extend_from_slice(signature.s.as_bytes());
+        bytes.push(signature.v as u8);
+        assert_eq!(recover_signer(&hash, &bytes)?, key.address());
+        Ok(())
+    }
+
+    fn stub_receipt(tx_hash: H256, block_number: u64, status: u64) -> Value {
+        json!({
+            "transactionHash": tx_hash,
+            "transactionIndex": "0x0",
+            "blockNumber": U64::from(block_number),
+            "cumulativeGasUsed": "0x5208",
+            "gasUsed": "0x5208",
+            "effectiveGasPrice": "0x3b9aca00",
+            "logs": [],
+            "logsBloom": H2048::zero(),
+            "status": U64::from(status),
+        })
+    }
+
+    #[test]
+    fn test_get_tx_receipt() -> Result<()> {
+        let (pending, succeeded, reverted) = (
+            H256::repeat_byte(0x01),
+            H256::repeat_byte(0x02),
+            H256::repeat_byte(0x03),
+        );
+        let url = spawn_json_rpc_node(move |method, params| match method {
+            "eth_blockNumber" => Ok(json!("0x12")),
+            "eth_getTransactionReceipt" => {
+                let tx_hash: H256 = serde_json::from_value(params[0].clone()).unwrap();
+                Ok(match tx_hash {
+                    h if h == succeeded => stub_receipt(h, 0x10, 1),
+                    h if h == reverted => stub_receipt(h, 0x12, 0),
+                    _ => Value::Null,
+                })
+            }
+            _ => Err(json!({ "code": -32601, "message": "method not found" })),
+        });
+
+        block_on(async {
+            let connection = get_connection(&url).await?;
+
+            let report = get_tx_receipt(connection.web3(), pending).await?;
+            assert_eq!(report.status, EthTxStatus::Pending);
+            assert_eq!(report.confirmations, 0);
+
+            // Mined two blocks ago, counting its own block
+            let report = get_tx_receipt(connection.web3(), succeeded).await?;
+            assert_eq!(report.status, EthTxStatus::Success);
+            assert_eq!(report.block_number, Some(U64::from(0x10)));
+            assert_eq!(report.gas_used, Some(U256::from(21000)));
+            assert_eq!(report.confirmations, 3);
+
+            let report = get_tx_receipt(connection.web3(), reverted).await?;
+            assert_eq!(report.status, EthTxStatus::Reverted);
+            assert_eq!(report.confirmations, 1);
+            Ok(())
+        })
+    }
+
+    /// Node where `tx_hash` is pending on the first receipt poll, then mined at block 0x10,
+    /// the chain growing by two blocks per poll.
+    fn spawn_mining_node(tx_hash: H256) -> String {
+        let mut receipt_polls: u64 = 0;
+        spawn_json_rpc_node(move |method, _| match method {
+            "eth_getTransactionReceipt" => {
+                receipt_polls += 1;
+                match receipt_polls {
+                    1 => Ok(Value::Null),
+                    _ => Ok(stub_receipt(tx_hash, 0x10, 1)),
+                }
+            }
+            "eth_blockNumber" => Ok(json!(U64::from(0x10 + 2 * receipt_polls.saturating_sub(2)))),
+            _ => Err(json!({ "code": -32601, "message": "method not found" })),
+        })
+    }
+
+    #[test]
+    fn test_wait_for_confirmations() -> Result<()> {
+        let tx_hash = H256::repeat_byte(0x01);
+        let timeout = std::time::Duration::from_secs(60);
+
+        // Pending, then one confirmation, then three
+        let url = spawn_mining_node(tx_hash);
+        let report = block_on(async {
+            let connection = get_connection(&url).await?;
+            wait_for_confirmations(&connection, tx_hash, 3, timeout).await
+        })?;
+        assert_eq!(report.status, EthTxStatus::Success);
+        assert_eq!(report.confirmations, 3);
+
+        // No confirmations asked for still waits for the transaction to be mined
+        let url = spawn_mining_node(tx_hash);
+        let report = block_on(async {
+            let connection = get_connection(&url).await?;
+            wait_for_confirmations(&connection, tx_hash, 0, timeout).await
+        })?;
+        assert_eq!(report.status, EthTxStatus::Success);
+        assert_eq!(report.confirmations, 1);
+
+        // Never mined
+        let url = spawn_json_rpc_node(|_, _| Ok(Value::Null));
+        let res = block_on(async {
+            let connection = get_connection(&url).await?;
+            let timeout = std::time::Duration::from_secs(1);
+            wait_for_confirmations(&connection, tx_hash, 1, timeout).await
+        });
+        assert!(res.err().unwrap().to_string().contains("timed out"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_async_wait_for_eth_tx_confirmations() -> Result<()> {
+        // Runs on the caller's runtime, where the blocking API would panic
+        let tx_hash = H256::repeat_byte(0x01);
+        let url = spawn_mining_node(tx_hash);
+        let network = EthNetwork::new("stub", DEV_CHAIN_ID, &[&url]);
+        let timeout = std::time::Duration::from_secs(60);
+        let report = a_wait_for_eth_tx_confirmations(&network, tx_hash, 1, timeout).await?;
+        assert_eq!(report.status, EthTxStatus::Success);
+        assert_eq!(report.confirmations, 1);
+        Ok(())
+    }
+
     fn check_eth_balance(w: &mut Wallet) -> bool {
-        print_balance(w.get_balance());
-        if w.get_balance() == 0 {
+        print_balance(w.get_balance().unwrap());
+        if w.get_balance().unwrap() == 0 {
             return false;
         }
         true
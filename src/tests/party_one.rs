@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use curv::arithmetic::traits::Converter;
+use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+use curv::cryptographic_primitives::twoparty::coin_flip_optimal_rounds;
+use curv::cryptographic_primitives::twoparty::dh_key_exchange_variant_with_pok_comm::{
+    CommWitness, EcKeyPair, Party1FirstMessage, Party1SecondMessage,
+};
+use curv::elliptic::curves::secp256_k1::{FE, GE};
+use kms::chain_code::two_party::party1::ChainCode1;
+use kms::ecdsa::two_party::{party1, MasterKey1};
+use kms::rotation::two_party::party1::Rotation1;
+use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::{party_one, party_two};
+
+use crate::dto::ecdsa::SignSecondMsgRequest;
+use crate::ecdsa::Party1Transport;
+
+/// What party one keeps about a key between the rounds of a protocol.
+#[derive(Default)]
+struct Party1Key {
+    keygen: Option<(party_one::CommWitness, party_one::EcKeyPair)>,
+    /// Paillier key pair and private share of party one, its public share and party two's.
+    paillier: Option<(party_one::PaillierKeyPair, party_one::Party1Private, GE, GE)>,
+    chaincode: Option<(CommWitness<GE>, EcKeyPair<GE>)>,
+    master_key: Option<MasterKey1>,
+    sign: Option<(party_two::EphKeyGenFirstMsg, party_one::EphEcKeyPair)>,
+    rotate: Option<(FE, FE)>,
+    last_pos: u32,
+}
+
+/// Party one running in-process, so that the protocols can be tested without the server.
+#[derive(Default)]
+pub struct Party1Simulator {
+    keys: Mutex<HashMap<String, Party1Key>>,
+}
+
+impl Party1Simulator {
+    pub fn new() -> Party1Simulator {
+        Party1Simulator::default()
+    }
+
+    fn with_key<R>(&self, id: &str, round: impl FnOnce(&mut Party1Key) -> Result<R>) -> Result<R> {
+        let mut keys = self.keys.lock().unwrap();
+        let key = keys
+            .get_mut(id)
+            .ok_or_else(|| anyhow!("unknown key id: {}", id))?;
+        round(key)
+    }
+}
+
+fn take<T>(state: &mut Option<T>, round: &str) -> Result<T> {
+    state
+        .take()
+        .ok_or_else(|| anyhow!("{} round has not been run", round))
+}
+
+#[async_trait]
+impl Party1Transport for Party1Simulator {
+    async fn keygen_first(&self) -> Result<(String, party_one::KeyGenFirstMsg)> {
+        let (key_gen_first_msg, comm_witness, ec_key_pair) = MasterKey1::key_gen_first_message();
+
+        let mut keys = self.keys.lock().unwrap();
+        let id = format!("simulated-{}", keys.len());
+        keys.insert(
+            id.clone(),
+            Party1Key {
+                keygen: Some((comm_witness, ec_key_pair)),
+                ..Party1Key::default()
+            },
+        );
+        Ok((id, key_gen_first_msg))
+    }
+
+    async fn keygen_second(
+        &self,
+        id: &str,
+        d_log_proof: &DLogProof<GE>,
+    ) -> Result<party1::KeyGenParty1Message2> {
+        self.with_key(id, |key| {
+            let (comm_witness, ec_key_pair) = take(&mut key.keygen, "keygen first")?;
+            let (kg_party_one_second_message, paillier_key_pair, party_one_private) =
+                MasterKey1::key_gen_second_message(comm_witness, &ec_key_pair, d_log_proof);
+            key.paillier = Some((
+                paillier_key_pair,
+                party_one_private,
+                ec_key_pair.public_share,
+                d_log_proof.pk,
+            ));
+            Ok(kg_party_one_second_message)
+        })
+    }
+
+    async fn chaincode_first(&self, id: &str) -> Result<Party1FirstMessage> {
+        self.with_key(id, |key| {
+            let (cc_party_one_first_message, cc_comm_witness, cc_ec_key_pair) =
+                ChainCode1::chain_code_first_message();
+            key.chaincode = Some((cc_comm_witness, cc_ec_key_pair));
+            Ok(cc_party_one_first_message)
+        })
+    }
+
+    async fn chaincode_second(
+        &self,
+        id: &str,
+        d_log_proof: &DLogProof<GE>,
+    ) -> Result<Party1SecondMessage<GE>> {
+        self.with_key(id, |key| {
+            let (cc_comm_witness, cc_ec_key_pair) = take(&mut key.chaincode, "chaincode first")?;
+            let (paillier_key_pair, party_one_private, party_one_public, party_two_public) =
+                take(&mut key.paillier, "keygen second")?;
+
+            let cc_party_one_second_message =
+                ChainCode1::chain_code_second_message(cc_comm_witness, d_log_proof);
+            let chain_code = ChainCode1::compute_chain_code(&cc_ec_key_pair, &d_log_proof.pk);
+
+            key.master_key = Some(MasterKey1::set_master_key(
+                &chain_code.chain_code,
+                party_one_private,
+                &party_one_public,
+                &party_two_public,
+                paillier_key_pair,
+            ));
+            Ok(cc_party_one_second_message)
+        })
+    }
+
+    async fn sign_first(
+        &self,
+        id: &str,
+        eph_key_gen_first_message: &party_two::EphKeyGenFirstMsg,
+    ) -> Result<party_one::EphKeyGenFirstMsg> {
+        self.with_key(id, |key| {
+            let (sign_party_one_first_message, eph_ec_key_pair_party1) =
+                MasterKey1::sign_first_message();
+            key.sign = Some((eph_key_gen_first_message.clone(), eph_ec_key_pair_party1));
+            Ok(sign_party_one_first_message)
+        })
+    }
+
+    async fn sign_second(
+        &self,
+        id: &str,
+        request: &SignSecondMsgRequest,
+    ) -> Result<party_one::SignatureRecid> {
+        self.with_key(id, |key| {
+            let (eph_key_gen_first_message_party_two, eph_ec_key_pair_party1) =
+                take(&mut key.sign, "sign first")?;
+            let master_key = key
+                .master_key
+                .as_ref()
+                .ok_or_else(|| anyhow!("keygen has not completed"))?;
+
+            let child_master_key = master_key.get_child(vec![
+                request.x_pos_child_key.clone(),
+                request.y_pos_child_key.clone(),
+            ]);
+            let signature = child_master_key
+                .sign_second_message(
+                    &request.party_two_sign_message,
+                    &eph_key_gen_first_message_party_two,
+                    &eph_ec_key_pair_party1,
+                    &request.message,
+                )
+                .map_err(|_| anyhow!("party two sign message failed verification"))?;
+
+            let pos = u32::from_str_radix(&request.y_pos_child_key.to_hex(), 16).unwrap_or(0);
+            key.last_pos = key.last_pos.max(pos);
+            Ok(signature)
+        })
+    }
+
+    async fn rotate_first(
+        &self,
+        id: &str,
+    ) -> Result<coin_flip_optimal_rounds::Party1FirstMessage<GE>> {
+        self.with_key(id, |key| {
+            let (coin_flip_party1_first_message, m1, r1) = Rotation1::key_rotate_first_message();
+            key.rotate = Some((m1, r1));
+            Ok(coin_flip_party1_first_message)
+        })
+    }
+
+    async fn rotate_second(
+        &self,
+        id: &str,
+        coin_flip_party2_first_message: &coin_flip_optimal_rounds::Party2FirstMessage<GE>,
+    ) -> Result<(
+        coin_flip_optimal_rounds::Party1SecondMessage<GE>,
+        party1::RotationParty1Message1,
+    )> {
+        self.with_key(id, |key| {
+            let (m1, r1) = take(&mut key.rotate, "rotate first")?;
+            let master_key = take(&mut key.master_key, "keygen")?;
+
+            let (coin_flip_party1_second_message, random1) =
+                Rotation1::key_rotate_second_message(coin_flip_party2_first_message, &m1, &r1);
+            let (rotation_party1_first_message, rotated_master_key) =
+                master_key.rotation_first_message(&random1);
+
+            key.master_key = Some(rotated_master_key);
+            Ok((
+                coin_flip_party1_second_message,
+                rotation_party1_first_message,
+            ))
+        })
+    }
+
+    async fn recover(&self, id: &str) -> Result<u32> {
+        self.with_key(id, |key| Ok(key.last_pos))
+    }
+}
@@ -1,34 +1,181 @@
-use std::time::Instant;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::Result;
 use floating_duration::TimeFormat;
+use reqwest::StatusCode;
+use thiserror::Error;
 
-#[derive(Debug)]
+use super::auth::TokenProvider;
+
+/// Header carrying the id of a request, the same on every retry of it so that party one can
+/// deduplicate retried protocol messages.
+pub const REQUEST_ID_HEADER: &str = "X-Request-ID";
+
+#[derive(Error, Debug)]
+pub enum RequestError {
+    #[error("authentication with party one failed ({status}): {body}")]
+    Auth { status: u16, body: String },
+    #[error("party one server error on {path} ({status}): {body}")]
+    Server {
+        path: String,
+        status: u16,
+        body: String,
+    },
+    #[error("party one rejected {path} ({status}): {body}")]
+    Rejected {
+        path: String,
+        status: u16,
+        body: String,
+    },
+    #[error("unexpected response to {path}: {e}")]
+    Protocol { path: String, e: String },
+    #[error("HTTP POST to {path} failed: {e}")]
+    Connection { path: String, e: String },
+}
+
+impl RequestError {
+    /// Whether sending the same request again may succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            RequestError::Server { .. } | RequestError::Connection { .. }
+        )
+    }
+
+    fn from_status(path: &str, status: StatusCode, body: String) -> RequestError {
+        let path = path.to_owned();
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => RequestError::Auth {
+                status: status.as_u16(),
+                body,
+            },
+            s if s.is_server_error() || s == StatusCode::TOO_MANY_REQUESTS => {
+                RequestError::Server {
+                    path,
+                    status: status.as_u16(),
+                    body,
+                }
+            }
+            _ => RequestError::Rejected {
+                path,
+                status: status.as_u16(),
+                body,
+            },
+        }
+    }
+}
+
+/// Timeouts and retry policy of the requests to party one. An idempotent request failing
+/// with a retryable error is sent again up to `retries` times, waiting `backoff`, then twice
+/// as long before each new attempt. Other requests are never retried.
+#[derive(Debug, Clone)]
+pub struct RequestConfig {
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    pub retries: u32,
+    pub backoff: Duration,
+}
+
+impl Default for RequestConfig {
+    fn default() -> Self {
+        RequestConfig {
+            timeout: Duration::from_secs(60),
+            connect_timeout: Duration::from_secs(10),
+            retries: 3,
+            backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Client of the party-one server. Every request is async, the blocking API in `requests`
+/// drives them on the shared runtime. With a `token_provider`, requests are authenticated
+/// with its tokens rather than `auth_token`.
 pub struct AsyncClientShim {
     pub client: reqwest::Client,
     pub auth_token: Option<String>,
+    pub token_provider: Option<Arc<dyn TokenProvider>>,
     pub user_id: String,
     pub endpoint: String,
+    pub config: RequestConfig,
+}
+
+impl fmt::Debug for AsyncClientShim {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AsyncClientShim")
+            .field("has_auth_token", &self.auth_token.is_some())
+            .field("has_token_provider", &self.token_provider.is_some())
+            .field("user_id", &self.user_id)
+            .field("endpoint", &self.endpoint)
+            .field("config", &self.config)
+            .finish()
+    }
 }
 
 impl AsyncClientShim {
     pub fn new(endpoint: String, auth_token: Option<String>, user_id: String) -> AsyncClientShim {
-        let client = reqwest::Client::new();
+        AsyncClientShim::with_config(endpoint, auth_token, user_id, RequestConfig::default())
+    }
+
+    pub fn with_config(
+        endpoint: String,
+        auth_token: Option<String>,
+        user_id: String,
+        config: RequestConfig,
+    ) -> AsyncClientShim {
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .connect_timeout(config.connect_timeout)
+            .build()
+            .expect("Unable to build HTTP client");
 
         AsyncClientShim {
             client,
             auth_token,
+            token_provider: None,
             user_id,
             endpoint,
+            config,
+        }
+    }
+
+    pub fn with_token_provider(
+        mut self,
+        token_provider: Arc<dyn TokenProvider>,
+    ) -> AsyncClientShim {
+        self.token_provider = Some(token_provider);
+        self
+    }
+
+    async fn token(&self) -> Result<Option<String>> {
+        match &self.token_provider {
+            Some(token_provider) => Ok(Some(token_provider.token().await?)),
+            None => Ok(self.auth_token.clone()),
         }
     }
 }
 
+/// Unique id for a new request.
+pub fn new_request_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    format!(
+        "{:x}-{:x}-{:x}",
+        std::process::id(),
+        nanos,
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
 pub async fn a_post<V>(client_shim: &AsyncClientShim, path: &str) -> Result<Option<V>>
 where
     V: serde::de::DeserializeOwned,
 {
-    base_postb(client_shim, path, "{}").await
+    base_postb(client_shim, path, "{}", false).await
 }
 
 pub async fn a_postb<T, V>(client_shim: &AsyncClientShim, path: &str, body: T) -> Result<Option<V>>
@@ -36,14 +183,86 @@ where
     T: serde::ser::Serialize,
     V: serde::de::DeserializeOwned,
 {
-    base_postb(client_shim, path, body).await
+    base_postb(client_shim, path, body, false).await
+}
+
+/// `a_post` for a request party one can safely handle more than once, retried as configured.
+pub async fn a_post_idempotent<V>(client_shim: &AsyncClientShim, path: &str) -> Result<Option<V>>
+where
+    V: serde::de::DeserializeOwned,
+{
+    base_postb(client_shim, path, "{}", true).await
+}
+
+/// `a_postb` for a request party one can safely handle more than once, retried as configured.
+pub async fn a_postb_idempotent<T, V>(
+    client_shim: &AsyncClientShim,
+    path: &str,
+    body: T,
+) -> Result<Option<V>>
+where
+    T: serde::ser::Serialize,
+    V: serde::de::DeserializeOwned,
+{
+    base_postb(client_shim, path, body, true).await
 }
 
+/// Post `body` to `path`, retrying as configured if the request is `idempotent`. `None` if
+/// party one answers with no content. A token rejected with 401 is refreshed once with the
+/// token provider, if any.
 pub async fn base_postb<T, V>(
     client_shim: &AsyncClientShim,
     path: &str,
     body: T,
+    idempotent: bool,
 ) -> Result<Option<V>>
+where
+    T: serde::ser::Serialize,
+    V: serde::de::DeserializeOwned,
+{
+    let request_id = new_request_id();
+    let mut backoff = client_shim.config.backoff;
+    let mut attempt = 0;
+    let mut refreshed = false;
+    let mut token = client_shim.token().await?;
+    loop {
+        match post_once(client_shim, path, &body, &request_id, token.as_deref()).await {
+            Err(RequestError::Auth { status: 401, .. })
+                if !refreshed && client_shim.token_provider.is_some() =>
+            {
+                let token_provider = client_shim.token_provider.as_ref().unwrap();
+                info!("(req {}, id: {}) refreshing auth token", path, request_id);
+                token = Some(
+                    token_provider
+                        .refresh(token.as_deref().unwrap_or_default())
+                        .await?,
+                );
+                refreshed = true;
+            }
+            Err(e) if idempotent && e.is_retryable() && attempt < client_shim.config.retries => {
+                warn!(
+                    "(req {}, id: {}) attempt {} failed, retrying: {}",
+                    path,
+                    request_id,
+                    attempt + 1,
+                    e
+                );
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            res => return Ok(res?),
+        }
+    }
+}
+
+async fn post_once<T, V>(
+    client_shim: &AsyncClientShim,
+    path: &str,
+    body: &T,
+    request_id: &str,
+    token: Option<&str>,
+) -> Result<Option<V>, RequestError>
 where
     T: serde::ser::Serialize,
     V: serde::de::DeserializeOwned,
@@ -52,13 +271,40 @@ where
 
     let mut b = client_shim
         .client
-        .post(&format!("{}/{}", client_shim.endpoint, path));
+        .post(&format!("{}/{}", client_shim.endpoint, path))
+        .header(REQUEST_ID_HEADER, request_id);
 
-    if client_shim.auth_token.is_some() {
-        b = b.bearer_auth(client_shim.auth_token.clone().unwrap());
+    if let Some(token) = token {
+        b = b.bearer_auth(token);
         b = b.header("user_id", client_shim.user_id.clone());
     }
-    let res = b.json(&body).send().await?;
-    info!("(req {}, took: {})", path, TimeFormat(start.elapsed()));
-    Ok(Some(res.json::<V>().await?))
+    let connection_error = |e: reqwest::Error| RequestError::Connection {
+        path: path.to_owned(),
+        e: e.to_string(),
+    };
+    let res = b.json(body).send().await.map_err(connection_error)?;
+    let status = res.status();
+    let value = res.text().await.map_err(connection_error)?;
+
+    info!(
+        "(req {}, id: {}, status: {}, took: {})",
+        path,
+        request_id,
+        status,
+        TimeFormat(start.elapsed())
+    );
+
+    if !status.is_success() {
+        return Err(RequestError::from_status(path, status, value));
+    }
+    if value.trim().is_empty() {
+        return Ok(None);
+    }
+
+    serde_json::from_str(value.as_str())
+        .map(Some)
+        .map_err(|e| RequestError::Protocol {
+            path: path.to_owned(),
+            e: e.to_string(),
+        })
 }
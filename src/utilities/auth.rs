@@ -0,0 +1,74 @@
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::sync::{Arc, Mutex, RwLock};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+
+/// Source of the auth token sent to party one. It is asked for the token before every
+/// request, and to refresh it when party one answers 401.
+#[async_trait]
+pub trait TokenProvider: Send + Sync {
+    async fn token(&self) -> Result<String>;
+
+    /// Fresh token to replace `expired`, which party one rejected.
+    async fn refresh(&self, expired: &str) -> Result<String>;
+}
+
+/// App callback returning a fresh auth token to replace `c_expired_token`, or null if it
+/// cannot. The returned string stays owned by the app and is copied before the next call.
+pub type TokenRefreshCallback = extern "C" fn(c_expired_token: *const c_char) -> *const c_char;
+
+static TOKEN_REFRESH_CALLBACK: RwLock<Option<TokenRefreshCallback>> = RwLock::new(None);
+
+/// Token provider of the FFI: starts with the token the app passed in and refreshes it with
+/// its callback.
+pub struct CallbackTokenProvider {
+    token: Mutex<String>,
+    callback: TokenRefreshCallback,
+}
+
+impl CallbackTokenProvider {
+    pub fn new(token: String, callback: TokenRefreshCallback) -> CallbackTokenProvider {
+        CallbackTokenProvider {
+            token: Mutex::new(token),
+            callback,
+        }
+    }
+}
+
+#[async_trait]
+impl TokenProvider for CallbackTokenProvider {
+    async fn token(&self) -> Result<String> {
+        Ok(self.token.lock().unwrap().clone())
+    }
+
+    async fn refresh(&self, expired: &str) -> Result<String> {
+        let c_expired_token = CString::new(expired)?;
+        let c_token = (self.callback)(c_expired_token.as_ptr());
+        if c_token.is_null() {
+            return Err(anyhow!("the app could not refresh the auth token"));
+        }
+        let token = unsafe { CStr::from_ptr(c_token) }.to_str()?.to_owned();
+
+        *self.token.lock().unwrap() = token.clone();
+        Ok(token)
+    }
+}
+
+/// Provider for a client created through the FFI with `token`, if the app registered a
+/// refresh callback.
+pub fn registered_token_provider(token: &str) -> Option<Arc<dyn TokenProvider>> {
+    let callback = (*TOKEN_REFRESH_CALLBACK.read().unwrap())?;
+    Some(Arc::new(CallbackTokenProvider::new(
+        token.to_owned(),
+        callback,
+    )))
+}
+
+/// Register the callback refreshing expired auth tokens of every later FFI call, null to
+/// unregister it.
+#[no_mangle]
+pub extern "C" fn set_token_refresh_callback(c_callback: Option<TokenRefreshCallback>) {
+    *TOKEN_REFRESH_CALLBACK.write().unwrap() = c_callback;
+}
@@ -1,15 +1,20 @@
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub mod ffi_utils {
     use anyhow::Result;
+    use serde::de::DeserializeOwned;
     use std::{
         collections::HashMap,
         ffi::{CStr, CString},
+        hash::Hash,
         os::raw::c_char,
     };
 
     use crate::{
         dto::ecdsa::{MKPosDto, PrivateShare},
-        utilities::{err_handling::ErrorFFIKind, requests::ClientShim},
+        eth::network::{get_eth_network, EthNetwork},
+        utilities::{
+            auth::registered_token_provider, err_handling::ErrorFFIKind, requests::ClientShim,
+        },
     };
 
     pub fn get_str_from_c_char(c: *const c_char, err_msg: &str) -> Result<String, ErrorFFIKind> {
@@ -36,7 +41,12 @@ pub mod ffi_utils {
         let auth_token = get_str_from_c_char(c_auth_token, "auth_token")?;
         let user_id = get_str_from_c_char(c_user_id, "user_id")?;
 
-        Ok(ClientShim::new(endpoint, Some(auth_token), user_id))
+        let token_provider = registered_token_provider(&auth_token);
+        let client_shim = ClientShim::new(endpoint, Some(auth_token), user_id);
+        Ok(match token_provider {
+            Some(token_provider) => client_shim.with_token_provider(token_provider),
+            None => client_shim,
+        })
     }
 
     pub fn get_private_share_from_raw(
@@ -57,13 +67,17 @@ pub mod ffi_utils {
         Ok(private_share)
     }
 
-    pub fn get_addresses_derivation_map_from_raw(
+    /// Keys are BTC address strings or, for ETH, `EthAddress`es.
+    pub fn get_addresses_derivation_map_from_raw<K>(
         c_addresses_derivation_map: *const c_char,
-    ) -> Result<HashMap<String, MKPosDto>, ErrorFFIKind> {
+    ) -> Result<HashMap<K, MKPosDto>, ErrorFFIKind>
+    where
+        K: DeserializeOwned + Eq + Hash,
+    {
         let addresses_derivation_map_json =
             get_str_from_c_char(c_addresses_derivation_map, "addresses_derivation_map_json")?;
 
-        let addresses_derivation_map: HashMap<String, MKPosDto> =
+        let addresses_derivation_map: HashMap<K, MKPosDto> =
             match serde_json::from_str(&addresses_derivation_map_json) {
                 Ok(s) => s,
                 Err(e) => {
@@ -77,6 +91,29 @@ pub mod ffi_utils {
         Ok(addresses_derivation_map)
     }
 
+    /// Accepts either a registered network name (e.g. `sepolia`) or an `EthNetwork` JSON object.
+    pub fn get_eth_network_from_raw(c_network: *const c_char) -> Result<EthNetwork, ErrorFFIKind> {
+        let network = get_str_from_c_char(c_network, "network")?;
+
+        if network.trim_start().starts_with('{') {
+            return match serde_json::from_str(&network) {
+                Ok(s) => Ok(s),
+                Err(e) => Err(ErrorFFIKind::E104 {
+                    msg: "network".to_owned(),
+                    e: e.to_string(),
+                }),
+            };
+        }
+
+        match get_eth_network(&network) {
+            Ok(s) => Ok(s),
+            Err(e) => Err(ErrorFFIKind::E104 {
+                msg: "network".to_owned(),
+                e: e.to_string(),
+            }),
+        }
+    }
+
     #[no_mangle]
     pub extern "C" fn cstring_free(cstring: *mut c_char) {
         if cstring.is_null() {
@@ -1,9 +1,13 @@
 pub mod a_requests;
+pub mod auth;
 pub mod err_handling;
 pub mod ffi;
 pub mod requests;
+pub mod runtime;
+pub mod uri;
 
 use crate::dto::ecdsa::PrivateShare;
+use curv::arithmetic::traits::Converter;
 use curv::BigInt;
 use kms::ecdsa::two_party::MasterKey2;
 
@@ -16,3 +20,11 @@ pub fn derive_new_key(private_share: &PrivateShare, pos: u32) -> (u32, MasterKey
 
     (last_pos, last_child_master_key)
 }
+
+pub fn to_bytes32(n: &BigInt) -> [u8; 32] {
+    let bytes = BigInt::to_bytes(n);
+    let mut padded = [0u8; 32];
+    let len = bytes.len().min(32);
+    padded[32 - len..].copy_from_slice(&bytes[bytes.len() - len..]);
+    padded
+}
@@ -0,0 +1,53 @@
+use std::cell::Cell;
+use std::future::Future;
+use std::sync::OnceLock;
+
+use tokio::runtime::{Builder, Handle, Runtime, RuntimeFlavor};
+
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+thread_local! {
+    static ON_SHARED_RUNTIME: Cell<bool> = Cell::new(false);
+}
+
+/// Runtime shared by all blocking wrappers, so that connections opened on it (e.g. cached
+/// WebSockets) stay alive between calls.
+pub fn runtime() -> &'static Runtime {
+    RUNTIME.get_or_init(|| {
+        Builder::new_multi_thread()
+            .enable_all()
+            .on_thread_start(|| ON_SHARED_RUNTIME.with(|on| on.set(true)))
+            .build()
+            .expect("Unable to start tokio runtime")
+    })
+}
+
+/// Whether the calling code runs on the shared runtime, rather than on one of the caller's.
+pub fn on_shared_runtime() -> bool {
+    ON_SHARED_RUNTIME.with(|on| on.get())
+}
+
+/// Restores the flag of the calling thread when `block_on` returns or unwinds.
+struct SharedRuntimeGuard(bool);
+
+impl Drop for SharedRuntimeGuard {
+    fn drop(&mut self) {
+        ON_SHARED_RUNTIME.with(|on| on.set(self.0));
+    }
+}
+
+/// Drive `future` to completion on the shared runtime. From a worker of a multi-threaded
+/// runtime the worker is handed off first with `block_in_place`; like tokio's own `block_on`,
+/// this panics when called from a current-thread runtime.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let run = || {
+        let _guard = SharedRuntimeGuard(ON_SHARED_RUNTIME.with(|on| on.replace(true)));
+        runtime().block_on(future)
+    };
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(run)
+        }
+        _ => run(),
+    }
+}
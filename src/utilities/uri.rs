@@ -0,0 +1,96 @@
+use anyhow::{anyhow, Result};
+use web3::types::U256;
+
+/// Split `uri` into what follows `scheme:` up to the query, and its decoded query parameters.
+pub fn split_uri<'a>(uri: &'a str, scheme: &str) -> Result<(&'a str, Vec<(String, String)>)> {
+    let uri = uri.trim();
+    let rest = match uri.split_once(':') {
+        Some((s, rest)) if s.eq_ignore_ascii_case(scheme) => rest,
+        _ => return Err(anyhow!("not a {}: URI: {}", scheme, uri)),
+    };
+
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let params = query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (key, value) = p.split_once('=').unwrap_or((p, ""));
+            Ok((percent_decode(key)?, percent_decode(value)?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((path, params))
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters.
+pub fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+pub fn percent_decode(s: &str) -> Result<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = s
+                .get(i + 1..i + 3)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+                .ok_or_else(|| anyhow!("invalid percent-encoding in {}", s))?;
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Ok(String::from_utf8(decoded)?)
+}
+
+/// Parse a plain decimal number, e.g. `0.015`, as an integer count of `10^-decimals` units.
+/// Fails if the number has more fractional digits than `decimals`.
+pub fn parse_units(s: &str, decimals: u32) -> Result<U256> {
+    let (int_part, frac_part) = s.split_once('.').unwrap_or((s, ""));
+    let all_digits = |p: &str| p.bytes().all(|b| b.is_ascii_digit());
+    if (int_part.is_empty() && frac_part.is_empty())
+        || !all_digits(int_part)
+        || !all_digits(frac_part)
+    {
+        return Err(anyhow!("invalid amount: {}", s));
+    }
+
+    let frac_part = frac_part.trim_end_matches('0');
+    if frac_part.len() > decimals as usize {
+        return Err(anyhow!("amount {} has more than {} decimals", s, decimals));
+    }
+
+    let digits = format!(
+        "{}{}{}",
+        int_part,
+        frac_part,
+        "0".repeat(decimals as usize - frac_part.len())
+    );
+    U256::from_dec_str(&digits).map_err(|e| anyhow!("invalid amount {}: {:?}", s, e))
+}
+
+/// Inverse of `parse_units`, without trailing zeros.
+pub fn format_units(amount: U256, decimals: u32) -> String {
+    let digits = format!(
+        "{:0>width$}",
+        amount.to_string(),
+        width = decimals as usize + 1
+    );
+    let (int_part, frac_part) = digits.split_at(digits.len() - decimals as usize);
+    let frac_part = frac_part.trim_end_matches('0');
+    if frac_part.is_empty() {
+        int_part.to_owned()
+    } else {
+        format!("{}.{}", int_part, frac_part)
+    }
+}
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use bitcoin::{self};
 use curv::elliptic::curves::secp256_k1::GE;
 use curv::elliptic::curves::traits::ECPoint;
@@ -7,34 +7,44 @@ use kms::ecdsa::two_party::MasterKey2;
 use kms::ecdsa::two_party::*;
 use serde_json::{self, Value};
 use std::fs;
-use web3::types::H256;
+use std::str::FromStr;
+use web3::types::{Address, H256, U256};
 
 use centipede::juggling::proof_system::{Helgamalsegmented, Proof};
 use centipede::juggling::segmentation::Msegmentation;
 
-use crate::btc::raw_tx::select_tx_in;
+use crate::btc::raw_tx::{a_create_raw_tx_sat, a_push_raw_tx, a_select_tx_in};
+use crate::btc::uri::{btc_to_satoshi, format_btc_amount, parse_btc_amount, BtcPaymentUri};
 use crate::btc::utils::{get_bitcoin_network, to_bitcoin_address, to_bitcoin_public_key};
-use crate::dto::btc::BlockCypherRawTx;
 use crate::dto::ecdsa::{MKPosDto, PrivateShare};
+use crate::dto::eth::{
+    EthTransfer, EthTransferHistory, EthTransferKind, EthTxReceiptReport, EthTxRecord,
+};
 use crate::ecdsa::recover::{backup_client_mk, verify_client_backup};
+use crate::ecdsa::Party1Transport;
 use crate::eth;
-use crate::eth::raw_tx::sign_and_send;
+use crate::eth::address::EthAddress;
+use crate::eth::funding::{a_execute_funding_plan, a_plan_eth_funding};
+use crate::eth::network::{get_eth_network, get_eth_network_by_chain_id, EthNetwork};
+use crate::eth::raw_tx::{a_sign_and_send, a_sign_and_send_on_chain};
+use crate::eth::receipt::{a_wait_for_eth_tx_confirmations, DEFAULT_CONFIRMATION_TIMEOUT};
+use crate::eth::replace::{a_fetch_tx_record, a_replace_tx, EthReplacementFees, EthTxReplacement};
+use crate::eth::sync::{a_sync_eth_transfers, a_watch_eth_transfers};
+use crate::eth::uri::EthPaymentUri;
 use crate::eth::utils::pubkey_to_eth_address;
-use crate::tests::common::RINKEBY_TEST_API;
+use crate::utilities::a_requests::AsyncClientShim;
 use crate::utilities::derive_new_key;
 use crate::utilities::requests::ClientShim;
-
-use super::btc;
+use crate::utilities::runtime::block_on;
+use crate::utilities::uri::parse_units;
 
 use super::ecdsa;
 use super::escrow;
-use super::utilities::requests;
 use std::collections::HashMap;
 
 // TODO: move that to a config file and double check electrum server addresses
 pub const WALLET_FILENAME: &str = "wallet/wallet.json";
 const BACKUP_FILENAME: &str = "wallet/backup.data";
-const BLOCK_CYPHER_HOST: &str = "https://api.blockcypher.com/v1/btc/test3";
 #[derive(Serialize, Deserialize)]
 pub struct Wallet {
     pub id: String,
@@ -43,33 +53,53 @@ pub struct Wallet {
     pub private_share: PrivateShare,
     pub last_derived_pos: u32,
     pub addresses_derivation_map: HashMap<String, MKPosDto>,
+    #[serde(default)]
+    pub eth_addresses_derivation_map: HashMap<EthAddress, MKPosDto>,
+    #[serde(default)]
+    pub eth_txs: Vec<EthTxRecord>,
+    /// Transfer history of each synced chain, by chain id.
+    #[serde(default)]
+    pub eth_transfers: HashMap<u64, EthTransferHistory>,
 }
 
 impl Wallet {
     pub fn new(client_shim: &ClientShim, net: &str, c_type: &str) -> Wallet {
-        // let id = Uuid::new_v4().to_string();
-        let private_share = match ecdsa::get_private_share(client_shim) {
-            Ok(p) => p,
+        match block_on(Wallet::a_new(client_shim, net, c_type)) {
+            Ok(w) => w,
             Err(e) => panic!("{}", e),
-        };
+        }
+    }
+
+    pub async fn a_new(client_shim: &AsyncClientShim, net: &str, c_type: &str) -> Result<Wallet> {
+        // let id = Uuid::new_v4().to_string();
+        let private_share = ecdsa::a_get_private_share(client_shim).await?;
 
         let last_derived_pos = 0;
         let addresses_derivation_map = HashMap::new();
+        let eth_addresses_derivation_map = HashMap::new();
 
-        Wallet {
+        Ok(Wallet {
             id: private_share.id.clone(),
             coin_type: c_type.to_owned(),
             network: net.to_owned(),
             private_share,
             last_derived_pos,
             addresses_derivation_map,
-        }
+            eth_addresses_derivation_map,
+            eth_txs: Vec::new(),
+            eth_transfers: HashMap::new(),
+        })
     }
 
     pub fn rotate(self, client_shim: &ClientShim, filepath: &str) {
+        block_on(self.a_rotate(client_shim, filepath)).unwrap();
+    }
+
+    pub async fn a_rotate(self, client_shim: &AsyncClientShim, filepath: &str) -> Result<()> {
         let rotated_private_share =
-            ecdsa::rotate_private_share(self.private_share, client_shim).unwrap();
+            ecdsa::a_rotate_private_share(self.private_share, client_shim).await?;
         let addresses_derivation_map = HashMap::new();
+        let eth_addresses_derivation_map = HashMap::new();
         let mut wallet_after_rotate = Wallet {
             id: self.id.clone(),
             coin_type: self.coin_type.clone(),
@@ -77,10 +107,14 @@ impl Wallet {
             private_share: rotated_private_share,
             last_derived_pos: self.last_derived_pos,
             addresses_derivation_map,
+            eth_addresses_derivation_map,
+            eth_txs: self.eth_txs,
+            eth_transfers: self.eth_transfers,
         };
-        wallet_after_rotate.derived().unwrap();
+        wallet_after_rotate.derived()?;
 
         wallet_after_rotate.save_to(filepath);
+        Ok(())
     }
 
     pub fn backup(&self) {
@@ -104,10 +138,26 @@ impl Wallet {
         net: &str,
         client_shim: &ClientShim,
     ) -> Wallet {
+        match block_on(Wallet::a_recover_and_save_share(
+            escrow_service,
+            net,
+            client_shim,
+        )) {
+            Ok(w) => w,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub async fn a_recover_and_save_share(
+        escrow_service: escrow::Escrow,
+        net: &str,
+        client_shim: &AsyncClientShim,
+    ) -> Result<Wallet> {
         let g: GE = ECPoint::generator();
         let y_priv = escrow_service.get_private_key();
 
-        let data = fs::read_to_string(BACKUP_FILENAME).expect("Unable to load client backup!");
+        let data = fs::read_to_string(BACKUP_FILENAME)
+            .map_err(|e| anyhow!("Unable to load client backup: {}", e))?;
 
         let (encryptions, _proof, public_data, chain_code2, key_id): (
             Helgamalsegmented,
@@ -115,21 +165,21 @@ impl Wallet {
             Party2Public,
             BigInt,
             String,
-        ) = serde_json::from_str(&data).unwrap();
+        ) = serde_json::from_str(&data)?;
 
-        let sk = Msegmentation::decrypt(&encryptions, &g, &y_priv, &escrow::SEGMENT_SIZE);
+        let sk = Msegmentation::decrypt(&encryptions, &g, &y_priv, &escrow::SEGMENT_SIZE)
+            .map_err(|_| anyhow!("Unable to decrypt client backup"))?;
 
         let client_master_key_recovered =
-            MasterKey2::recover_master_key(sk.unwrap(), public_data, chain_code2);
-        let pos_old: u32 = requests::post(client_shim, &format!("ecdsa/{}/recover", key_id))
-            .unwrap()
-            .unwrap();
+            MasterKey2::recover_master_key(sk, public_data, chain_code2);
+        let pos_old: u32 = client_shim.recover(&key_id).await?;
 
         let pos_old = if pos_old < 10 { 10 } else { pos_old };
         //TODO: temporary, server will keep updated pos, to do so we need to send update to server for every get_new_address
 
         // let id = Uuid::new_v4().to_string();
         let addresses_derivation_map = HashMap::new(); //TODO: add a fucntion to recreate
+        let eth_addresses_derivation_map = HashMap::new();
 
         let new_wallet = Wallet {
             id: key_id.clone(),
@@ -141,12 +191,15 @@ impl Wallet {
             },
             last_derived_pos: pos_old,
             addresses_derivation_map,
+            eth_addresses_derivation_map,
+            eth_txs: Vec::new(),
+            eth_transfers: HashMap::new(),
         };
 
         new_wallet.save();
         println!("Recovery Completed Successfully ❤️");
 
-        new_wallet
+        Ok(new_wallet)
     }
 
     pub fn save_to(&self, filepath: &str) {
@@ -164,7 +217,22 @@ impl Wallet {
     pub fn load_from(filepath: &str) -> Wallet {
         let data = fs::read_to_string(filepath).expect("Unable to load wallet!");
 
-        let wallet: Wallet = serde_json::from_str(&data).unwrap();
+        let mut wallet: Wallet = serde_json::from_str(&data).unwrap();
+
+        // ETH addresses used to be kept, lowercased, in `addresses_derivation_map`
+        if wallet.coin_type == "eth" {
+            for (address, pos_mk) in wallet.addresses_derivation_map.drain() {
+                match EthAddress::from_str(&address) {
+                    Ok(address) => {
+                        wallet.eth_addresses_derivation_map.insert(address, pos_mk);
+                    }
+                    Err(e) => warn!(
+                        "(wallet id: {}) Dropped invalid ETH address {}: {}",
+                        wallet.id, address, e
+                    ),
+                }
+            }
+        }
 
         debug!("(wallet id: {}) Loaded wallet to memory", wallet.id);
 
@@ -181,85 +249,494 @@ impl Wallet {
         to_address: &str,
         amount: f64,
         client_shim: &ClientShim,
-    ) -> String {
+    ) -> Result<String> {
+        block_on(self.a_send(from_address, to_address, amount, client_shim))
+    }
+
+    pub async fn a_send(
+        &mut self,
+        from_address: &str,
+        to_address: &str,
+        amount: f64,
+        client_shim: &AsyncClientShim,
+    ) -> Result<String> {
         let coin_type = &self.coin_type;
         if coin_type == "btc" {
-            let raw_tx_opt = btc::raw_tx::create_raw_tx(
+            return self
+                .a_send_btc(to_address, btc_to_satoshi(amount)?, client_shim)
+                .await;
+        } else if coin_type == "eth" {
+            let network = self.eth_network()?;
+            let tx_record = a_sign_and_send(
+                from_address,
                 to_address,
                 amount,
+                &network,
                 client_shim,
-                self.last_derived_pos,
                 &self.private_share,
-                &self.addresses_derivation_map,
+                &self.eth_addresses_derivation_map,
+            )
+            .await?;
+            let tx_hash = tx_record.tx_hash;
+            self.eth_txs.push(tx_record);
+
+            println!(
+                "Sent {} ETH to address {}. Transaction State: {:?}",
+                amount,
+                EthAddress::from_str(to_address)?,
+                tx_hash
             );
+            return Ok(format!("{:?}", tx_hash));
+        }
+        Err(anyhow!("Unsupported coin type: {}", coin_type))
+    }
 
-            let raw_tx_opt = match raw_tx_opt {
-                Ok(tx) => tx,
-                Err(e) => {
-                    panic!("Unable to create raw transaction {}", e);
-                }
-            };
+    /// Pay `amount_satoshi` to `to_address` from this BTC wallet, returning the transaction hash.
+    async fn a_send_btc(
+        &mut self,
+        to_address: &str,
+        amount_satoshi: u64,
+        client_shim: &AsyncClientShim,
+    ) -> Result<String> {
+        let raw_tx = a_create_raw_tx_sat(
+            to_address,
+            amount_satoshi,
+            client_shim,
+            self.last_derived_pos,
+            &self.private_share,
+            &self.addresses_derivation_map,
+        )
+        .await?
+        .ok_or_else(|| anyhow!("Unable to create raw transaction"))?;
+
+        let change_address_payload = raw_tx.change_address_payload;
+
+        let _ = &self.addresses_derivation_map.insert(
+            change_address_payload.address,
+            MKPosDto {
+                mk: change_address_payload.mk,
+                pos: change_address_payload.pos,
+            },
+        );
+        self.last_derived_pos = &self.last_derived_pos + 1;
+
+        let tx_resp_str = a_push_raw_tx(raw_tx.raw_tx_hex).await?;
+        println!(
+            "Network: [{}], Sent {} BTC to address {}. Transaction State: {}",
+            &self.network,
+            format_btc_amount(amount_satoshi),
+            &to_address,
+            tx_resp_str
+        );
+        let tx_obj: Value = serde_json::from_str(&tx_resp_str)
+            .map_err(|e| anyhow!("Unable to parse tx response {}", e))?;
+        match &tx_obj["tx"]["hash"] {
+            Value::String(s) => Ok(s.to_owned()),
+            _ => Err(anyhow!("Unable to get tx hash")),
+        }
+    }
 
-            let raw_tx = raw_tx_opt.unwrap();
-            let change_address_payload = raw_tx.change_address_payload;
+    /// Send the native currency of `chain` from one of this ETH wallet's addresses. The
+    /// transaction is built and broadcast through the chain's node, the server only co-signs.
+    pub fn send_on_chain(
+        &mut self,
+        chain: &str,
+        from_address: &str,
+        to_address: &str,
+        amount: f64,
+        client_shim: &ClientShim,
+    ) -> Result<EthTxRecord> {
+        block_on(self.a_send_on_chain(chain, from_address, to_address, amount, client_shim))
+    }
 
-            let _ = &self.addresses_derivation_map.insert(
-                change_address_payload.address,
-                MKPosDto {
-                    mk: change_address_payload.mk,
-                    pos: change_address_payload.pos,
-                },
-            );
-            self.last_derived_pos = &self.last_derived_pos + 1;
+    pub async fn a_send_on_chain(
+        &mut self,
+        chain: &str,
+        from_address: &str,
+        to_address: &str,
+        amount: f64,
+        client_shim: &AsyncClientShim,
+    ) -> Result<EthTxRecord> {
+        let network = self.evm_network(Some(chain))?;
+        let tx_record = a_sign_and_send_on_chain(
+            from_address,
+            to_address,
+            network.from_native(amount)?,
+            vec![],
+            &network,
+            client_shim,
+            &self.private_share,
+            &self.eth_addresses_derivation_map,
+        )
+        .await?;
+        self.eth_txs.push(tx_record.clone());
+
+        println!(
+            "Network: [{}], Sent {} {} to address {}. Transaction: {:?}",
+            network.name,
+            amount,
+            network.currency_symbol,
+            EthAddress::from_str(to_address)?,
+            tx_record.tx_hash
+        );
+        Ok(tx_record)
+    }
 
-            let raw_tx_url = BLOCK_CYPHER_HOST.to_owned() + "/txs/push";
-            let raw_tx = BlockCypherRawTx {
-                tx: raw_tx.raw_tx_hex,
-            };
-            let tx_resp_str = reqwest::blocking::Client::new()
-                .post(raw_tx_url)
-                .json(&raw_tx)
-                .send()
-                .unwrap()
-                .text()
-                .unwrap();
+    /// Pay `amount` on `chain` (this wallet's network by default) from whichever derived
+    /// address can afford it. With `sweep`, funds of several addresses are first gathered
+    /// into one when none can pay alone. The plan is printed before anything is signed.
+    pub fn send_from_any(
+        &mut self,
+        chain: Option<&str>,
+        to_address: &str,
+        amount: f64,
+        sweep: bool,
+        client_shim: &ClientShim,
+    ) -> Result<Vec<EthTxRecord>> {
+        block_on(self.a_send_from_any(chain, to_address, amount, sweep, client_shim))
+    }
+
+    pub async fn a_send_from_any(
+        &mut self,
+        chain: Option<&str>,
+        to_address: &str,
+        amount: f64,
+        sweep: bool,
+        client_shim: &AsyncClientShim,
+    ) -> Result<Vec<EthTxRecord>> {
+        let network = self.evm_network(chain)?;
+        let to = EthAddress::from_str(to_address)?;
+        let value = network.from_native(amount)?;
+        self.a_send_value_from_any(&network, to, value, sweep, client_shim)
+            .await
+    }
+
+    async fn a_send_value_from_any(
+        &mut self,
+        network: &EthNetwork,
+        to: EthAddress,
+        value: U256,
+        sweep: bool,
+        client_shim: &AsyncClientShim,
+    ) -> Result<Vec<EthTxRecord>> {
+        let plan =
+            a_plan_eth_funding(network, &self.eth_addresses(), to.into(), value, sweep).await?;
+
+        for step in plan.sweeps.iter().chain(std::iter::once(&plan.payment)) {
             println!(
-                "Network: [{}], Sent {} BTC to address {}. Transaction State: {}",
-                &self.network, amount, &to_address, tx_resp_str
+                "Network: [{}], Plan: {} {} from {} to {} (max fee: {} {})",
+                network.name,
+                network.to_native(step.value),
+                network.currency_symbol,
+                EthAddress::from(step.from),
+                EthAddress::from(step.to),
+                network.to_native(step.max_fee),
+                network.currency_symbol
             );
-            let tx_obj: Value = match serde_json::from_str(&tx_resp_str) {
-                Ok(tx) => tx,
-                Err(e) => {
-                    println!("Unable to parse tx response {}", e);
-                    return "".to_owned();
-                }
+        }
+
+        let records = a_execute_funding_plan(
+            &plan,
+            network,
+            client_shim,
+            &self.private_share,
+            &self.eth_addresses_derivation_map,
+        )
+        .await?;
+        self.eth_txs.extend(records.iter().cloned());
+
+        println!(
+            "Network: [{}], Sent {} {} to address {}. Transaction: {:?}",
+            network.name,
+            network.to_native(value),
+            network.currency_symbol,
+            to,
+            records.last().map(|r| r.tx_hash)
+        );
+        Ok(records)
+    }
+
+    /// Pay a `bitcoin:` or `ethereum:` URI, which must carry an amount. ETH payments go out
+    /// on `chain` (this wallet's network by default), from `from_address` or, for native
+    /// transfers, from whichever address can pay. ERC-20 transfers need `from_address`.
+    pub fn pay_uri(
+        &mut self,
+        uri: &str,
+        from_address: Option<&str>,
+        chain: Option<&str>,
+        sweep: bool,
+        client_shim: &ClientShim,
+    ) -> Result<()> {
+        block_on(self.a_pay_uri(uri, from_address, chain, sweep, client_shim))
+    }
+
+    pub async fn a_pay_uri(
+        &mut self,
+        uri: &str,
+        from_address: Option<&str>,
+        chain: Option<&str>,
+        sweep: bool,
+        client_shim: &AsyncClientShim,
+    ) -> Result<()> {
+        if self.coin_type == "btc" {
+            let payment_uri = BtcPaymentUri::parse(uri, get_bitcoin_network(&self.network)?)?;
+            let amount = payment_uri
+                .amount
+                .ok_or_else(|| anyhow!("the payment URI has no amount"))?;
+            self.a_send_btc(&payment_uri.address, amount, client_shim)
+                .await?;
+            return Ok(());
+        }
+
+        let network = self.evm_network(chain)?;
+        let payment_uri = EthPaymentUri::parse(uri, &network)?;
+        let value = payment_uri
+            .value
+            .ok_or_else(|| anyhow!("the payment URI has no amount"))?;
+
+        match (payment_uri.token, from_address) {
+            (None, None) => {
+                self.a_send_value_from_any(
+                    &network,
+                    payment_uri.to.into(),
+                    value,
+                    sweep,
+                    client_shim,
+                )
+                .await?;
+            }
+            (None, Some(from_address)) => {
+                let tx_record = a_sign_and_send_on_chain(
+                    from_address,
+                    &EthAddress::from(payment_uri.to).to_string(),
+                    value,
+                    vec![],
+                    &network,
+                    client_shim,
+                    &self.private_share,
+                    &self.eth_addresses_derivation_map,
+                )
+                .await?;
+                println!(
+                    "Network: [{}], Sent {} {} to address {}. Transaction: {:?}",
+                    network.name,
+                    network.to_native(value),
+                    network.currency_symbol,
+                    EthAddress::from(payment_uri.to),
+                    tx_record.tx_hash
+                );
+                self.eth_txs.push(tx_record);
+            }
+            (Some(token), Some(from_address)) => {
+                let tx_record = a_sign_and_send_on_chain(
+                    from_address,
+                    &EthAddress::from(token).to_string(),
+                    U256::zero(),
+                    payment_uri.calldata(),
+                    &network,
+                    client_shim,
+                    &self.private_share,
+                    &self.eth_addresses_derivation_map,
+                )
+                .await?;
+                println!(
+                    "Network: [{}], Sent {} units of token {} to address {}. Transaction: {:?}",
+                    network.name,
+                    value,
+                    EthAddress::from(token),
+                    EthAddress::from(payment_uri.to),
+                    tx_record.tx_hash
+                );
+                self.eth_txs.push(tx_record);
+            }
+            (Some(_), None) => {
+                return Err(anyhow!("an ERC-20 payment needs a sender address"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Derive a new address and print a payment URI requesting `amount` (in BTC, or in the
+    /// native currency of `chain` for ETH wallets) to it.
+    pub fn request_payment(
+        &mut self,
+        amount: Option<&str>,
+        label: Option<&str>,
+        chain: Option<&str>,
+    ) -> Result<String> {
+        let address = self.get_crypto_address();
+        let label = label.map(|l| l.to_owned());
+
+        let uri = if self.coin_type == "btc" {
+            let amount = match amount {
+                Some(amount) => Some(parse_btc_amount(amount)?),
+                None => None,
             };
-            let tx_hash = match &tx_obj["tx"]["hash"] {
-                Value::String(s) => s,
-                _ => {
-                    println!("Unable to get tx hash");
-                    return "".to_owned();
-                }
+            BtcPaymentUri::new(&address, amount, label).to_string()
+        } else {
+            let network = self.evm_network(chain)?;
+            let amount = match amount {
+                Some(amount) => Some(parse_units(amount, network.decimals as u32)?),
+                None => None,
             };
-            return tx_hash.to_owned();
-        } else if coin_type == "eth" {
-            let tx_hash = send_eth(
+            EthPaymentUri::native(
+                EthAddress::from_str(&address)?.into(),
+                network.chain_id,
                 amount,
-                client_shim,
-                from_address,
-                to_address,
-                &self.private_share,
-                &self.addresses_derivation_map,
             )
-            .unwrap();
+            .with_label(label)
+            .to_string()
+        };
 
-            println!(
-                "Sent {} ETH to address {}. Transaction State: {:?}",
-                amount, &to_address, tx_hash
-            );
-            return tx_hash.to_string();
+        println!("Payment request: {}", uri);
+        Ok(uri)
+    }
+
+    /// Block until an ETH transaction of `chain` (this wallet's network by default) has
+    /// `confirmations` blocks.
+    pub fn wait_for_eth_tx(
+        &self,
+        tx_hash: &str,
+        confirmations: u64,
+        chain: Option<&str>,
+    ) -> Result<EthTxReceiptReport> {
+        block_on(self.a_wait_for_eth_tx(tx_hash, confirmations, chain))
+    }
+
+    pub async fn a_wait_for_eth_tx(
+        &self,
+        tx_hash: &str,
+        confirmations: u64,
+        chain: Option<&str>,
+    ) -> Result<EthTxReceiptReport> {
+        let network = self.evm_network(chain)?;
+        let tx_hash = H256::from_str(tx_hash)?;
+        let report = a_wait_for_eth_tx_confirmations(
+            &network,
+            tx_hash,
+            confirmations,
+            DEFAULT_CONFIRMATION_TIMEOUT,
+        )
+        .await?;
+
+        println!(
+            "Network: [{}], Transaction {:?}: {:?} (block: {:?}, confirmations: {}, gas used: {:?}, effective gas price: {:?})",
+            network.name,
+            report.tx_hash,
+            report.status,
+            report.block_number,
+            report.confirmations,
+            report.gas_used,
+            report.effective_gas_price
+        );
+        Ok(report)
+    }
+
+    /// Speed up or cancel a pending ETH transaction. The original is looked up in the tracked
+    /// transactions first, then on the node of this wallet's network.
+    pub fn replace_eth_tx(
+        &mut self,
+        tx_hash: &str,
+        replacement: EthTxReplacement,
+        fees: &EthReplacementFees,
+        client_shim: &ClientShim,
+    ) -> Result<EthTxRecord> {
+        block_on(self.a_replace_eth_tx(tx_hash, replacement, fees, client_shim))
+    }
+
+    pub async fn a_replace_eth_tx(
+        &mut self,
+        tx_hash: &str,
+        replacement: EthTxReplacement,
+        fees: &EthReplacementFees,
+        client_shim: &AsyncClientShim,
+    ) -> Result<EthTxRecord> {
+        let tx_hash = H256::from_str(tx_hash)?;
+        let (original, network) = match self.eth_txs.iter().find(|tx| tx.tx_hash == tx_hash) {
+            Some(tx) => (tx.clone(), get_eth_network_by_chain_id(tx.chain_id)?),
+            None => {
+                let network = self.eth_network()?;
+                (a_fetch_tx_record(&network, tx_hash).await?, network)
+            }
+        };
+
+        let record = a_replace_tx(
+            &original,
+            replacement,
+            fees,
+            &network,
+            client_shim,
+            &self.private_share,
+            &self.eth_addresses_derivation_map,
+        )
+        .await?;
+
+        match self.eth_txs.iter_mut().find(|tx| tx.tx_hash == tx_hash) {
+            Some(tx) => tx.replaced_by = Some(record.tx_hash),
+            None => self.eth_txs.push(EthTxRecord {
+                replaced_by: Some(record.tx_hash),
+                ..original
+            }),
         }
-        "".to_owned()
+        self.eth_txs.push(record.clone());
+
+        println!(
+            "Network: [{}], {:?} of transaction {:?}: {:?} (nonce: {}, max fee per gas: {}, max priority fee per gas: {})",
+            network.name,
+            replacement,
+            tx_hash,
+            record.tx_hash,
+            record.nonce,
+            record.gas_price,
+            record.max_priority_fee_per_gas
+        );
+        Ok(record)
+    }
+
+    /// Scan `chain` (this wallet's network by default) for native and ERC-20 transfers
+    /// touching this wallet's addresses since the last sync.
+    pub fn sync_eth_transfers(&mut self, chain: Option<&str>) -> Result<Vec<EthTransfer>> {
+        block_on(self.a_sync_eth_transfers(chain))
+    }
+
+    pub async fn a_sync_eth_transfers(&mut self, chain: Option<&str>) -> Result<Vec<EthTransfer>> {
+        let network = self.evm_network(chain)?;
+        let addresses = self.eth_addresses();
+        let history = self.eth_transfers.entry(network.chain_id).or_default();
+        let transfers = a_sync_eth_transfers(&network, &addresses, history).await?;
+
+        for transfer in &transfers {
+            print_eth_transfer(&network, transfer);
+        }
+        println!(
+            "Network: [{}], {} new transfers, synced up to block {:?}",
+            network.name,
+            transfers.len(),
+            history.synced_block
+        );
+        Ok(transfers)
+    }
+
+    /// Print transfers of `chain` as new blocks arrive, until the WebSocket connection ends.
+    pub fn watch_eth_transfers(&mut self, chain: Option<&str>) -> Result<()> {
+        block_on(self.a_watch_eth_transfers(chain))
+    }
+
+    pub async fn a_watch_eth_transfers(&mut self, chain: Option<&str>) -> Result<()> {
+        let network = self.evm_network(chain)?;
+        let addresses = self.eth_addresses();
+        let history = self.eth_transfers.entry(network.chain_id).or_default();
+        a_watch_eth_transfers(&network, &addresses, history, |transfer| {
+            print_eth_transfer(&network, transfer)
+        })
+        .await
+    }
+
+    fn eth_addresses(&self) -> Vec<Address> {
+        self.eth_addresses_derivation_map
+            .keys()
+            .map(|a| (*a).into())
+            .collect()
     }
 
     pub fn get_crypto_address(&mut self) -> String {
@@ -274,12 +751,12 @@ impl Wallet {
             println!("BTC Network: [{}], Address: [{}]", &self.network, address);
             return address.to_string();
         } else if coin_type == "eth" {
-            let address = pubkey_to_eth_address(&mk);
-            self.addresses_derivation_map
-                .insert(format!("{:?}", address), MKPosDto { mk, pos });
+            let address = EthAddress::from(pubkey_to_eth_address(&mk));
+            self.eth_addresses_derivation_map
+                .insert(address, MKPosDto { mk, pos });
             self.last_derived_pos = pos;
 
-            println!("ETH address: {:?}", address);
+            println!("ETH address: {}", address);
             return address.to_string();
         }
         "".to_owned()
@@ -305,67 +782,107 @@ impl Wallet {
             for i in 0..self.last_derived_pos {
                 let (pos, mk) = derive_new_key(&self.private_share, i);
 
-                let address = pubkey_to_eth_address(&mk);
+                let address = EthAddress::from(pubkey_to_eth_address(&mk));
 
-                self.addresses_derivation_map
-                    .insert(format!("{:?}", address), MKPosDto { mk, pos });
+                self.eth_addresses_derivation_map
+                    .insert(address, MKPosDto { mk, pos });
             }
         }
         Ok(())
     }
 
-    pub fn get_balance(&mut self) -> usize {
+    /// ETH wallets keep the name of their network in `network`, see `eth::network`.
+    pub fn eth_network(&self) -> Result<EthNetwork> {
+        get_eth_network(&self.network)
+    }
+
+    /// The same ETH wallet can be used on any EVM chain, `chain` overriding `network`.
+    pub fn evm_network(&self, chain: Option<&str>) -> Result<EthNetwork> {
+        match chain {
+            Some(chain) => get_eth_network(chain),
+            None => self.eth_network(),
+        }
+    }
+
+    pub fn get_evm_balance(&self, chain: Option<&str>) -> Result<f64> {
+        block_on(self.a_get_evm_balance(chain))
+    }
+
+    pub async fn a_get_evm_balance(&self, chain: Option<&str>) -> Result<f64> {
+        let network = self.evm_network(chain)?;
+        let total = a_get_eth_balance(&network, self.last_derived_pos, &self.private_share).await?;
+        println!(
+            "Network: [{}], {} Balance: [{}]",
+            network.name, network.currency_symbol, total
+        );
+        Ok(total)
+    }
+
+    pub fn get_balance(&mut self) -> Result<usize> {
+        block_on(self.a_get_balance())
+    }
+
+    pub async fn a_get_balance(&mut self) -> Result<usize> {
         let coin_type = &self.coin_type;
         if coin_type == "btc" {
             let mut total = 0;
-            for b in select_tx_in(self.last_derived_pos, &self.private_share).unwrap() {
+            for b in a_select_tx_in(self.last_derived_pos, &self.private_share).await? {
                 total += b.value;
             }
             println!(
                 "Network: [{}], Balance: [balance: {}]",
                 &self.network, total
             );
-            return total;
+            return Ok(total);
         } else if coin_type == "eth" {
-            let total: f64 = get_eth_balance(self.last_derived_pos, &self.private_share).unwrap();
-            println!("ETH Balance: [{}]", total);
-            return (total * 1000.0) as usize; // multiply 1000 to get value that is greater than 0
+            let total: f64 = self.a_get_evm_balance(None).await?;
+            return Ok((total * 1000.0) as usize); // multiply 1000 to get value that is greater than 0
         }
-        0
+        Ok(0)
     }
 }
 
-#[tokio::main]
-async fn get_eth_balance(last_derived_pos: u32, private_share: &PrivateShare) -> Result<f64> {
-    let balance_l =
-        eth::utils::get_all_addresses_balance(RINKEBY_TEST_API, last_derived_pos, private_share)
-            .await?;
+async fn a_get_eth_balance(
+    network: &EthNetwork,
+    last_derived_pos: u32,
+    private_share: &PrivateShare,
+) -> Result<f64> {
+    let balance_l = eth::utils::get_all_addresses_balance_in_wei(
+        network.rpc_url()?,
+        last_derived_pos,
+        private_share,
+    )
+    .await?;
 
-    let mut total = 0.0;
+    let mut total = U256::zero();
     for b in balance_l {
         total += b
     }
 
-    Ok(total)
+    Ok(network.to_native(total))
 }
 
-fn send_eth(
-    eth_value: f64,
-    client_shim: &ClientShim,
-    from: &str,
-    to: &str,
-    private_share: &PrivateShare,
-    addresses_derivation_map: &HashMap<String, MKPosDto>,
-) -> Result<H256> {
-    let result = sign_and_send(
-        from,
-        to,
-        eth_value,
-        client_shim,
-        private_share,
-        addresses_derivation_map,
-    )?;
-    Ok(result)
+fn print_eth_transfer(network: &EthNetwork, transfer: &EthTransfer) {
+    let amount = match (transfer.kind, transfer.token) {
+        (EthTransferKind::Erc20, Some(token)) => {
+            format!("{} of token {}", transfer.value, EthAddress::from(token))
+        }
+        _ => format!(
+            "{} {}",
+            network.to_native(transfer.value),
+            network.currency_symbol
+        ),
+    };
+    println!(
+        "Network: [{}], {} from {} to {} (tx: {:?}, block: {}, confirmations: {})",
+        network.name,
+        amount,
+        EthAddress::from(transfer.from),
+        EthAddress::from(transfer.to),
+        transfer.tx_hash,
+        transfer.block_number,
+        transfer.confirmations
+    );
 }
 
 #[cfg(test)]